@@ -9,6 +9,11 @@ import {
   window,
   WorkspaceConfiguration,
   commands,
+  Uri,
+  Range,
+  Selection,
+  ThemeColor,
+  EventEmitter,
 } from "vscode";
 const path = require("path");
 const fs = require("fs");
@@ -68,11 +73,25 @@ export async function activate(context: ExtensionContext) {
     debug: run,
   };
   let clientOptions: LanguageClientOptions = {
+    initializationOptions: {
+      // the server only reads files within the workspace folders
+      restricted: !workspace.isTrusted,
+      // the inactive branches of `#ifdef` are dimmed, see `sqfAnalyzer/inactiveRegions`
+      inactive_regions: true,
+    },
     // Register the server for plain text documents
-    documentSelector: [{ scheme: "file", language: "sqf" }],
+    documentSelector: [
+      { scheme: "file", language: "sqf" },
+      // config documents (CfgFunctions, dialogs, mission.sqm)
+      { scheme: "file", pattern: "**/{config.cpp,description.ext,mission.sqm,*.hpp}" },
+      // headers of scripts
+      { scheme: "file", pattern: "**/*.{inc,h}" },
+      // state machines, whose states hold SQF
+      { scheme: "file", pattern: "**/*.fsm" },
+    ],
     synchronize: {
       // Notify the server about file changes to '.clientrc files contained in the workspace
-      fileEvents: workspace.createFileSystemWatcher("**/.clientrc"),
+      fileEvents: workspace.createFileSystemWatcher("**/{.clientrc,.sqf-analyzer-baseline.json}"),
       configurationSection: "sqf-analyzer"
     }
   };
@@ -86,6 +105,133 @@ export async function activate(context: ExtensionContext) {
 
   context.subscriptions.push(commands.registerCommand('sqf-analyzer.restart', commandHandler));
 
+  // results of the game, shown as `sqf-analyzer:/game/{id}.sqf`
+  const gameResults = new Map<string, string>();
+  const showGameResult = async (command: string, argument: string) => {
+    try {
+      const result = (await commands.executeCommand<string>(command, argument)) ?? "";
+      const id = `${gameResults.size + 1}`;
+      gameResults.set(id, `/*\n${argument}\n*/\n${result}\n`);
+      const document = await workspace.openTextDocument(Uri.parse(`sqf-analyzer:/game/${id}.sqf`));
+      await window.showTextDocument(document, { preview: true, preserveFocus: true });
+    } catch (error: any) {
+      window.showErrorMessage(error?.message ?? `${error}`);
+    }
+  };
+  context.subscriptions.push(commands.registerCommand('sqf-analyzer.evaluateSelection', async () => {
+    const editor = window.activeTextEditor;
+    const code = editor?.document.getText(editor.selection);
+    if (code) {
+      await showGameResult("sqf-analyzer.evaluateInGame", code);
+    }
+  }));
+  context.subscriptions.push(commands.registerCommand('sqf-analyzer.getVariableValue', async () => {
+    const editor = window.activeTextEditor;
+    const range = editor?.document.getWordRangeAtPosition(editor.selection.active);
+    if (editor && range) {
+      await showGameResult("sqf-analyzer.getVariable", editor.document.getText(range));
+    }
+  }));
+
+  // the declaration, writes and reads of the variable at the cursor, highlighted and listed
+  const writeDecoration = window.createTextEditorDecorationType({
+    backgroundColor: new ThemeColor("editor.wordHighlightStrongBackground"),
+  });
+  const readDecoration = window.createTextEditorDecorationType({
+    backgroundColor: new ThemeColor("editor.wordHighlightBackground"),
+  });
+  context.subscriptions.push(writeDecoration, readDecoration);
+  context.subscriptions.push(commands.registerCommand('sqf-analyzer.showDataFlow', async () => {
+    const editor = window.activeTextEditor;
+    if (!editor) {
+      return;
+    }
+    type LspRange = { start: { line: number, character: number }, end: { line: number, character: number } };
+    const flow = await commands.executeCommand<{ name: string, declaration: LspRange, writes: LspRange[], reads: LspRange[] }>(
+      "sqf-analyzer.dataFlow",
+      editor.document.uri.toString(),
+      { line: editor.selection.active.line, character: editor.selection.active.character },
+    );
+    if (!flow) {
+      window.showInformationMessage("No local variable at the cursor");
+      return;
+    }
+    const toRange = (x: LspRange) => new Range(x.start.line, x.start.character, x.end.line, x.end.character);
+    const declaration = toRange(flow.declaration);
+    const writes = flow.writes.map(toRange);
+    const reads = flow.reads.map(toRange);
+    editor.setDecorations(writeDecoration, [declaration, ...writes]);
+    editor.setDecorations(readDecoration, reads);
+
+    const item = (group: string, range: Range) => ({
+      label: `${group}: line ${range.start.line + 1}`,
+      description: editor.document.lineAt(range.start.line).text.trim(),
+      range,
+    });
+    const picked = await window.showQuickPick([
+      item("declaration", declaration),
+      ...writes.map((x) => item("write", x)),
+      ...reads.map((x) => item("read", x)),
+    ], { title: `Data flow of ${flow.name}` });
+    if (picked) {
+      editor.selection = new Selection(picked.range.start, picked.range.end);
+      editor.revealRange(picked.range);
+    }
+    editor.setDecorations(writeDecoration, []);
+    editor.setDecorations(readDecoration, []);
+  }));
+
+  // the diagnostics of the project grouped by rule, shown as `sqf-analyzer:/problems.md`
+  const problems = new EventEmitter<Uri>();
+  context.subscriptions.push(problems);
+  context.subscriptions.push(commands.registerCommand('sqf-analyzer.showProblemsByRule', async () => {
+    const uri = Uri.parse("sqf-analyzer:/problems.md");
+    // computed again each time it is shown
+    problems.fire(uri);
+    await commands.executeCommand("markdown.showPreview", uri);
+  }));
+
+  // serves virtual documents such as `sqf-analyzer:/rules/unused-variable.md` (linked from diagnostics)
+  const provider = {
+    onDidChange: problems.event,
+    provideTextDocumentContent: async (uri: Uri): Promise<string> => {
+      const rule = uri.path.match(/^\/rules\/(.+)\.md$/);
+      if (rule) {
+        return (await commands.executeCommand<string>("sqf-analyzer.explain", rule[1])) ?? "";
+      }
+      if (uri.path === "/problems.md") {
+        return (await commands.executeCommand<string>("sqf-analyzer.problemsByRule")) ?? "";
+      }
+      const game = uri.path.match(/^\/game\/(\d+)\.sqf$/);
+      if (game) {
+        return gameResults.get(game[1]) ?? "";
+      }
+      return "";
+    }
+  };
+  context.subscriptions.push(workspace.registerTextDocumentContentProvider("sqf-analyzer", provider));
+
+  // the regions not compiled (inactive branches of `#ifdef`) of each document, dimmed
+  const inactiveDecoration = window.createTextEditorDecorationType({
+    opacity: "0.5",
+  });
+  context.subscriptions.push(inactiveDecoration);
+  const inactiveRegions = new Map<string, Range[]>();
+  const dimInactiveRegions = () => {
+    for (const editor of window.visibleTextEditors) {
+      editor.setDecorations(inactiveDecoration, inactiveRegions.get(editor.document.uri.toString()) ?? []);
+    }
+  };
+  context.subscriptions.push(window.onDidChangeVisibleTextEditors(dimInactiveRegions));
+  context.subscriptions.push(workspace.onDidCloseTextDocument((document) => {
+    inactiveRegions.delete(document.uri.toString());
+  }));
+  client.onNotification("sqfAnalyzer/inactiveRegions", (params: { uri: string, regions: { start: { line: number, character: number }, end: { line: number, character: number } }[] }) => {
+    const regions = params.regions.map((x) => new Range(x.start.line, x.start.character, x.end.line, x.end.character));
+    inactiveRegions.set(client.protocol2CodeConverter.asUri(params.uri).toString(), regions);
+    dimInactiveRegions();
+  });
+
   client.start();
 }
 
@@ -1,37 +1,139 @@
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
 use rayon::prelude::*;
-use sqf::analyzer::{Configuration, Origin, Output, State};
+use sqf::analyzer::{Configuration, Origin, Output};
 use sqf::cpp::analyze_file;
 use sqf::error::Error;
-use sqf::span::Spanned;
+use sqf::span::{Span, Spanned};
 use sqf::types::Type;
 use sqf::{self, UncasedStr, MISSION_INIT_SCRIPTS};
 use sqf::{get_path, preprocessor};
-use tower_lsp::lsp_types::{CompletionItem, Url};
+use tower_lsp::lsp_types::Url;
 
-use crate::analyze::compute;
-use crate::semantic_token::SemanticTokenLocation;
+use crate::analyze::{compute_isolated, Analysis, Stage, StagedError};
+use crate::config::{self, functions::Declaration};
+use crate::{fsm, gitignore, hemtt, ifdef, include};
 
-type Functions = HashMap<Arc<UncasedStr>, Spanned<String>>;
+pub type Functions = HashMap<Arc<UncasedStr>, Spanned<String>>;
+
+/// A global variable of the mission namespace, and its origin
+pub type Global = (Arc<UncasedStr>, (Origin, Option<Output>));
+
+/// The locations on disk of addon prefixes (e.g. `\x\cba\addons`)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Prefixes {
+    pub global: HashMap<Arc<str>, PathBuf>,
+    /// the prefixes of each workspace folder, which take precedence over the global ones for the
+    /// files within it
+    pub folders: Vec<(PathBuf, HashMap<Arc<str>, PathBuf>)>,
+}
+
+fn prefixes(
+    value: &serde_json::Map<String, serde_json::Value>,
+    root: Option<&Path>,
+) -> HashMap<Arc<str>, PathBuf> {
+    value
+        .iter()
+        .filter_map(|(key, value)| {
+            let value = PathBuf::from(value.as_str()?);
+            // paths of a folder are relative to it
+            let value = match root {
+                Some(root) if value.is_relative() => root.join(value),
+                _ => value,
+            };
+            Some((key.as_str().into(), value))
+        })
+        .collect()
+}
+
+impl Prefixes {
+    /// Reads the `addons` setting, a map of prefix to path, where a key may instead be a workspace
+    /// folder (URI or path) whose value is its own map of prefix to path
+    pub fn from_setting(value: &serde_json::Map<String, serde_json::Value>) -> Self {
+        let folders = value
+            .iter()
+            .filter_map(|(key, value)| {
+                let value = value.as_object()?;
+                let folder = Url::parse(key)
+                    .ok()
+                    .and_then(|x| x.to_file_path().ok())
+                    .unwrap_or_else(|| PathBuf::from(key));
+                Some((folder.clone(), prefixes(value, Some(&folder))))
+            })
+            .collect();
+        Self {
+            global: prefixes(value, None),
+            folders,
+        }
+    }
+
+    /// Returns the prefixes applicable to the file at `path`
+    pub fn get(&self, path: &Path) -> HashMap<Arc<str>, PathBuf> {
+        let mut prefixes = self.global.clone();
+        if let Some((_, folder)) = self
+            .folders
+            .iter()
+            .filter(|(root, _)| path.starts_with(root))
+            .max_by_key(|(root, _)| root.components().count())
+        {
+            prefixes.extend(folder.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        prefixes
+    }
+}
+
+/// The files the analysis may read and how the projects within them are found, from the settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scope {
+    /// the directories the analysis may read from, if restricted (e.g. the workspace folders of an
+    /// untrusted workspace). Includes are resolved by `sqf` and are thus only restricted via the
+    /// addon prefixes given to it.
+    pub allowed: Option<Vec<PathBuf>>,
+    /// the files, relative to the root of a mission, that identify it besides `description.ext`,
+    /// e.g. `mission.sqm` for missions whose `description.ext` is generated
+    pub mission_root_markers: Vec<String>,
+    /// whether scans skip the directories ignored by git
+    pub respect_gitignore: bool,
+}
+
+impl Default for Scope {
+    fn default() -> Self {
+        Self {
+            allowed: None,
+            mission_root_markers: vec![],
+            respect_gitignore: true,
+        }
+    }
+}
+
+impl Scope {
+    /// Whether the file or directory at `path` may be read by the analysis
+    pub fn is_allowed(&self, path: &Path) -> bool {
+        self.allowed
+            .as_ref()
+            .map(|roots| roots.iter().any(|root| path.starts_with(root)))
+            .unwrap_or(true)
+    }
+}
 
 /// tries to find the addon's config or mission description.ext of a given file
-pub fn identify(file_path: PathBuf) -> Option<(PathBuf, Functions)> {
-    if let Some((path, functions)) = identify_(file_path.clone(), "config.cpp") {
+pub fn identify(file_path: PathBuf, scope: &Scope) -> Option<(PathBuf, Functions)> {
+    if let Some((path, functions)) = identify_(file_path.clone(), "config.cpp", scope) {
         Some((path, functions))
-    } else if let Some((path, functions)) = identify_(file_path, "description.ext") {
+    } else if let Some((path, functions)) = identify_mission(file_path, scope) {
         Some((path, functions))
     } else {
         None
     }
 }
 
-fn identify_(mut addon_path: PathBuf, name: &str) -> Option<(PathBuf, Functions)> {
+fn identify_(mut addon_path: PathBuf, name: &str, scope: &Scope) -> Option<(PathBuf, Functions)> {
     while addon_path.components().count() > 3 && addon_path.pop() {
-        let configuration = preprocessor::Configuration::with_path(addon_path.join(name));
-        let Ok((functions, _)) = analyze_file(configuration) else {
+        let Some(functions) = functions(&addon_path.join(name), scope) else {
             continue;
         };
         return Some((addon_path.join(name), functions));
@@ -39,43 +141,405 @@ fn identify_(mut addon_path: PathBuf, name: &str) -> Option<(PathBuf, Functions)
     None
 }
 
+/// Tries to find the mission of a given file: the closest directory with a `description.ext` or
+/// with one of the mission root markers (see [`Scope::mission_root_markers`]). A mission identified by
+/// a marker is still keyed by the `description.ext` of its root, with the functions it declares if
+/// it exists.
+fn identify_mission(mut mission_path: PathBuf, scope: &Scope) -> Option<(PathBuf, Functions)> {
+    while mission_path.components().count() > 3 && mission_path.pop() {
+        let config_path = mission_path.join("description.ext");
+        if let Some(functions) = functions(&config_path, scope) {
+            return Some((config_path, functions));
+        }
+        let markers = &scope.mission_root_markers;
+        if scope.is_allowed(&mission_path) && markers.iter().any(|x| mission_path.join(x).is_file())
+        {
+            return Some((config_path, Default::default()));
+        }
+    }
+    None
+}
+
+lazy_static::lazy_static! {
+    /// The functions of each config and the modification times they were derived from.
+    /// Configs are preprocessed (with their includes) every time a file is identified, i.e. on
+    /// every change, which is expensive for configs including shared headers.
+    static ref FUNCTIONS: Mutex<HashMap<PathBuf, (Stamp, Functions)>> = Default::default();
+}
+
+/// The number of times the functions of a config were taken from [`FUNCTIONS`]
+static FUNCTIONS_HITS: AtomicUsize = AtomicUsize::new(0);
+/// The number of times the functions of a config were derived
+static FUNCTIONS_MISSES: AtomicUsize = AtomicUsize::new(0);
+
+/// The number of times the functions of a config were taken from the cache, and derived again
+pub fn functions_cache_statistics() -> (usize, usize) {
+    (
+        FUNCTIONS_HITS.load(Ordering::Relaxed),
+        FUNCTIONS_MISSES.load(Ordering::Relaxed),
+    )
+}
+
+/// A config and the files it includes (`None` when unresolved), with their modification times
+type Stamp = Vec<(Option<PathBuf>, Option<SystemTime>)>;
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|x| x.modified()).ok()
+}
+
+fn flatten(nodes: &[include::Node], stamp: &mut Stamp) {
+    for node in nodes {
+        stamp.push((node.path.clone(), node.path.as_deref().and_then(modified)));
+        flatten(&node.includes, stamp);
+    }
+}
+
+/// The modification times of a config and of all the files it includes, directly or not, resolved
+/// as when preprocessing it
+fn stamp(config_path: &Path, content: &str, scope: &Scope) -> Stamp {
+    let mut stamp = vec![(Some(config_path.to_path_buf()), modified(config_path))];
+    flatten(
+        &include::tree(content, config_path, &Default::default(), scope),
+        &mut stamp,
+    );
+    stamp
+}
+
+/// Returns the functions declared in the config at `config_path`, with the path of each function
+/// declared in the config itself derived from its metadata (the `file` of the function or of its
+/// category, and its `ext`).
+/// The result is cached until the config or the files it includes are modified.
+pub fn functions(config_path: &Path, scope: &Scope) -> Option<Functions> {
+    if !scope.is_allowed(config_path) || config::is_binarized(config_path) {
+        return None;
+    }
+    let content = std::fs::read_to_string(config_path).ok()?;
+    let stamp = stamp(config_path, &content, scope);
+    if let Some((cached_stamp, functions)) = FUNCTIONS.lock().unwrap().get(config_path) {
+        if *cached_stamp == stamp {
+            FUNCTIONS_HITS.fetch_add(1, Ordering::Relaxed);
+            return Some(functions.clone());
+        }
+    }
+
+    FUNCTIONS_MISSES.fetch_add(1, Ordering::Relaxed);
+    let functions = functions_(config_path, &content)?;
+    let mut cache = FUNCTIONS.lock().unwrap();
+    // the configs deleted (or renamed) since they were cached
+    cache.retain(|path, _| path.is_file());
+    cache.insert(config_path.to_path_buf(), (stamp, functions.clone()));
+    Some(functions)
+}
+
+fn functions_(config_path: &Path, content: &str) -> Option<Functions> {
+    let configuration = preprocessor::Configuration::with_path(config_path.to_path_buf());
+    let (mut functions, _) = analyze_file(configuration).ok()?;
+    for declaration in config::functions::declarations(&config::parse(content)) {
+        if let Some(path) = functions.get_mut(UncasedStr::new(&declaration.name)) {
+            *path = declaration.script();
+        }
+    }
+    Some(functions)
+}
+
+/// Returns the functions declared in `CfgFunctions` of the config at `config_path`.
+/// Functions declared in included files are not returned.
+pub fn declarations(config_path: &Path) -> Vec<Declaration> {
+    let Ok(content) = std::fs::read_to_string(config_path) else {
+        return vec![];
+    };
+    config::functions::declarations(&config::parse(&content))
+}
+
 /// searches for all addons and mission description.ext within a project
-pub fn find(url: &Url) -> Vec<(PathBuf, Functions)> {
+pub fn find(url: &Url, scope: &Scope) -> Vec<(PathBuf, Functions)> {
     let Ok(addon_path) = url.to_file_path() else {
         return vec![];
     };
 
-    let mut r = find_(addon_path.clone(), "config.cpp");
-    r.extend(find_(addon_path, "description.ext"));
+    let mut r = find_(addon_path.clone(), "config.cpp", scope);
+    let missions = find_(addon_path, "description.ext", scope);
+    // addons embedded in a mission, `mission/addons/*/config.cpp`
+    let embedded = missions
+        .iter()
+        .flat_map(|(mission_path, _)| embedded(mission_path, scope))
+        .collect::<Vec<_>>();
+    r.extend(missions);
+    for (path, functions) in embedded {
+        if !r.iter().any(|x| x.0 == path) {
+            r.push((path, functions))
+        }
+    }
     r
 }
 
-pub fn find_(addon_path: PathBuf, name: &str) -> Vec<(PathBuf, Functions)> {
-    let Some(first) = identify_(addon_path, name) else {
+/// Returns the addons in the `addons` folder of the mission whose `description.ext` is `mission_path`
+pub fn embedded(mission_path: &Path, scope: &Scope) -> Vec<(PathBuf, Functions)> {
+    let Some(directory) = mission_path.parent() else {
+        return vec![];
+    };
+    list_directories(directory.join("addons"), scope)
+        .into_iter()
+        .filter_map(|directory| {
+            let path = directory.join("config.cpp");
+            Some((path.clone(), functions(&path, scope)?))
+        })
+        .collect()
+}
+
+/// Returns whether the config at `addon_path` is an addon embedded in the mission at `mission_path`
+pub fn is_embedded(addon_path: &Path, mission_path: &Path) -> bool {
+    let is_mission = mission_path
+        .file_name()
+        .map(|x| x.eq_ignore_ascii_case("description.ext"))
+        .unwrap_or(false);
+    is_mission
+        && addon_path
+            .file_name()
+            .map(|x| x.eq_ignore_ascii_case("config.cpp"))
+            .unwrap_or(false)
+        && mission_path
+            .parent()
+            .map(|x| addon_path.starts_with(x.join("addons")))
+            .unwrap_or(false)
+}
+
+/// The functions of a config as globals of the mission namespace
+pub fn function_globals(
+    addon_path: &Path,
+    functions: &Functions,
+    addons: &HashMap<Arc<str>, PathBuf>,
+) -> Vec<Global> {
+    functions
+        .iter()
+        .filter_map(|(k, path)| {
+            let path = get_path(&path.inner, addon_path, addons).ok()?;
+            Some((
+                k.clone(),
+                (Origin(path, None), Some(Output::Type(Type::Code))),
+            ))
+        })
+        .collect()
+}
+
+pub fn find_(addon_path: PathBuf, name: &str, scope: &Scope) -> Vec<(PathBuf, Functions)> {
+    let identify = |path: PathBuf| {
+        if name == "description.ext" {
+            identify_mission(path, scope)
+        } else {
+            identify_(path, name, scope)
+        }
+    };
+    let Some(first) = identify(addon_path) else {
         return vec![];
     };
     let mut down1 = first.0.clone(); // addons/A/config.cpp
     down1.pop(); // addons/A/
     down1.pop(); // addons/
 
-    list_directories(&down1)
+    list_directories(&down1, scope)
         .into_iter()
         .filter_map(|mut directory| {
             directory.push(name);
-            identify_(directory, name)
+            identify(directory)
         })
         .collect()
 }
 
-fn list_directories(path: impl AsRef<Path>) -> Vec<PathBuf> {
+/// Resolves the path of the script of the function `name` declared in the config at `config_path`,
+/// using the same resolution as [`process`].
+/// Returns `None` if the function is not declared, and `Some(Err)` if its path cannot be derived.
+pub fn function_path(
+    config_path: &Path,
+    name: &str,
+    addons: &HashMap<Arc<str>, PathBuf>,
+    scope: &Scope,
+) -> Option<Result<Arc<Path>, Error>> {
+    let functions = functions(config_path, scope)?;
+    let declared = functions.get(UncasedStr::new(name))?;
+    Some(resolve(&declared.inner, config_path, addons))
+}
+
+/// [`get_path`], in its [`canonical`] form
+fn resolve(
+    path: &str,
+    addon_path: &Path,
+    addons: &HashMap<Arc<str>, PathBuf>,
+) -> Result<Arc<Path>, Error> {
+    get_path(path, addon_path, addons).map(|path| canonical(&path).into())
+}
+
+/// The canonical form of `path`, so that each file has a single key in the maps of the server:
+/// symlinks and (on Windows) `subst` drives are resolved, and the case is that of the file system.
+/// Paths that do not exist are returned as is.
+pub fn canonical(path: &Path) -> PathBuf {
+    let Ok(canonical) = std::fs::canonicalize(path) else {
+        return path.to_path_buf();
+    };
+    // on Windows, canonical paths are verbatim (`\\?\C:\`), which other paths are not
+    match canonical.to_str().and_then(|x| x.strip_prefix(r"\\?\")) {
+        Some(stripped) if !stripped.starts_with("UNC") => PathBuf::from(stripped),
+        _ => canonical,
+    }
+}
+
+/// Returns the script of each function whose path can be derived, as resolved by [`process`]
+pub fn function_files(
+    addon_path: &Path,
+    functions: &Functions,
+) -> HashMap<Arc<UncasedStr>, Arc<Path>> {
+    functions
+        .iter()
+        .filter_map(|(name, sqf_path)| {
+            let path = resolve(&sqf_path.inner, addon_path, &Default::default()).ok()?;
+            Some((name.clone(), path))
+        })
+        .collect()
+}
+
+/// Returns the errors of the declarations of `functions` whose script cannot be derived or opened
+pub fn declaration_errors(addon_path: &Path, functions: &Functions) -> Vec<Error> {
+    functions
+        .iter()
+        .filter_map(|(name, sqf_path)| {
+            match get_path(&sqf_path.inner, addon_path, &Default::default()) {
+                Ok(path) => {
+                    (!path.is_file()).then(|| unopenable(&name.to_string(), sqf_path.span, &path))
+                }
+                Err(_) => Some(underivable(&name.to_string(), sqf_path)),
+            }
+        })
+        .collect()
+}
+
+fn underivable(function_name: &str, original: &Spanned<String>) -> Error {
+    Error::new(
+        format!(
+            "The function \"{}\" is declared but could not derive a path for \"{}\"",
+            function_name, original.inner
+        ),
+        original.span,
+    )
+}
+
+fn unopenable(function_name: &str, span: Span, path: &Path) -> Error {
+    Error::new(
+        format!(
+            "The function \"{}\" is declared but could not open file \"{}\"",
+            function_name,
+            path.display()
+        ),
+        span,
+    )
+}
+
+/// Returns the binarized files next to the configs found: the `mission.sqm` of missions and the
+/// `config.bin` of addons without a `config.cpp`, which are skipped by the analysis
+pub fn binarized(configs: &[PathBuf], scope: &Scope) -> Vec<PathBuf> {
+    let mut binarized = vec![];
+    for config in configs {
+        let is_mission = config
+            .file_name()
+            .map(|x| x.eq_ignore_ascii_case("description.ext"))
+            .unwrap_or(false);
+        if is_mission {
+            binarized.push(config.with_file_name("mission.sqm"));
+        } else if let Some(addons) = config.parent().and_then(|x| x.parent()) {
+            // addons/A/config.cpp
+            binarized.extend(
+                list_directories(addons, scope)
+                    .into_iter()
+                    .filter(|x| !x.join("config.cpp").is_file())
+                    .map(|x| x.join("config.bin")),
+            );
+        }
+    }
+    binarized.sort();
+    binarized.dedup();
+    binarized.retain(|x| config::is_binarized(x));
+    binarized
+}
+
+/// Returns every `config.cpp` and `description.ext` in a directory tree
+pub fn configs_in(directory: &Path, scope: &Scope) -> Vec<PathBuf> {
+    configs_in_(directory, scope, &mut Default::default())
+}
+
+fn configs_in_(
+    directory: &Path,
+    scope: &Scope,
+    visited: &mut std::collections::HashSet<PathBuf>,
+) -> Vec<PathBuf> {
+    // symlinks may form cycles
+    if !visited.insert(canonical(directory)) {
+        return vec![];
+    }
+    let mut configs = ["config.cpp", "description.ext"]
+        .iter()
+        .map(|name| directory.join(name))
+        .filter(|path| path.is_file())
+        .collect::<Vec<_>>();
+    for directory in list_directories(directory, scope) {
+        let is_hidden = directory
+            .file_name()
+            .map(|x| x.to_string_lossy().starts_with('.'))
+            .unwrap_or(false);
+        if !is_hidden {
+            configs.extend(configs_in_(&directory, scope, visited));
+        }
+    }
+    configs
+}
+
+/// Returns every FSM in a directory tree
+pub fn fsms_in(directory: &Path, scope: &Scope) -> Vec<PathBuf> {
+    fsms_in_(directory, scope, &mut Default::default())
+}
+
+fn fsms_in_(
+    directory: &Path,
+    scope: &Scope,
+    visited: &mut std::collections::HashSet<PathBuf>,
+) -> Vec<PathBuf> {
+    // symlinks may form cycles
+    if !visited.insert(canonical(directory)) || !scope.is_allowed(directory) {
+        return vec![];
+    }
+    let mut fsms = std::fs::read_dir(directory)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| fsm::is_fsm(path) && path.is_file())
+        .collect::<Vec<_>>();
+    for directory in list_directories(directory, scope) {
+        let is_hidden = directory
+            .file_name()
+            .map(|x| x.to_string_lossy().starts_with('.'))
+            .unwrap_or(false);
+        if !is_hidden {
+            fsms.extend(fsms_in_(&directory, scope, visited));
+        }
+    }
+    fsms
+}
+
+fn list_directories(path: impl AsRef<Path>, scope: &Scope) -> Vec<PathBuf> {
+    if !scope.is_allowed(path.as_ref()) {
+        return vec![];
+    }
     let Ok(entries) = std::fs::read_dir(path) else {
         return vec![];
     };
     entries
         .flatten()
         .flat_map(|entry| {
-            let meta = entry.metadata().ok()?;
-            if meta.is_dir() {
+            // symlinked directories are followed
+            let meta = std::fs::metadata(entry.path()).ok()?;
+            // the build output of HEMTT is a copy of the project
+            let path = entry.path();
+            let is_ignored = scope.respect_gitignore && gitignore::is_ignored(&path);
+            if meta.is_dir() && !hemtt::is_output(&path) && !is_ignored {
                 Some(entry.path())
             } else {
                 None
@@ -84,55 +548,38 @@ fn list_directories(path: impl AsRef<Path>) -> Vec<PathBuf> {
         .collect()
 }
 
-type R = (
-    Option<String>,
-    Vec<Error>,
-    Option<(State, Vec<SemanticTokenLocation>, Vec<CompletionItem>)>,
-);
+type R = (Option<String>, Vec<StagedError>, Option<Analysis>);
 
-fn process_file(content: String, configuration: Configuration, functions: &Functions) -> R {
+fn process_file(
+    content: String,
+    configuration: Configuration,
+    functions: &Functions,
+    globals: &[Global],
+    ifdef: &ifdef::Settings,
+) -> R {
     let mut errors = vec![];
 
-    let mission = functions
-        .iter()
-        .filter_map(|(k, path)| {
-            let Ok(path) = get_path(&path.inner, &configuration.base_path, &configuration.addons)
-            else {
-                return None;
-            };
-            Some((
-                k.clone(),
-                (Origin(path, None), Some(Output::Type(Type::Code))),
-            ))
-        })
+    let mission = function_globals(&configuration.base_path, functions, &configuration.addons)
+        .into_iter()
+        .chain(globals.iter().cloned())
         .collect();
-    let (state, semantic_state, completion, new_errors) =
-        match compute(&content, configuration, mission) {
-            Ok(a) => a,
-            Err(e) => {
-                errors.push(e);
-                return (Some(content), errors, None);
-            }
-        };
+    let code = fsm::sqf(&configuration.file_path, &content).into_owned();
+    let (state, new_errors) = match compute_isolated(&code, configuration, mission, ifdef) {
+        Ok(a) => a,
+        Err(e) => {
+            errors.push(e);
+            return (Some(content), errors, None);
+        }
+    };
 
     errors.extend(new_errors);
 
-    (
-        Some(content),
-        errors,
-        Some((state, semantic_state, completion)),
-    )
+    (Some(content), errors, Some(Analysis::new(state)))
 }
 
-type R2 = HashMap<
-    Arc<Path>,
-    (
-        Option<Arc<UncasedStr>>,
-        (State, Vec<SemanticTokenLocation>, Vec<CompletionItem>),
-    ),
->;
+type R2 = HashMap<Arc<Path>, (Option<Arc<UncasedStr>>, Analysis)>;
 
-type R1 = (R2, HashMap<Arc<Path>, (String, Vec<Error>)>);
+type R1 = (R2, HashMap<Arc<Path>, (String, Vec<StagedError>)>);
 
 enum Either {
     Original(Spanned<String>),
@@ -143,9 +590,13 @@ pub fn process(
     addon_path: PathBuf,
     addons: HashMap<Arc<str>, PathBuf>,
     functions: &Functions,
+    globals: &[Global],
+    max_size: Option<usize>,
+    scope: &Scope,
+    ifdef: &ifdef::Settings,
 ) -> R1 {
     let f = functions.par_iter().map(|(function_name, sqf_path)| {
-        let path = get_path(&sqf_path.inner, &addon_path, &Default::default()).ok();
+        let path = resolve(&sqf_path.inner, &addon_path, &Default::default()).ok();
         (
             Some(Spanned::new(function_name.clone(), sqf_path.span)),
             path.map(Either::Path)
@@ -156,39 +607,77 @@ pub fn process(
     let defaults = MISSION_INIT_SCRIPTS.into_par_iter().map(|file| {
         let mut directory = addon_path.to_owned();
         directory.pop();
-        let path: Arc<Path> = directory.join(file).into();
+        let path: Arc<Path> = canonical(&directory.join(file)).into();
         (None::<Spanned<Arc<UncasedStr>>>, Either::Path(path))
     });
 
+    // FSMs executed with `execFSM`, which are not declared as functions
+    let declared = functions
+        .values()
+        .filter_map(|x| resolve(&x.inner, &addon_path, &Default::default()).ok())
+        .collect::<std::collections::HashSet<_>>();
+    let fsms = addon_path
+        .parent()
+        .map(|x| fsms_in(x, scope))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|x| Arc::<Path>::from(canonical(&x)))
+        .filter(|x| !declared.contains(x))
+        .map(|x| (None, Either::Path(x)))
+        .collect::<Vec<_>>();
+
     // iterator over all relevant files to analyze
-    let files = f.chain(defaults);
+    let files = f.chain(defaults).chain(fsms.into_par_iter());
 
     let results = files
         .filter_map(|(function_name, path)| {
             let (path, content) = match path {
                 Either::Original(original) => {
-                    let processed = (None, vec![Error::new(
-                        format!("The function \"{}\" is declared but could not derive a path for \"{}\"", function_name.as_ref().unwrap().inner, original.inner),
-                        original.span,
-                    )], None);
-                    return Some((addon_path.clone().into(), function_name.map(|x| x.inner), processed));
+                    let processed = (
+                        None,
+                        vec![(
+                            Stage::Analyzer,
+                            underivable(
+                                &function_name.as_ref().unwrap().inner.to_string(),
+                                &original,
+                            ),
+                        )],
+                        None,
+                    );
+                    return Some((
+                        addon_path.clone().into(),
+                        function_name.map(|x| x.inner),
+                        processed,
+                    ));
                 }
                 Either::Path(path) => {
+                    if !scope.is_allowed(&path) {
+                        return None;
+                    }
                     let Ok(content) = std::fs::read_to_string(path.as_ref()) else {
                         if let Some(ma) = &function_name {
-                            let processed = (None, vec![Error::new(
-                                format!("The function \"{}\" is declared but could not open file \"{}\"", ma.inner, path.display()),
-                                ma.span,
-                            )], None);
+                            let processed = (
+                                None,
+                                vec![(
+                                    Stage::Analyzer,
+                                    unopenable(&ma.inner.to_string(), ma.span, &path),
+                                )],
+                                None,
+                            );
 
-                            return Some((path, function_name.map(|x| x.inner), processed));
+                            // the error is on the declaration, i.e. on the config
+                            return Some((
+                                addon_path.clone().into(),
+                                function_name.map(|x| x.inner),
+                                processed,
+                            ));
                         } else {
                             // default files are optional, skip if not found
-                            return None
+                            return None;
                         };
                     };
                     (path, content)
-                },
+                }
             };
 
             let configuration = Configuration {
@@ -200,7 +689,12 @@ pub fn process(
             Some((
                 path,
                 function_name.map(|x| x.inner),
-                process_file(content, configuration, functions),
+                if max_size.is_some_and(|max| content.len() > max) {
+                    // too large to be analyzed
+                    (Some(content), vec![], None)
+                } else {
+                    process_file(content, configuration, functions, globals, ifdef)
+                },
             ))
         })
         .collect::<Vec<_>>();
@@ -211,10 +705,13 @@ pub fn process(
         if let Some(state) = state {
             states.insert(path.clone(), (name.clone(), state));
         }
-        if let Some(content) = content {
-            originals.insert(path, (content, errors));
-        } else if let Ok(content) = std::fs::read_to_string(&path) {
-            originals.insert(path, (content, errors));
+        // the config may have errors from multiple functions
+        if let Some(content) = content.or_else(|| std::fs::read_to_string(&path).ok()) {
+            originals
+                .entry(path)
+                .or_insert_with(|| (content, vec![]))
+                .1
+                .extend(errors);
         }
     }
 
@@ -1,42 +1,636 @@
+use std::any::Any;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
 use sqf::analyzer::{analyze, MissionNamespace, State};
-use sqf::error::Error;
+use sqf::error::{Error, ErrorType};
 use sqf::parser::parse;
-use sqf::preprocessor::AstIterator;
+use sqf::preprocessor::{Ast, AstIterator};
+use sqf::span::Span;
+use tokio::sync::Semaphore;
 use tower_lsp::lsp_types::CompletionItem;
 
-use crate::completion;
+use crate::completion::{self, Symbols};
 use crate::semantic_token::{semantic_tokens, SemanticTokenLocation};
+use crate::{addon, brackets, builtins, ifdef, locale};
+
+/// The stage of the analysis that produced an error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// the directives (`#define`, `#include`, ...)
+    Preprocessor,
+    /// the syntax, including the expansion of macros and includes
+    Parser,
+    /// the types and scopes
+    Analyzer,
+}
+
+impl Stage {
+    /// The source of the diagnostics of this stage
+    pub fn source(self) -> &'static str {
+        match self {
+            Stage::Preprocessor => "sqf-preprocessor",
+            Stage::Parser => "sqf-parser",
+            Stage::Analyzer => "sqf-analyzer",
+        }
+    }
+}
+
+/// An error and the stage that produced it
+pub type StagedError = (Stage, Error);
+
+type Return = (State, Vec<StagedError>);
+
+/// The analysis of a version of a document.
+/// Its semantic tokens and symbol table are only computed (and then cached) when first requested,
+/// since most analyzed documents are never displayed.
+#[derive(Debug, Default)]
+pub struct Analysis {
+    pub state: State,
+    semantic_tokens: OnceLock<Vec<SemanticTokenLocation>>,
+    symbols: OnceLock<Symbols>,
+}
+
+impl Analysis {
+    pub fn new(state: State) -> Self {
+        Self {
+            state,
+            ..Default::default()
+        }
+    }
+
+    /// The semantic tokens of `text`, the current text of the document
+    pub fn semantic_tokens(&self, text: &str) -> &[SemanticTokenLocation] {
+        self.semantic_tokens.get_or_init(|| {
+            preprocess(text, |ast| {
+                semantic_tokens(&ast, &self.state.namespace.mission)
+            })
+            .map(|(tokens, _)| tokens)
+            .unwrap_or_default()
+        })
+    }
+
+    fn symbols(&self) -> &Symbols {
+        self.symbols
+            .get_or_init(|| completion::symbols(&self.state.namespace))
+    }
 
-type Return = (
-    State,
-    Vec<SemanticTokenLocation>,
-    Vec<CompletionItem>,
-    Vec<Error>,
-);
+    /// The completion items of the variables in scope and of the commands starting with `prefix`
+    pub fn completion(&self, prefix: &str) -> impl Iterator<Item = &CompletionItem> {
+        self.symbols()
+            .starting_with(prefix)
+            .iter()
+            .chain(completion::COMMANDS.starting_with(prefix))
+    }
+
+    /// [`Analysis::completion`], ranked by relevance and limited to `limit` items (if any).
+    /// Returns whether items were left out, in which case the client should ask again as the prefix
+    /// grows.
+    pub fn ranked_completion(
+        &self,
+        prefix: &str,
+        limit: Option<usize>,
+        explanations: &locale::Explanations,
+    ) -> (Vec<CompletionItem>, bool) {
+        completion::rank(self.completion(prefix), prefix, limit, explanations)
+    }
+
+    /// [`Analysis::ranked_completion`] in UI event handlers, with the commands of displays and
+    /// controls first
+    pub fn ranked_ui_completion(
+        &self,
+        prefix: &str,
+        limit: Option<usize>,
+        explanations: &locale::Explanations,
+    ) -> (Vec<CompletionItem>, bool) {
+        let (ui, other): (Vec<_>, Vec<_>) = self
+            .completion(prefix)
+            .partition(|x| completion::is_ui_command(&x.label));
+        completion::rank(ui.into_iter().chain(other), prefix, limit, explanations)
+    }
+
+    /// The completion item of the variable or command `name`
+    pub fn symbol(
+        &self,
+        name: &str,
+        explanations: &locale::Explanations,
+    ) -> Option<CompletionItem> {
+        self.symbols()
+            .get(name)
+            .or_else(|| completion::COMMANDS.get(name))
+            .cloned()
+            .map(|x| completion::localize(x, explanations))
+    }
+
+    /// Discards the semantic tokens, for when the text changed but the state is kept
+    pub fn invalidate(&mut self) {
+        self.semantic_tokens = OnceLock::new();
+    }
+
+    /// Estimate of the memory used; the state is opaque and only its own size is counted
+    pub fn estimated_size(&self) -> usize {
+        std::mem::size_of::<State>()
+            + self.semantic_tokens.get().map(Vec::len).unwrap_or_default()
+                * std::mem::size_of::<SemanticTokenLocation>()
+            + self.symbols.get().map(Symbols::len).unwrap_or_default()
+                * std::mem::size_of::<CompletionItem>()
+    }
+}
 
 pub fn compute(
     text: &str,
     configuration: sqf::analyzer::Configuration,
     mission: MissionNamespace,
-) -> Result<Return, Error> {
-    let ast = sqf::preprocessor::parse(text)?;
-    let semantic_tokens = semantic_tokens(&ast, &mission);
+    ifdef: &ifdef::Settings,
+) -> Result<Return, StagedError> {
+    compute_(text, configuration, mission, ifdef, &mut Timings::default())
+}
+
+/// [`compute`], reporting a panic of the analysis as an error of the file instead of unwinding
+pub fn compute_isolated(
+    text: &str,
+    configuration: sqf::analyzer::Configuration,
+    mission: MissionNamespace,
+    ifdef: &ifdef::Settings,
+) -> Result<Return, StagedError> {
+    let path = configuration.file_path.clone();
+    catch_unwind(AssertUnwindSafe(|| {
+        compute(text, configuration, mission, ifdef)
+    }))
+    .unwrap_or_else(|payload| Err(panicked(&path, payload)))
+}
+
+/// The error of a file whose analysis panicked
+fn panicked(path: &Path, payload: Box<dyn Any + Send>) -> StagedError {
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|x| x.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown cause".to_string());
+    log::error!("the analysis of \"{}\" panicked: {message}", path.display());
+    let error = Error::new(
+        format!("The analysis of this file failed unexpectedly ({message}); please report it"),
+        (0, 0),
+    );
+    (Stage::Analyzer, error)
+}
+
+/// Moves the spans of the analysis of a text preceded by `by` chars of context (e.g. the directives
+/// of the script including a header) back to their positions in the text, dropping the ones of the
+/// context
+pub fn shift(state: Option<&mut State>, errors: &mut Vec<StagedError>, by: usize) {
+    let shift = |span: Span| (span.0 >= by).then(|| (span.0 - by, span.1 - by));
+    let shift_errors = |errors: &mut Vec<&mut Error>| {
+        for error in errors.iter_mut().filter(|x| x.origin.is_none()) {
+            error.span = shift(error.span).unwrap_or_default();
+        }
+    };
+    let is_context = |error: &Error| error.origin.is_none() && error.span.0 < by;
+    errors.retain(|x| !is_context(&x.1));
+    shift_errors(&mut errors.iter_mut().map(|x| &mut x.1).collect());
+
+    let Some(state) = state else {
+        return;
+    };
+    let file = state.configuration.file_path.clone();
+    state.errors.retain(|x| !is_context(x));
+    shift_errors(&mut state.errors.iter_mut().collect());
+    state.types = std::mem::take(&mut state.types)
+        .into_iter()
+        .filter_map(|(span, type_)| Some((shift(span)?, type_)))
+        .collect();
+    state.explanations = std::mem::take(&mut state.explanations)
+        .into_iter()
+        .filter_map(|(span, explanation)| Some((shift(span)?, explanation)))
+        .collect();
+    state.origins = std::mem::take(&mut state.origins)
+        .into_iter()
+        .filter_map(|(span, mut origin)| {
+            if origin.0 == file {
+                origin.1 = origin.1.and_then(shift);
+            }
+            Some((shift(span)?, origin))
+        })
+        .collect();
+    state.parameters = std::mem::take(&mut state.parameters)
+        .into_iter()
+        .filter_map(|(span, name)| Some((shift(span)?, name)))
+        .collect();
+    for stack in &mut state.namespace.stack {
+        for (span, _) in stack.variables.values_mut() {
+            *span = shift(*span).unwrap_or_default();
+        }
+    }
+    for (origin, _) in state.namespace.mission.values_mut() {
+        if origin.0 == file {
+            origin.1 = origin.1.and_then(shift);
+        }
+    }
+}
+
+/// The maximum number of preprocessing errors recovered from in a file
+const MAX_RECOVERIES: usize = 32;
+
+/// Blanks the lines of `text` spanned by `span` (in chars), and those continued from them with `\`,
+/// keeping the offsets of all chars. Returns `None` when there was nothing left to blank.
+fn blank_lines(text: &str, span: Span) -> Option<String> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut start = span.0.min(chars.len());
+    while start > 0 && chars[start - 1] != '\n' {
+        start -= 1;
+    }
+    let mut end = span.1.clamp(start, chars.len());
+    loop {
+        while end < chars.len() && chars[end] != '\n' {
+            end += 1;
+        }
+        let line = chars[start..end].iter().collect::<String>();
+        if end < chars.len() && line.trim_end().ends_with('\\') {
+            // a continued directive
+            end += 1;
+        } else {
+            break;
+        }
+    }
+    if chars[start..end].iter().all(|c| c.is_whitespace()) {
+        return None;
+    }
+    Some(
+        chars
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| {
+                if (start..end).contains(&i) && !matches!(c, '\n' | '\r') {
+                    ' '
+                } else {
+                    c
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Preprocesses `text` and calls `f` with its AST. On errors, the lines of the offending directive
+/// are blanked and the text is preprocessed again, so that a single bad directive does not prevent
+/// the analysis of the rest of the file.
+/// Returns the result of `f` and the errors recovered from, or the last error when it could not
+/// recover from it.
+pub fn preprocess<T>(
+    text: &str,
+    f: impl FnOnce(VecDeque<Ast<'_>>) -> T,
+) -> Result<(T, Vec<Error>), Error> {
+    preprocess_recovering(text, |ast, _| f(ast))
+}
+
+/// [`preprocess`], also passing to `f` the errors recovered from
+fn preprocess_recovering<T>(
+    text: &str,
+    f: impl FnOnce(VecDeque<Ast<'_>>, &[Error]) -> T,
+) -> Result<(T, Vec<Error>), Error> {
+    let mut recovered = Cow::Borrowed(text);
+    let mut errors = vec![];
+    loop {
+        let error = match sqf::preprocessor::parse(&recovered) {
+            Ok(ast) => return Ok((f(ast, &errors), errors)),
+            Err(error) => error,
+        };
+        if errors.len() == MAX_RECOVERIES {
+            return Err(error);
+        }
+        let Some(blanked) = blank_lines(&recovered, error.span) else {
+            return Err(error);
+        };
+        errors.push(error);
+        recovered = Cow::Owned(blanked);
+    }
+}
+
+fn compute_(
+    text: &str,
+    configuration: sqf::analyzer::Configuration,
+    mission: MissionNamespace,
+    ifdef: &ifdef::Settings,
+    timings: &mut Timings,
+) -> Result<Return, StagedError> {
+    compute_until(
+        text,
+        configuration,
+        mission,
+        ifdef,
+        timings,
+        None,
+        &Default::default(),
+    )
+    .expect("an analysis without deadline is never stopped")
+}
+
+/// Whether `deadline` passed
+fn is_expired(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|x| Instant::now() >= x)
+}
+
+/// [`compute_`], stopped at the end of the stage (preprocessing, parsing) during which `deadline`
+/// passed, if any. Returns `None` when stopped. The errors of each stage are added to `completed`
+/// as soon as it completes, so that they are known even when the analysis is abandoned.
+fn compute_until(
+    text: &str,
+    configuration: sqf::analyzer::Configuration,
+    mission: MissionNamespace,
+    ifdef: &ifdef::Settings,
+    timings: &mut Timings,
+    deadline: Option<Instant>,
+    completed: &Mutex<Vec<StagedError>>,
+) -> Option<Result<Return, StagedError>> {
+    let start = Instant::now();
+    // unbalanced brackets are reported by the server, see `brackets`
+    let text = brackets::repair(text, &brackets::unbalanced(text, ifdef));
+    let preprocessed = preprocess_recovering(&text, |ast, recovered| {
+        let (ast, _) = ifdef::evaluate(ast, ifdef);
+        timings.preprocess = start.elapsed();
+        completed
+            .lock()
+            .unwrap()
+            .extend(recovered.iter().map(|e| (Stage::Preprocessor, e.clone())));
+        compute_ast(ast, configuration, mission, timings, deadline, completed)
+    });
+    let (result, recovered) = match preprocessed {
+        Ok(x) => x,
+        Err(e) => return Some(Err((Stage::Preprocessor, e))),
+    };
+    let (state, errors) = result?;
+    let errors = recovered
+        .into_iter()
+        .map(|e| (Stage::Preprocessor, e))
+        .chain(errors)
+        .collect();
+    Some(Ok((state, errors)))
+}
 
+fn compute_ast(
+    ast: VecDeque<Ast<'_>>,
+    configuration: sqf::analyzer::Configuration,
+    mission: MissionNamespace,
+    timings: &mut Timings,
+    deadline: Option<Instant>,
+    completed: &Mutex<Vec<StagedError>>,
+) -> Option<Return> {
+    if is_expired(deadline) {
+        return None;
+    }
     let conf = sqf::preprocessor::Configuration {
         path: configuration.file_path.clone(),
         addons: configuration.addons.clone(),
         ..Default::default()
     };
 
+    // macros and includes are expanded while parsing
+    let start = Instant::now();
     let iter = AstIterator::new(ast, conf);
-    let (ast, mut errors) = parse(iter);
+    let (ast, errors) = parse(iter);
+    let mut errors = errors
+        .into_iter()
+        .map(|e| (Stage::Parser, e))
+        .collect::<Vec<_>>();
+    timings.parse = start.elapsed();
+    completed.lock().unwrap().extend(errors.iter().cloned());
+    if is_expired(deadline) {
+        return None;
+    }
+
+    let start = Instant::now();
     let mut state = State {
         configuration,
         ..Default::default()
     };
     state.namespace.mission = mission;
     analyze(&ast, &mut state);
-    errors.extend(state.errors.clone());
-    let complete = completion::completion(&state.namespace);
-    Ok((state, semantic_tokens, complete, errors))
+    // the built-in macros unknown to the preprocessor are left as is, but are defined in the game
+    errors.extend(
+        state
+            .errors
+            .iter()
+            .filter(|e| {
+                !matches!(&e.type_, ErrorType::UndefinedVariable(name) if builtins::get(name.as_str()).is_some())
+            })
+            .cloned()
+            .map(|e| (Stage::Analyzer, e)),
+    );
+    timings.analyze = start.elapsed();
+    Some((state, errors))
+}
+
+/// The syntax errors of `text`, i.e. those of preprocessing and parsing, without the (expensive)
+/// analysis of types and scopes
+pub fn check_syntax(
+    text: &str,
+    configuration: &sqf::analyzer::Configuration,
+    ifdef: &ifdef::Settings,
+) -> Vec<StagedError> {
+    let conf = sqf::preprocessor::Configuration {
+        path: configuration.file_path.clone(),
+        addons: configuration.addons.clone(),
+        ..Default::default()
+    };
+    let text = brackets::repair(text, &brackets::unbalanced(text, ifdef));
+    match preprocess(&text, |ast| {
+        parse(AstIterator::new(ifdef::evaluate(ast, ifdef).0, conf)).1
+    }) {
+        Ok((errors, recovered)) => recovered
+            .into_iter()
+            .map(|e| (Stage::Preprocessor, e))
+            .chain(errors.into_iter().map(|e| (Stage::Parser, e)))
+            .collect(),
+        Err(e) => vec![(Stage::Preprocessor, e)],
+    }
+}
+
+pub enum Outcome {
+    Complete(Box<Result<Return, StagedError>>),
+    /// The analysis exceeded its budget, with the errors of the stages it completed within it
+    Truncated(Vec<StagedError>),
+}
+
+/// The number of analyses run at once by [`compute_within`]
+const MAX_CONCURRENT_ANALYSES: usize = 4;
+
+/// The slots of the analyses run by [`compute_within`]. An analysis exceeding its budget keeps its
+/// slot until it stops, so that abandoned analyses cannot pile up.
+static ANALYSES: Semaphore = Semaphore::const_new(MAX_CONCURRENT_ANALYSES);
+
+/// [`compute`] within a time budget (if any), on a blocking thread of the runtime once one of the
+/// [`MAX_CONCURRENT_ANALYSES`] slots is free. The budget includes the wait for a slot. When the
+/// budget is exceeded, the analysis is abandoned and stops at the end of its current stage.
+pub async fn compute_within(
+    text: String,
+    configuration: sqf::analyzer::Configuration,
+    mission: MissionNamespace,
+    ifdef: ifdef::Settings,
+    budget: Option<Duration>,
+) -> Outcome {
+    let deadline = budget.map(|x| Instant::now() + x);
+    let permit = match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline.into(), ANALYSES.acquire())
+            .await
+            .ok(),
+        None => Some(ANALYSES.acquire().await),
+    };
+    let Some(Ok(permit)) = permit else {
+        return Outcome::Truncated(vec![]);
+    };
+    let completed = Arc::new(Mutex::new(vec![]));
+    let stages = completed.clone();
+    let task = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let path = configuration.file_path.clone();
+        catch_unwind(AssertUnwindSafe(|| {
+            compute_until(
+                &text,
+                configuration,
+                mission,
+                &ifdef,
+                &mut Timings::default(),
+                deadline,
+                &stages,
+            )
+        }))
+        .unwrap_or_else(|payload| Some(Err(panicked(&path, payload))))
+    });
+
+    let result = match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline.into(), task).await.ok(),
+        None => Some(task.await),
+    };
+    match result {
+        Some(Ok(Some(result))) => Outcome::Complete(Box::new(result)),
+        _ => Outcome::Truncated(std::mem::take(&mut completed.lock().unwrap())),
+    }
+}
+
+/// Time spent on each stage of the analysis of a file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Timings {
+    pub preprocess: Duration,
+    pub semantic_tokens: Duration,
+    /// parsing, including the expansion of macros and includes
+    pub parse: Duration,
+    pub analyze: Duration,
+    pub completion: Duration,
+}
+
+impl Timings {
+    pub fn total(&self) -> Duration {
+        self.preprocess + self.semantic_tokens + self.parse + self.analyze + self.completion
+    }
+}
+
+impl std::ops::Add for Timings {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            preprocess: self.preprocess + other.preprocess,
+            semantic_tokens: self.semantic_tokens + other.semantic_tokens,
+            parse: self.parse + other.parse,
+            analyze: self.analyze + other.analyze,
+            completion: self.completion + other.completion,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub timings: Timings,
+    /// the number of errors of the file, or `None` if it could not be read
+    pub errors: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ProjectReport {
+    /// the configs (`config.cpp` and `description.ext`) found
+    pub configs: Vec<PathBuf>,
+    pub files: Vec<FileReport>,
+    /// the wall-clock duration of the whole analysis
+    pub duration: Duration,
+}
+
+impl ProjectReport {
+    /// The time spent on each stage over all files
+    pub fn timings(&self) -> Timings {
+        self.files
+            .iter()
+            .fold(Timings::default(), |acc, x| acc + x.timings)
+    }
+}
+
+/// Analyzes the functions of every addon and mission in a directory tree, sequentially, timing
+/// each stage of the analysis of each file
+pub fn compute_project(
+    path: &Path,
+    scope: &addon::Scope,
+    ifdef: &ifdef::Settings,
+) -> ProjectReport {
+    let start = Instant::now();
+    let configs = addon::configs_in(path, scope);
+
+    let files = configs
+        .iter()
+        .filter_map(|config| Some((config, addon::functions(config, scope)?)))
+        .flat_map(|(config, functions)| {
+            let mission = addon::function_globals(config, &functions, &Default::default())
+                .into_iter()
+                .collect::<MissionNamespace>();
+            addon::function_files(config, &functions)
+                .into_values()
+                .map(move |file| (config, file, mission.clone()))
+        })
+        .map(|(config, file, mission)| {
+            let mut timings = Timings::default();
+            let errors = scope
+                .is_allowed(&file)
+                .then(|| std::fs::read_to_string(&file).ok())
+                .flatten()
+                .map(|content| {
+                    let configuration = sqf::analyzer::Configuration {
+                        file_path: file.clone(),
+                        base_path: config.clone(),
+                        ..Default::default()
+                    };
+                    match catch_unwind(AssertUnwindSafe(|| {
+                        let (state, errors) =
+                            compute_(&content, configuration, mission, ifdef, &mut timings)?;
+                        // computed on request by the server, timed here as if they were eager
+                        let analysis = Analysis::new(state);
+                        let start = Instant::now();
+                        analysis.semantic_tokens(&content);
+                        timings.semantic_tokens = start.elapsed();
+                        let start = Instant::now();
+                        analysis.completion("").count();
+                        timings.completion = start.elapsed();
+                        Ok::<_, StagedError>(errors)
+                    })) {
+                        Ok(Ok(errors)) => {
+                            errors.len() + brackets::unbalanced(&content, ifdef).len()
+                        }
+                        Ok(Err(_)) | Err(_) => 1,
+                    }
+                });
+            FileReport {
+                path: file.to_path_buf(),
+                timings,
+                errors,
+            }
+        })
+        .collect();
+
+    ProjectReport {
+        configs,
+        files,
+        duration: start.elapsed(),
+    }
 }
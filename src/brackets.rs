@@ -0,0 +1,120 @@
+//! Balance of the brackets (`{}`, `()`, `[]`) of a script.
+//!
+//! The parser of `sqf` reports an unbalanced bracket where it gives up, often far from the bracket
+//! itself, and then stops. The brackets are checked beforehand so that the diagnostic points at the
+//! bracket left open, and the offending brackets are blanked before parsing so that the rest of the
+//! file is still analyzed.
+use std::borrow::Cow;
+
+use sqf::span::{Span, Spanned};
+
+use crate::lint::Lint;
+use crate::{analyze, ifdef, tokens};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unbalanced {
+    /// where the bracket was expected to be closed, or the unexpected closing bracket
+    pub span: Span,
+    pub message: String,
+    /// the bracket left open, if any
+    pub opened: Option<Spanned<char>>,
+}
+
+impl Unbalanced {
+    pub fn lint(&self) -> Lint {
+        Lint {
+            code: "unbalanced-bracket",
+            span: self.span,
+            message: self.message.clone(),
+        }
+    }
+}
+
+fn closing(open: char) -> char {
+    match open {
+        '{' => '}',
+        '(' => ')',
+        _ => ']',
+    }
+}
+
+/// Returns the unbalanced brackets of the terms of a script
+pub fn check(terms: &[Spanned<&str>]) -> Vec<Unbalanced> {
+    let mut open: Vec<Spanned<char>> = vec![];
+    let mut unbalanced = vec![];
+    for term in terms {
+        let bracket = match term.inner {
+            "{" | "(" | "[" => {
+                open.push(Spanned::new(term.inner.as_bytes()[0] as char, term.span));
+                continue;
+            }
+            "}" => '}',
+            ")" => ')',
+            "]" => ']',
+            _ => continue,
+        };
+        let Some(position) = open.iter().rposition(|x| closing(x.inner) == bracket) else {
+            unbalanced.push(Unbalanced {
+                span: term.span,
+                message: format!("unexpected `{bracket}`: there is no bracket to close"),
+                opened: None,
+            });
+            continue;
+        };
+        // the brackets opened since were never closed
+        for opened in open.drain(position..).skip(1).rev() {
+            unbalanced.push(Unbalanced {
+                span: term.span,
+                message: format!("expected `{}` before `{bracket}`", closing(opened.inner)),
+                opened: Some(opened),
+            });
+        }
+    }
+    let end = terms.last().map(|x| x.span.1).unwrap_or_default();
+    unbalanced.extend(open.into_iter().rev().map(|opened| Unbalanced {
+        span: (end, end),
+        message: format!(
+            "expected `{}` at the end of the file",
+            closing(opened.inner)
+        ),
+        opened: Some(opened),
+    }));
+    unbalanced.sort_by_key(|x| x.span);
+    unbalanced
+}
+
+/// Returns the unbalanced brackets of a script, in its active `#ifdef` branches
+pub fn unbalanced(text: &str, settings: &ifdef::Settings) -> Vec<Unbalanced> {
+    analyze::preprocess(text, |ast| {
+        check(&tokens::terms(&ifdef::evaluate(ast, settings).0))
+    })
+    .map(|(unbalanced, _)| unbalanced)
+    .unwrap_or_default()
+}
+
+/// Blanks the brackets left open and the unexpected ones, keeping the offsets of all chars, so that
+/// the rest of the script can be parsed
+pub fn repair<'a>(text: &'a str, unbalanced: &[Unbalanced]) -> Cow<'a, str> {
+    if unbalanced.is_empty() {
+        return Cow::Borrowed(text);
+    }
+    let blanked = unbalanced
+        .iter()
+        .map(|x| x.opened.map(|x| x.span).unwrap_or(x.span))
+        .collect::<Vec<_>>();
+    Cow::Owned(
+        text.chars()
+            .enumerate()
+            .map(|(i, c)| {
+                if blanked
+                    .iter()
+                    .any(|(start, end)| (*start..*end).contains(&i))
+                {
+                    ' '
+                } else {
+                    c
+                }
+            })
+            .collect(),
+    )
+}
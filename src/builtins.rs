@@ -0,0 +1,130 @@
+//! The macros built into the preprocessor of the game, e.g. `__FILE__` and `__LINE__`
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use sqf::span::Spanned;
+
+use crate::{analyze, tokens};
+
+/// Each built-in macro and its description
+pub const BUILTINS: &[(&str, &str)] = &[
+    ("__FILE__", "The path of the file being preprocessed"),
+    ("__LINE__", "The line of the usage"),
+    (
+        "__COUNTER__",
+        "A counter incremented at each usage within the file, starting at 0",
+    ),
+    ("__COUNTER_RESET__", "Resets `__COUNTER__` to 0"),
+    (
+        "__DATE_ARR__",
+        "The date of preprocessing, as `year,month,day,hour,minute`",
+    ),
+    (
+        "__DATE_STR__",
+        "The date of preprocessing, as `\"2023/12/31, 23:59:59\"`",
+    ),
+    (
+        "__DATE_STR_ISO8601__",
+        "The date of preprocessing in UTC, as `\"2023-12-31T23:59:59Z\"`",
+    ),
+    ("__TIME__", "The time of preprocessing, as `23:59:59`"),
+    (
+        "__TIME_UTC__",
+        "The time of preprocessing in UTC, as `23:59:59`",
+    ),
+    (
+        "__TIMESTAMP_UTC__",
+        "The time of preprocessing, in seconds since the Unix epoch",
+    ),
+    (
+        "__GAME_VER__",
+        "The version of the game, e.g. `02.14.150957`",
+    ),
+    (
+        "__GAME_VER_MAJ__",
+        "The major version of the game, e.g. `02`",
+    ),
+    (
+        "__GAME_VER_MIN__",
+        "The minor version of the game, e.g. `14`",
+    ),
+    ("__GAME_BUILD__", "The build of the game, e.g. `150957`"),
+    ("__RAND_INT8__", "A random integer between -128 and 127"),
+    ("__RAND_UINT8__", "A random integer between 0 and 255"),
+    ("__RAND_INT16__", "A random 16 bit integer"),
+    ("__RAND_UINT16__", "A random unsigned 16 bit integer"),
+    ("__RAND_INT32__", "A random 32 bit integer"),
+    ("__RAND_UINT32__", "A random unsigned 32 bit integer"),
+    ("__RAND_INT64__", "A random 64 bit integer"),
+    ("__RAND_UINT64__", "A random unsigned 64 bit integer"),
+    ("__ARMA__", "Defined in all games of the Arma series"),
+    ("__ARMA3__", "Defined in Arma 3"),
+    (
+        "__A3_DEBUG__",
+        "Defined when the game runs with the `-debug` parameter",
+    ),
+    (
+        "__A3_EXPERIMENTAL__",
+        "Defined in the development and profiling branches of the game",
+    ),
+];
+
+/// The description of the built-in macro `name`, if it is one
+pub fn get(name: &str) -> Option<&'static str> {
+    BUILTINS
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+        .map(|(_, description)| *description)
+}
+
+/// The value of the built-in macro `name` used on `line` (0-based) of the file at `file` (as seen by
+/// the game), when it is known before running the game
+pub fn value(name: &str, file: &str, line: usize) -> Option<String> {
+    match name {
+        "__FILE__" => Some(file.to_string()),
+        "__LINE__" => Some((line + 1).to_string()),
+        "__ARMA__" | "__ARMA3__" => Some("1".to_string()),
+        _ => None,
+    }
+}
+
+/// The usages of `__FILE__` and `__LINE__` in `text`, whose value is shown inline
+pub fn usages(text: &str) -> Vec<Spanned<&'static str>> {
+    analyze::preprocess(text, |ast| {
+        tokens::terms(&ast)
+            .into_iter()
+            .filter_map(|term| {
+                let name = ["__FILE__", "__LINE__"]
+                    .into_iter()
+                    .find(|x| *x == term.inner)?;
+                Some(Spanned::new(name, term.span))
+            })
+            .collect()
+    })
+    .map(|(usages, _)| usages)
+    .unwrap_or_default()
+}
+
+/// The path of the file at `path` as seen by the game (i.e. the value of `__FILE__`): under the
+/// prefix of its addon when it has one, otherwise relative to its workspace folder
+pub fn game_path(path: &Path, addons: &HashMap<Arc<str>, PathBuf>, folders: &[PathBuf]) -> String {
+    let relative = |path: &Path| {
+        path.components()
+            .map(|x| x.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("\\")
+    };
+    addons
+        .iter()
+        .filter_map(|(prefix, directory)| Some((prefix, path.strip_prefix(directory).ok()?)))
+        .min_by_key(|(_, rest)| rest.components().count())
+        .map(|(prefix, rest)| format!("{}\\{}", prefix.trim_matches('\\'), relative(rest)))
+        .or_else(|| {
+            folders
+                .iter()
+                .find_map(|folder| path.strip_prefix(folder).ok())
+                .map(relative)
+        })
+        .unwrap_or_else(|| relative(path))
+}
@@ -0,0 +1,128 @@
+//! The graph of the calls between the functions of a project: `call`, `spawn` and `remoteExec`
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+use sqf::span::Spanned;
+
+use crate::tokens;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Kind {
+    Call,
+    Spawn,
+    RemoteExec,
+}
+
+impl Kind {
+    fn label(self) -> &'static str {
+        match self {
+            Kind::Call => "call",
+            Kind::Spawn => "spawn",
+            Kind::RemoteExec => "remoteExec",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub kind: Kind,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Graph {
+    pub nodes: Vec<String>,
+    pub edges: Vec<Edge>,
+}
+
+/// Returns the functions called by the terms of a script, by name as written: `call f`,
+/// `spawn f`, and `remoteExec ["f", ...]` (or `remoteExecCall`)
+pub fn calls(terms: &[Spanned<&str>]) -> Vec<(String, Kind)> {
+    terms
+        .iter()
+        .enumerate()
+        .filter_map(|(i, command)| {
+            let command = command.inner;
+            let next = terms.get(i + 1)?;
+            if command.eq_ignore_ascii_case("call") {
+                Some((next.inner.to_string(), Kind::Call))
+            } else if command.eq_ignore_ascii_case("spawn") {
+                Some((next.inner.to_string(), Kind::Spawn))
+            } else if command.eq_ignore_ascii_case("remoteExec")
+                || command.eq_ignore_ascii_case("remoteExecCall")
+            {
+                // `remoteExec ["f", ...]` or `remoteExec "f"`
+                let name = if next.inner == "[" {
+                    terms.get(i + 2)?
+                } else {
+                    next
+                };
+                Some((tokens::unquote(name.inner)?, Kind::RemoteExec))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+impl Graph {
+    /// Builds the graph of the functions `functions` (name and terms of its script), keeping the
+    /// calls between them
+    pub fn new<'a>(functions: impl IntoIterator<Item = (String, Vec<Spanned<&'a str>>)>) -> Self {
+        Self::from_calls(
+            functions
+                .into_iter()
+                .map(|(name, terms)| (name, calls(&terms))),
+        )
+    }
+
+    /// Builds the graph of the functions `functions` (name and [`calls`] of its script), keeping the
+    /// calls between them
+    pub fn from_calls(functions: impl IntoIterator<Item = (String, Vec<(String, Kind)>)>) -> Self {
+        let functions = functions.into_iter().collect::<Vec<_>>();
+        // the names as declared, by lowercase name
+        let names = functions
+            .iter()
+            .map(|(name, _)| (name.to_ascii_lowercase(), name.clone()))
+            .collect::<BTreeMap<_, _>>();
+        let edges = functions
+            .iter()
+            .flat_map(|(from, calls)| {
+                calls.iter().filter_map(|(to, kind)| {
+                    Some(Edge {
+                        from: from.clone(),
+                        to: names.get(&to.to_ascii_lowercase())?.clone(),
+                        kind: *kind,
+                    })
+                })
+            })
+            .collect::<BTreeSet<_>>();
+        Self {
+            nodes: names.into_values().collect(),
+            edges: edges.into_iter().collect(),
+        }
+    }
+
+    /// The graph in the DOT language of Graphviz
+    pub fn to_dot(&self) -> String {
+        let mut lines = vec!["digraph calls {".to_string()];
+        lines.extend(self.nodes.iter().map(|x| format!("    \"{x}\";")));
+        lines.extend(self.edges.iter().map(|x| {
+            let style = match x.kind {
+                Kind::Call => "",
+                Kind::Spawn => ", style=dashed",
+                Kind::RemoteExec => ", style=dotted",
+            };
+            format!(
+                "    \"{}\" -> \"{}\" [label=\"{}\"{style}];",
+                x.from,
+                x.to,
+                x.kind.label()
+            )
+        }));
+        lines.push("}".to_string());
+        lines.join("\n")
+    }
+}
@@ -0,0 +1,38 @@
+//! The normalization of the casing of commands (e.g. `getvariable` to `getVariable`), which the game
+//! ignores
+use std::collections::HashSet;
+
+use sqf::analyzer::{BINARY, NULLARY, UNARY};
+use sqf::span::Span;
+use sqf::UncasedStr;
+
+use crate::{analyze, tokens};
+
+/// The name of the command `name` as documented, if it is one
+pub fn command(name: &str) -> Option<&'static str> {
+    let name = UncasedStr::new(name);
+    BINARY
+        .get_key_value(name)
+        .map(|(x, _)| *x)
+        .or_else(|| UNARY.get_key_value(name).map(|(x, _)| *x))
+        .or_else(|| NULLARY.get_key_value(name).map(|(x, _)| *x))
+        .map(|x| x.as_str())
+}
+
+/// The commands of `text` written with a casing different from their documented name, and that
+/// name. The invocations of the `macros` defined for the file (e.g. `LOG("a")`), which are
+/// case-sensitive, are not commands and are left as is.
+pub fn fixes(text: &str, macros: &HashSet<String>) -> Vec<(Span, &'static str)> {
+    analyze::preprocess(text, |ast| {
+        tokens::terms(&ast)
+            .into_iter()
+            .filter(|term| !macros.contains(term.inner))
+            .filter_map(|term| {
+                let name = command(term.inner)?;
+                (name != term.inner).then_some((term.span, name))
+            })
+            .collect()
+    })
+    .map(|(fixes, _)| fixes)
+    .unwrap_or_default()
+}
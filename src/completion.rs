@@ -6,6 +6,8 @@ use tower_lsp::lsp_types::{
     CompletionItem, CompletionItemKind, Documentation, MarkupContent, MarkupKind,
 };
 
+use crate::{casing, locale};
+
 fn params_to_string(params: &Vec<Parameter>) -> String {
     format!(
         "[{}]",
@@ -17,7 +19,7 @@ fn params_to_string(params: &Vec<Parameter>) -> String {
     )
 }
 
-fn output_to_string(output: &Option<Output>) -> Option<String> {
+pub(crate) fn output_to_string(output: &Option<Output>) -> Option<String> {
     output.as_ref().map(|output| match output {
         Output::Type(type_) => format!("{type_:?}"),
         Output::Code(params, output) => params
@@ -28,7 +30,108 @@ fn output_to_string(output: &Option<Output>) -> Option<String> {
     })
 }
 
-pub(super) fn completion(namespace: &Namespace) -> Vec<CompletionItem> {
+/// Completion items sorted by (case-insensitive) label, so that those starting with a prefix and
+/// those of a name are found in O(log n)
+#[derive(Debug, Default)]
+pub(crate) struct Symbols {
+    /// the lowercase label of each item
+    keys: Vec<String>,
+    items: Vec<CompletionItem>,
+}
+
+impl Symbols {
+    fn new(mut items: Vec<CompletionItem>) -> Self {
+        items.sort_by_cached_key(|x| x.label.to_lowercase());
+        Self {
+            keys: items.iter().map(|x| x.label.to_lowercase()).collect(),
+            items,
+        }
+    }
+
+    /// The items whose label starts with `prefix`
+    pub fn starting_with(&self, prefix: &str) -> &[CompletionItem] {
+        let prefix = prefix.to_lowercase();
+        let start = self.keys.partition_point(|x| x.as_str() < prefix.as_str());
+        let len = self.keys[start..].partition_point(|x| x.starts_with(&prefix));
+        &self.items[start..start + len]
+    }
+
+    /// The (first) item labeled `name`
+    pub fn get(&self, name: &str) -> Option<&CompletionItem> {
+        self.starting_with(name)
+            .first()
+            .filter(|x| x.label.eq_ignore_ascii_case(name))
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+}
+
+/// Ranks `items` by relevance to `prefix`: exact matches, then those matching its case, then in the
+/// order given. Keeps the first `limit` (if any) and returns whether any item was left out.
+pub(crate) fn rank<'a>(
+    items: impl Iterator<Item = &'a CompletionItem>,
+    prefix: &str,
+    limit: Option<usize>,
+    explanations: &locale::Explanations,
+) -> (Vec<CompletionItem>, bool) {
+    let mut items = items.enumerate().collect::<Vec<_>>();
+    items.sort_by_key(|(i, x)| {
+        (
+            x.label.len() != prefix.len(),
+            !x.label.starts_with(prefix),
+            *i,
+        )
+    });
+    let is_incomplete = limit.map(|limit| items.len() > limit).unwrap_or(false);
+    let items = items
+        .into_iter()
+        .take(limit.unwrap_or(usize::MAX))
+        .enumerate()
+        .map(|(rank, (_, item))| CompletionItem {
+            // clients sort by `sort_text`, falling back to the label
+            sort_text: Some(format!("{rank:05}")),
+            ..localize(item.clone(), explanations)
+        })
+        .collect();
+    (items, is_incomplete)
+}
+
+/// `item` with its explanations replaced by their translation (see [`locale`]) when it is a command
+pub(crate) fn localize(
+    mut item: CompletionItem,
+    explanations: &locale::Explanations,
+) -> CompletionItem {
+    // the items of variables have no documentation
+    if item.documentation.is_none() || casing::command(&item.label).is_none() {
+        return item;
+    }
+    let Some(explanation) = locale::explanation(explanations, &item.label) else {
+        return item;
+    };
+    if let Some(Documentation::MarkupContent(content)) = &mut item.documentation {
+        // each line is `* `signature`: explanation`
+        content.value = content
+            .value
+            .lines()
+            .map(|line| match line.get(3..).and_then(|x| x.find("`: ")) {
+                Some(end) => format!("{}`: {explanation}", &line[..3 + end]),
+                None => line.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+    item.detail = Some(explanation);
+    item
+}
+
+/// The symbol table of the variables in scope at the end of the analysis
+pub(super) fn symbols(namespace: &Namespace) -> Symbols {
+    Symbols::new(completion(namespace))
+}
+
+fn completion(namespace: &Namespace) -> Vec<CompletionItem> {
     namespace
         .stack
         .iter()
@@ -56,7 +159,42 @@ pub(super) fn completion(namespace: &Namespace) -> Vec<CompletionItem> {
                 ..Default::default()
             }
         }))
-        .chain(NULLARY.iter().map(|(var, (type_, detail))| CompletionItem {
+        .collect()
+}
+
+/// The prefixes of the commands of displays and controls (e.g. `ctrlSetText`, `lbAdd`)
+const UI_PREFIXES: &[&str] = &[
+    "ctrl", "display", "dialog", "lb", "lnb", "tv", "slider", "progress", "button", "cb", "html",
+];
+
+/// The commands of displays and controls that are not named after them
+const UI_COMMANDS: &[&str] = &[
+    "findDisplay",
+    "createDialog",
+    "closeDialog",
+    "createDisplay",
+    "allDisplays",
+    "allControls",
+    "uiNamespace",
+    "setFocus",
+];
+
+/// Whether `name` is a command of displays or controls, as used in UI event handlers
+pub(crate) fn is_ui_command(name: &str) -> bool {
+    let lowercase = name.to_lowercase();
+    UI_PREFIXES.iter().any(|x| lowercase.starts_with(x))
+        || UI_COMMANDS.iter().any(|x| x.eq_ignore_ascii_case(name))
+}
+
+lazy_static::lazy_static! {
+    /// The completion items of the commands, identical for every document and thus built once
+    pub(super) static ref COMMANDS: Symbols = Symbols::new(commands());
+}
+
+fn commands() -> Vec<CompletionItem> {
+    NULLARY
+        .iter()
+        .map(|(var, (type_, detail))| CompletionItem {
             label: var.to_string(),
             kind: Some(CompletionItemKind::CONSTANT),
             detail: Some(detail.to_string()),
@@ -65,7 +203,7 @@ pub(super) fn completion(namespace: &Namespace) -> Vec<CompletionItem> {
                 value: format!("* `{type_:?}`: {}", detail.to_string()),
             })),
             ..Default::default()
-        }))
+        })
         .chain(UNARY.iter().map(|(var, variants)| {
             CompletionItem {
                 label: var.to_string(),
@@ -0,0 +1,638 @@
+//! A tolerant parser of Arma's config format (`config.cpp`, `description.ext`, `mission.sqm`, `*.hpp`).
+//!
+//! Unlike `sqf::cpp`, which only extracts `CfgFunctions`, this parser keeps the whole class tree
+//! with spans so that it can be used for navigation and validation of config documents.
+//! Preprocessor directives are skipped, and macros are kept verbatim as expressions.
+use std::path::Path;
+
+use sqf::span::{Span, Spanned};
+
+pub mod format;
+pub mod functions;
+pub mod patches;
+pub mod symbols;
+
+/// whether the file is a config document (as opposed to an SQF script)
+pub fn is_config(path: &Path) -> bool {
+    path.extension()
+        .and_then(|x| x.to_str())
+        .map(|x| {
+            ["cpp", "hpp", "ext", "sqm"]
+                .iter()
+                .any(|e| e.eq_ignore_ascii_case(x))
+        })
+        .unwrap_or(false)
+}
+
+/// whether the file at `path` is a binarized config (e.g. `config.bin` or a binarized `mission.sqm`),
+/// which cannot be parsed as text
+pub fn is_binarized(path: &Path) -> bool {
+    use std::io::Read;
+
+    let mut signature = [0u8; 4];
+    std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut signature))
+        .map(|_| &signature == b"\0raP")
+        .unwrap_or(false)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// A quoted string, unescaped
+    String(String),
+    /// Anything else (numbers, macros, `true`, expressions), verbatim
+    Expression(String),
+    Array(Vec<Spanned<Value>>),
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(value) | Value::Expression(value) => Some(value),
+            Value::Array(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property {
+    pub name: Spanned<String>,
+    /// whether it was declared as an array (`name[] = {...}`)
+    pub is_array: bool,
+    pub value: Spanned<Value>,
+    /// span of the whole declaration
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Class {
+    pub name: Spanned<String>,
+    pub parent: Option<Spanned<String>>,
+    /// `None` for forward declarations (`class A;`)
+    pub body: Option<Vec<Entry>>,
+    /// span of the whole declaration
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Entry {
+    Class(Class),
+    Property(Property),
+    /// `delete A;`
+    Delete(Spanned<String>),
+}
+
+impl Class {
+    fn entries(&self) -> &[Entry] {
+        self.body.as_deref().unwrap_or_default()
+    }
+
+    pub fn classes(&self) -> impl Iterator<Item = &Class> {
+        classes(self.entries())
+    }
+
+    pub fn properties(&self) -> impl Iterator<Item = &Property> {
+        properties(self.entries())
+    }
+
+    /// Returns the class with `name` (case-insensitive), if any
+    pub fn class(&self, name: &str) -> Option<&Class> {
+        self.classes()
+            .find(|class| class.name.inner.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the property with `name` (case-insensitive), if any
+    pub fn property(&self, name: &str) -> Option<&Property> {
+        self.properties()
+            .find(|property| property.name.inner.eq_ignore_ascii_case(name))
+    }
+}
+
+pub fn classes(entries: &[Entry]) -> impl Iterator<Item = &Class> {
+    entries.iter().filter_map(|entry| match entry {
+        Entry::Class(class) => Some(class),
+        _ => None,
+    })
+}
+
+pub fn properties(entries: &[Entry]) -> impl Iterator<Item = &Property> {
+    entries.iter().filter_map(|entry| match entry {
+        Entry::Property(property) => Some(property),
+        _ => None,
+    })
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    pub entries: Vec<Entry>,
+    pub errors: Vec<Spanned<String>>,
+}
+
+impl Config {
+    pub fn classes(&self) -> impl Iterator<Item = &Class> {
+        classes(&self.entries)
+    }
+
+    /// Returns the top-level class with `name` (case-insensitive), if any
+    pub fn class(&self, name: &str) -> Option<&Class> {
+        self.classes()
+            .find(|class| class.name.inner.eq_ignore_ascii_case(name))
+    }
+}
+
+fn code_spans_(entries: &[Entry], is_code: &impl Fn(&str) -> bool, spans: &mut Vec<Span>) {
+    for entry in entries {
+        match entry {
+            Entry::Class(class) => code_spans_(class.entries(), is_code, spans),
+            Entry::Property(property) => {
+                if is_code(&property.name.inner) && matches!(property.value.inner, Value::String(_))
+                {
+                    spans.push(property.value.span);
+                }
+            }
+            Entry::Delete(_) => {}
+        }
+    }
+}
+
+/// Returns the spans of the strings assigned to the properties whose name `is_code`, in any class
+pub fn code_spans(config: &Config, is_code: impl Fn(&str) -> bool) -> Vec<Span> {
+    let mut spans = vec![];
+    code_spans_(&config.entries, &is_code, &mut spans);
+    spans
+}
+
+/// Returns the code of the strings at `spans` of `text` as a SQF script with the same positions:
+/// everything else is blanked, and each string ends with `;`
+pub fn code(text: &str, spans: &[Span]) -> String {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut code = chars
+        .iter()
+        .map(|c| if *c == '\n' { '\n' } else { ' ' })
+        .collect::<Vec<_>>();
+    for &(start, end) in spans {
+        // without the quotes
+        let mut i = start + 1;
+        let mut in_string = false;
+        while i + 1 < end {
+            if chars[i] == '"' && chars.get(i + 1) == Some(&'"') {
+                // `""` is an escaped quote, kept next to the content of the string it delimits
+                let quote = if in_string { i } else { i + 1 };
+                code[quote] = '"';
+                in_string = !in_string;
+                i += 2;
+            } else {
+                code[i] = chars[i];
+                i += 1;
+            }
+        }
+        code[end - 1] = ';';
+    }
+    code.into_iter().collect()
+}
+
+fn value_at_(value: &Spanned<Value>, offset: usize) -> Option<&Spanned<Value>> {
+    if !(value.span.0 <= offset && offset < value.span.1) {
+        return None;
+    }
+    match &value.inner {
+        Value::Array(items) => items.iter().find_map(|item| value_at_(item, offset)),
+        _ => Some(value),
+    }
+}
+
+fn entries_value_at(entries: &[Entry], offset: usize) -> Option<&Spanned<Value>> {
+    entries.iter().find_map(|entry| match entry {
+        Entry::Class(class) => entries_value_at(class.entries(), offset),
+        Entry::Property(property) => value_at_(&property.value, offset),
+        Entry::Delete(_) => None,
+    })
+}
+
+/// Returns the (non-array) value at `offset`, if any. Values within arrays are also considered.
+pub fn value_at(config: &Config, offset: usize) -> Option<&Spanned<Value>> {
+    entries_value_at(&config.entries, offset)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token<'a> {
+    Word(&'a str),
+    String(&'a str),
+    Symbol(char),
+}
+
+fn is_symbol(c: u8) -> bool {
+    matches!(
+        c,
+        b'{' | b'}' | b';' | b'=' | b',' | b'[' | b']' | b':' | b'"'
+    )
+}
+
+/// Splits the text in tokens, skipping whitespace, comments and preprocessor directives
+fn tokenize(text: &str) -> Vec<Spanned<Token<'_>>> {
+    let bytes = text.as_bytes();
+    let mut tokens = vec![];
+    let mut i = 0;
+    let mut line_start = true;
+    while i < bytes.len() {
+        let c = bytes[i];
+        if c == b'\n' {
+            line_start = true;
+            i += 1;
+            continue;
+        }
+        if c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c == b'#' && line_start {
+            // directive until the end of the line, considering `\` continuations
+            while i < bytes.len() && bytes[i] != b'\n' {
+                if bytes[i] == b'\\' && bytes.get(i + 1) == Some(&b'\n') {
+                    i += 1;
+                } else if bytes[i] == b'\\' && text[i + 1..].starts_with("\r\n") {
+                    i += 2;
+                }
+                i += 1;
+            }
+            continue;
+        }
+        line_start = false;
+        if text[i..].starts_with("//") {
+            while i < bytes.len() && bytes[i] != b'\n' {
+                i += 1;
+            }
+            continue;
+        }
+        if text[i..].starts_with("/*") {
+            i = text[i + 2..]
+                .find("*/")
+                .map(|end| i + 2 + end + 2)
+                .unwrap_or(bytes.len());
+            continue;
+        }
+        if c == b'"' {
+            let start = i;
+            i += 1;
+            while i < bytes.len() {
+                if bytes[i] == b'"' {
+                    if bytes.get(i + 1) == Some(&b'"') {
+                        i += 2;
+                        continue;
+                    }
+                    break;
+                }
+                i += 1;
+            }
+            i = (i + 1).min(bytes.len());
+            tokens.push(Spanned::new(Token::String(&text[start..i]), (start, i)));
+            continue;
+        }
+        if c == b'+' && bytes.get(i + 1) == Some(&b'=') {
+            // `+=` is treated as `=`
+            tokens.push(Spanned::new(Token::Symbol('='), (i, i + 2)));
+            i += 2;
+            continue;
+        }
+        if is_symbol(c) {
+            tokens.push(Spanned::new(Token::Symbol(c as char), (i, i + 1)));
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let mut depth = 0usize;
+        while i < bytes.len() {
+            let c = bytes[i];
+            if c == b'(' {
+                depth += 1;
+            } else if c == b')' {
+                depth = depth.saturating_sub(1);
+            } else if depth == 0 && (c.is_ascii_whitespace() || is_symbol(c)) {
+                break;
+            } else if c == b'"' {
+                // strings inside macro calls, e.g. `QUOTE(call FUNC(a))`
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += 1;
+                }
+            }
+            i += 1;
+        }
+        let i_ = i.min(bytes.len());
+        tokens.push(Spanned::new(Token::Word(&text[start..i_]), (start, i_)));
+        i = i_;
+    }
+    tokens
+}
+
+/// unquotes a string token, replacing `""` by `"`
+pub fn unquote(token: &str) -> String {
+    let token = token.strip_prefix('"').unwrap_or(token);
+    let token = token.strip_suffix('"').unwrap_or(token);
+    token.replace("\"\"", "\"")
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<Spanned<Token<'a>>>,
+    position: usize,
+    errors: Vec<Spanned<String>>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Spanned<Token<'a>>> {
+        self.tokens.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<Spanned<Token<'a>>> {
+        let token = self.peek();
+        self.position += 1;
+        token
+    }
+
+    fn is_symbol(&self, symbol: char) -> bool {
+        matches!(self.peek(), Some(Spanned { inner: Token::Symbol(c), .. }) if c == symbol)
+    }
+
+    fn end(&self) -> usize {
+        self.tokens
+            .get(self.position.saturating_sub(1))
+            .map(|x| x.span.1)
+            .unwrap_or(0)
+    }
+
+    fn error(&mut self, message: impl Into<String>, span: Span) {
+        self.errors.push(Spanned::new(message.into(), span));
+    }
+
+    /// consumes a `;`, reporting an error if it is missing
+    fn semicolon(&mut self) {
+        if self.is_symbol(';') {
+            self.position += 1;
+        } else {
+            let end = self.end();
+            self.error("expected \";\"", (end, end));
+        }
+    }
+
+    /// skips tokens until the next `;` or `}` (not consumed)
+    fn recover(&mut self) {
+        while let Some(token) = self.peek() {
+            match token.inner {
+                Token::Symbol(';') => {
+                    self.position += 1;
+                    return;
+                }
+                Token::Symbol('}') => return,
+                _ => self.position += 1,
+            }
+        }
+    }
+
+    fn entries(&mut self, nested: bool) -> Vec<Entry> {
+        let mut entries = vec![];
+        while let Some(token) = self.peek() {
+            match token.inner {
+                Token::Symbol('}') if nested => return entries,
+                Token::Symbol(';') => self.position += 1,
+                Token::Word("class") => {
+                    if let Some(class) = self.class() {
+                        entries.push(Entry::Class(class))
+                    }
+                }
+                Token::Word(word) if word == "delete" || word == "import" => {
+                    self.position += 1;
+                    match self.next() {
+                        Some(Spanned {
+                            inner: Token::Word(name),
+                            span,
+                        }) => {
+                            if word == "delete" {
+                                entries.push(Entry::Delete(Spanned::new(name.to_string(), span)));
+                            }
+                            self.semicolon();
+                        }
+                        _ => {
+                            self.error(format!("expected a name after \"{word}\""), token.span);
+                            self.recover();
+                        }
+                    }
+                }
+                Token::Word(_) => {
+                    if let Some(property) = self.property() {
+                        entries.push(Entry::Property(property))
+                    }
+                }
+                _ => {
+                    self.error("unexpected token", token.span);
+                    self.position += 1;
+                    self.recover();
+                }
+            }
+        }
+        if nested {
+            let end = self.end();
+            self.error("expected \"}\"", (end, end));
+        }
+        entries
+    }
+
+    fn class(&mut self) -> Option<Class> {
+        let keyword = self.next()?;
+        let Some(Spanned {
+            inner: Token::Word(name),
+            span,
+        }) = self.next()
+        else {
+            self.error("expected the name of the class", keyword.span);
+            self.position -= 1;
+            self.recover();
+            return None;
+        };
+        let name = Spanned::new(name.to_string(), span);
+
+        let parent = if self.is_symbol(':') {
+            self.position += 1;
+            match self.next() {
+                Some(Spanned {
+                    inner: Token::Word(parent),
+                    span,
+                }) => Some(Spanned::new(parent.to_string(), span)),
+                _ => {
+                    self.error("expected the name of the parent class", name.span);
+                    self.position -= 1;
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let body = if self.is_symbol('{') {
+            self.position += 1;
+            let body = self.entries(true);
+            if self.is_symbol('}') {
+                self.position += 1;
+            }
+            self.semicolon();
+            Some(body)
+        } else {
+            self.semicolon();
+            None
+        };
+
+        Some(Class {
+            name,
+            parent,
+            body,
+            span: (keyword.span.0, self.end()),
+        })
+    }
+
+    fn property(&mut self) -> Option<Property> {
+        let token = self.next()?;
+        let Token::Word(name) = token.inner else {
+            return None;
+        };
+        let name = Spanned::new(name.to_string(), token.span);
+        let is_array = self.is_symbol('[');
+        if is_array {
+            self.position += 1;
+            if self.is_symbol(']') {
+                self.position += 1;
+            } else {
+                self.error("expected \"]\"", name.span);
+            }
+        }
+        if !self.is_symbol('=') {
+            self.error("expected \"=\"", name.span);
+            self.recover();
+            return None;
+        }
+        self.position += 1;
+        let value = self.value();
+        self.semicolon();
+        Some(Property {
+            name: name.clone(),
+            is_array,
+            value,
+            span: (name.span.0, self.end()),
+        })
+    }
+
+    /// parses a value until `;`, `}` or `,` (at the top level)
+    fn value(&mut self) -> Spanned<Value> {
+        if self.is_symbol('{') {
+            return self.array();
+        }
+        let start = self.peek().map(|x| x.span.0).unwrap_or(self.end());
+        let mut end = start;
+        let mut tokens = 0;
+        let mut string = None;
+        while let Some(token) = self.peek() {
+            match token.inner {
+                Token::Symbol(';' | '}' | ',') => break,
+                Token::String(value) => string = Some(value),
+                _ => {}
+            }
+            tokens += 1;
+            end = token.span.1;
+            self.position += 1;
+        }
+        let value = match (tokens, string) {
+            (1, Some(string)) => Value::String(unquote(string)),
+            _ => Value::Expression(self.text[start..end].to_string()),
+        };
+        Spanned::new(value, (start, end))
+    }
+
+    fn array(&mut self) -> Spanned<Value> {
+        let start = self.next().map(|x| x.span.0).unwrap_or_default();
+        let mut items = vec![];
+        loop {
+            match self.peek().map(|x| x.inner) {
+                Some(Token::Symbol('}')) => {
+                    self.position += 1;
+                    break;
+                }
+                Some(Token::Symbol(',')) => self.position += 1,
+                Some(Token::Symbol(';')) | None => {
+                    let end = self.end();
+                    self.error("expected \"}\"", (end, end));
+                    break;
+                }
+                Some(_) => {
+                    let position = self.position;
+                    items.push(self.value());
+                    if position == self.position {
+                        // no progress; avoid an infinite loop
+                        self.position += 1;
+                    }
+                }
+            }
+        }
+        Spanned::new(Value::Array(items), (start, self.end()))
+    }
+}
+
+/// Parses a config file. It never fails: errors are collected and parsing continues on the next
+/// declaration.
+pub fn parse(text: &str) -> Config {
+    let mut parser = Parser {
+        text,
+        tokens: tokenize(text),
+        position: 0,
+        errors: vec![],
+    };
+    let entries = parser.entries(false);
+    let mut config = Config {
+        entries,
+        errors: parser.errors,
+    };
+    to_chars(&mut config, text);
+    config
+}
+
+/// Converts the spans of the parser, in bytes, to chars, the unit of spans everywhere else
+fn to_chars(config: &mut Config, text: &str) {
+    if text.is_ascii() {
+        return;
+    }
+    let mut chars = vec![0; text.len() + 1];
+    for (count, (i, c)) in text.char_indices().enumerate() {
+        chars[i..i + c.len_utf8()].fill(count);
+    }
+    chars[text.len()] = text.chars().count();
+
+    let span = |span: &mut Span| *span = (chars[span.0], chars[span.1]);
+    fn value(item: &mut Spanned<Value>, span: &impl Fn(&mut Span)) {
+        span(&mut item.span);
+        if let Value::Array(items) = &mut item.inner {
+            items.iter_mut().for_each(|x| value(x, span));
+        }
+    }
+    fn entries(items: &mut [Entry], span: &impl Fn(&mut Span)) {
+        for entry in items {
+            match entry {
+                Entry::Class(class) => {
+                    span(&mut class.name.span);
+                    if let Some(parent) = &mut class.parent {
+                        span(&mut parent.span);
+                    }
+                    if let Some(body) = &mut class.body {
+                        entries(body, span);
+                    }
+                    span(&mut class.span);
+                }
+                Entry::Property(property) => {
+                    span(&mut property.name.span);
+                    value(&mut property.value, span);
+                    span(&mut property.span);
+                }
+                Entry::Delete(name) => span(&mut name.span),
+            }
+        }
+    }
+    entries(&mut config.entries, &span);
+    config.errors.iter_mut().for_each(|x| span(&mut x.span));
+}
@@ -0,0 +1,285 @@
+//! Formatting of config documents: indentation of nested classes, spacing of assignments and of
+//! array initializers and, optionally, alignment of the `=` of consecutive assignments.
+//!
+//! The formatting is line based, so comments, preprocessor directives and the line breaks chosen by
+//! the author are kept. Strings, comments and macro calls (`QUOTE(...)`) are never modified.
+use super::parse;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    /// the indentation of one level, e.g. 4 spaces or a tab
+    pub indent: String,
+    /// whether the `=` of consecutive assignments of the same class are aligned
+    pub align_assignments: bool,
+}
+
+/// A line of code, normalized
+struct Line {
+    code: String,
+    /// the number of `}` before any other code, which close the classes of the previous lines
+    leading_closes: usize,
+    opens: usize,
+    closes: usize,
+    /// the position (in chars) of the ` = ` of the first assignment
+    assignment: Option<usize>,
+}
+
+/// Normalizes the spacing of a line of code. `in_comment` is whether the line starts within a block
+/// comment, and is updated to whether it ends within one.
+fn normalize(line: &str, in_comment: &mut bool) -> Line {
+    let mut result = Line {
+        code: String::new(),
+        leading_closes: 0,
+        opens: 0,
+        closes: 0,
+        assignment: None,
+    };
+    let out = &mut result.code;
+    let chars = line.chars().collect::<Vec<_>>();
+    let mut is_leading = true;
+    // whether the braces are the ones of an array, after an `=`
+    let mut is_array = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if *in_comment {
+            out.push(c);
+            if c == '*' && chars.get(i + 1) == Some(&'/') {
+                out.push('/');
+                *in_comment = false;
+                i += 1;
+            }
+            i += 1;
+            continue;
+        }
+        let next = chars.get(i + 1).copied();
+        if c != '}' && !c.is_whitespace() {
+            is_leading = false;
+        }
+        match c {
+            '/' if next == Some('/') => {
+                out.extend(&chars[i..]);
+                break;
+            }
+            '/' if next == Some('*') => {
+                out.push_str("/*");
+                *in_comment = true;
+                i += 2;
+                continue;
+            }
+            '"' => {
+                // until the closing quote, `""` being an escaped quote
+                let start = i;
+                i += 1;
+                while i < chars.len() {
+                    if chars[i] == '"' && chars.get(i + 1) == Some(&'"') {
+                        i += 2;
+                    } else if chars[i] == '"' {
+                        break;
+                    } else {
+                        i += 1;
+                    }
+                }
+                i = (i + 1).min(chars.len());
+                out.extend(&chars[start..i]);
+                continue;
+            }
+            '(' => {
+                // macro calls are kept verbatim
+                let start = i;
+                let mut depth = 0usize;
+                let mut in_string = false;
+                while i < chars.len() {
+                    match chars[i] {
+                        '"' => in_string = !in_string,
+                        '(' if !in_string => depth += 1,
+                        ')' if !in_string => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+                i = (i + 1).min(chars.len());
+                out.extend(&chars[start..i]);
+                continue;
+            }
+            ',' => {
+                trim_end(out);
+                out.push_str(", ");
+                i = skip_whitespace(&chars, i + 1);
+                continue;
+            }
+            '{' => {
+                result.opens += 1;
+                if !out.is_empty() && !out.ends_with(char::is_whitespace) && !out.ends_with('{') {
+                    out.push(' ');
+                }
+                out.push('{');
+                i += 1;
+                if is_array {
+                    // `{ 1` -> `{1`
+                    i = skip_whitespace(&chars, i);
+                }
+                continue;
+            }
+            '}' => {
+                if is_leading {
+                    result.leading_closes += 1;
+                } else {
+                    result.closes += 1;
+                }
+                if is_array && !out.trim().is_empty() {
+                    trim_end(out);
+                }
+                out.push('}');
+            }
+            ';' => {
+                trim_end(out);
+                out.push(';');
+                is_array = false;
+            }
+            '=' if next != Some('=') && !out.ends_with(['=', '!', '<', '>']) => {
+                trim_end(out);
+                let operator = if out.ends_with('+') {
+                    out.pop();
+                    trim_end(out);
+                    " += "
+                } else {
+                    " = "
+                };
+                if result.assignment.is_none() {
+                    result.assignment = Some(out.chars().count());
+                }
+                is_array = true;
+                out.push_str(operator);
+                i = skip_whitespace(&chars, i + 1);
+                continue;
+            }
+            '[' => {
+                // `name [] = ` -> `name[] = `
+                trim_end(out);
+                out.push('[');
+            }
+            _ => out.push(c),
+        }
+        i += 1;
+    }
+    trim_end(out);
+    result
+}
+
+fn trim_end(text: &mut String) {
+    text.truncate(text.trim_end().len());
+}
+
+fn skip_whitespace(chars: &[char], mut i: usize) -> usize {
+    while chars.get(i).is_some_and(|x| x.is_whitespace()) {
+        i += 1;
+    }
+    i
+}
+
+/// Returns the formatted text of a config document, or `None` when it has syntax errors, which
+/// would make the indentation unreliable.
+pub fn format(text: &str, options: &Options) -> Option<String> {
+    if !parse(text).errors.is_empty() {
+        return None;
+    }
+    let line_break = if text.contains("\r\n") { "\r\n" } else { "\n" };
+
+    let mut depth = 0usize;
+    let mut in_comment = false;
+    let mut in_directive = false;
+    // the formatted lines and, for assignments, their depth and the position of their `=`
+    let mut lines: Vec<(String, Option<(usize, usize)>)> = vec![];
+    for line in text.lines() {
+        let line = line.trim_end();
+        if in_directive {
+            // the continuations of directives are kept as is
+            in_directive = line.ends_with('\\');
+            lines.push((line.to_string(), None));
+            continue;
+        }
+        if line.trim_start().starts_with('#') && !in_comment {
+            in_directive = line.ends_with('\\');
+            lines.push((indent(line.trim_start(), depth, options), None));
+            continue;
+        }
+        let starts_in_comment = in_comment;
+        let normalized = normalize(line.trim_start(), &mut in_comment);
+        depth = depth.saturating_sub(normalized.leading_closes);
+        if starts_in_comment {
+            // the content of block comments is kept as is
+            lines.push((line.to_string(), None));
+        } else {
+            // assignments of a whole property, e.g. `name[] = {1, 2};`
+            let assignment = normalized
+                .assignment
+                .filter(|_| normalized.opens == normalized.closes)
+                .filter(|_| !normalized.code.starts_with("class "))
+                .map(|x| (depth, x));
+            lines.push((indent(&normalized.code, depth, options), assignment));
+        }
+        depth = (depth + normalized.opens).saturating_sub(normalized.closes);
+    }
+
+    if options.align_assignments {
+        align(&mut lines, options);
+    }
+
+    let mut formatted = lines
+        .into_iter()
+        .map(|(line, _)| line)
+        .collect::<Vec<_>>()
+        .join(line_break);
+    if text.ends_with('\n') {
+        formatted.push_str(line_break);
+    }
+    Some(formatted)
+}
+
+fn indent(code: &str, depth: usize, options: &Options) -> String {
+    if code.is_empty() {
+        String::new()
+    } else {
+        format!("{}{code}", options.indent.repeat(depth))
+    }
+}
+
+/// Aligns the `=` of the groups of consecutive assignments at the same depth
+fn align(lines: &mut [(String, Option<(usize, usize)>)], options: &Options) {
+    let mut start = 0;
+    while start < lines.len() {
+        let Some((depth, _)) = lines[start].1 else {
+            start += 1;
+            continue;
+        };
+        let end = lines[start..]
+            .iter()
+            .position(|x| x.1.map(|x| x.0) != Some(depth))
+            .map(|x| start + x)
+            .unwrap_or(lines.len());
+        let width = lines[start..end]
+            .iter()
+            .filter_map(|x| x.1.map(|x| x.1))
+            .max()
+            .unwrap_or_default();
+        let prefix = options.indent.chars().count() * depth;
+        for (line, assignment) in &mut lines[start..end] {
+            let Some((_, position)) = assignment else {
+                continue;
+            };
+            let at = line
+                .char_indices()
+                .nth(prefix + *position)
+                .map(|x| x.0)
+                .unwrap_or(line.len());
+            line.insert_str(at, &" ".repeat(width - *position));
+        }
+        start = end;
+    }
+}
@@ -0,0 +1,224 @@
+//! Functions declared in `CfgFunctions`
+use sqf::span::{Span, Spanned};
+
+use super::{Class, Config, Value};
+use crate::lint::Lint;
+
+/// The attributes of a function class: the name, the values allowed (any when empty) and the
+/// documentation
+pub const ATTRIBUTES: &[(&str, &[&str], &str)] = &[
+    (
+        "file",
+        &[],
+        "The path of the script, instead of `{category file}\\fn_{function}.sqf`",
+    ),
+    (
+        "preInit",
+        &["0", "1"],
+        "`1` to call the function before the objects of the mission are initialized",
+    ),
+    (
+        "postInit",
+        &["0", "1"],
+        "`1` to call the function after the objects of the mission are initialized",
+    ),
+    (
+        "preStart",
+        &["0", "1"],
+        "`1` to call the function when the game starts, before the main menu",
+    ),
+    (
+        "recompile",
+        &["0", "1"],
+        "`1` to compile the function again each time the mission starts",
+    ),
+    (
+        "ext",
+        &["\".sqf\"", "\".fsm\""],
+        "The extension of the script: `\".sqf\"` or `\".fsm\"` for a state machine",
+    ),
+    (
+        "headerType",
+        &["-1", "0", "1"],
+        "The header added to the function: `-1` none, `0` the default one, `1` the one of system functions",
+    ),
+    (
+        "description",
+        &[],
+        "The description of the function shown in the functions viewer",
+    ),
+];
+
+/// A function declared in `CfgFunctions >> tag >> category >> function`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+    /// the name of the function, `{tag}_fnc_{function}`
+    pub name: String,
+    pub tag: String,
+    pub category: Spanned<String>,
+    /// the class declaring the function
+    pub class: Spanned<String>,
+    /// span of the whole class
+    pub span: Span,
+    /// the `file` attribute of the function, if any
+    pub file: Option<Spanned<String>>,
+    /// the `file` attribute of the category, i.e. the directory of its functions, if any
+    pub category_file: Option<Spanned<String>>,
+    /// the `ext` attribute of the function (e.g. `.fsm`), if any
+    pub ext: Option<String>,
+    /// whether the engine calls it at initialization (`preInit`, `postInit` or `preStart`)
+    pub init: bool,
+}
+
+impl Declaration {
+    /// The path of the script of the function as declared (i.e. not resolved):
+    /// its `file`, `{category file}\fn_{function}{ext}` or `functions\{category}\fn_{function}{ext}`
+    pub fn script(&self) -> Spanned<String> {
+        if let Some(file) = &self.file {
+            return file.clone();
+        }
+        let ext = self.ext.as_deref().unwrap_or(".sqf");
+        match &self.category_file {
+            Some(directory) => Spanned::new(
+                format!("{}\\fn_{}{ext}", directory.inner, self.class.inner),
+                directory.span,
+            ),
+            None => Spanned::new(
+                format!(
+                    "functions\\{}\\fn_{}{ext}",
+                    self.category.inner, self.class.inner
+                ),
+                self.class.span,
+            ),
+        }
+    }
+}
+
+fn string(class: &Class, name: &str) -> Option<Spanned<String>> {
+    let property = class.property(name)?;
+    let value = property.value.inner.as_str()?;
+    Some(Spanned::new(value.to_string(), property.value.span))
+}
+
+fn is_set(class: &Class, name: &str) -> bool {
+    class
+        .property(name)
+        .and_then(|x| x.value.inner.as_str())
+        .map(|x| x.trim() == "1")
+        .unwrap_or(false)
+}
+
+fn tag(class: &Class) -> String {
+    class
+        .property("tag")
+        .and_then(|x| x.value.inner.as_str())
+        .map(|x| x.to_string())
+        .unwrap_or_else(|| class.name.inner.clone())
+}
+
+/// Returns all functions declared in `CfgFunctions` of a config
+pub fn declarations(config: &Config) -> Vec<Declaration> {
+    let Some(cfg_functions) = config.class("CfgFunctions") else {
+        return vec![];
+    };
+    cfg_functions
+        .classes()
+        .flat_map(|tag_class| {
+            let tag = tag(tag_class);
+            tag_class.classes().flat_map(move |category| {
+                let tag = tag.clone();
+                let category_file = string(category, "file");
+                category.classes().map(move |function| Declaration {
+                    name: format!("{}_fnc_{}", tag, function.name.inner),
+                    tag: tag.clone(),
+                    category: category.name.clone(),
+                    class: function.name.clone(),
+                    span: function.span,
+                    file: string(function, "file"),
+                    category_file: category_file.clone(),
+                    ext: string(function, "ext").map(|x| x.inner),
+                    init: ["preInit", "postInit", "preStart"]
+                        .iter()
+                        .any(|flag| is_set(function, flag)),
+                })
+            })
+        })
+        .collect()
+}
+
+/// The attribute `name` of a function class, if it is one
+pub fn attribute(
+    name: &str,
+) -> Option<&'static (&'static str, &'static [&'static str], &'static str)> {
+    ATTRIBUTES
+        .iter()
+        .find(|(attribute, _, _)| attribute.eq_ignore_ascii_case(name))
+}
+
+/// The value of an attribute as written, strings quoted
+fn written(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(format!("\"{value}\"")),
+        Value::Expression(value) => Some(value.trim().to_string()),
+        Value::Array(_) => None,
+    }
+}
+
+/// Lints the attributes of the function classes of `CfgFunctions`: the unknown ones and the values
+/// not allowed, which the game silently ignores
+pub fn lint(config: &Config) -> Vec<Lint> {
+    let Some(cfg_functions) = config.class("CfgFunctions") else {
+        return vec![];
+    };
+    cfg_functions
+        .classes()
+        .flat_map(|tag| tag.classes())
+        .flat_map(|category| category.classes())
+        .flat_map(|function| function.properties())
+        .filter_map(|property| {
+            let name = &property.name.inner;
+            let Some((attribute, allowed, _)) = attribute(name) else {
+                return Some(Lint {
+                    code: "function-attribute",
+                    span: property.name.span,
+                    message: format!(
+                        "\"{name}\" is not an attribute of functions; expected one of {}",
+                        ATTRIBUTES
+                            .iter()
+                            .map(|x| x.0)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                });
+            };
+            let value = written(&property.value.inner)?;
+            // values of macros are unknown
+            let is_macro = value.chars().next().is_some_and(|x| x.is_ascii_uppercase());
+            (!allowed.is_empty() && !is_macro && !allowed.contains(&value.as_str())).then(|| Lint {
+                code: "function-attribute",
+                span: property.value.span,
+                message: format!(
+                    "{value} is not a valid value of \"{attribute}\"; expected {}",
+                    allowed.join(" or ")
+                ),
+            })
+        })
+        .collect()
+}
+
+/// The initial content of the script of a new function
+pub fn template(name: &str) -> String {
+    format!(
+        "/*
+ * Function: {name}
+ *
+ * Arguments:
+ *
+ * Return Value:
+ *
+ */
+params [];
+
+"
+    )
+}
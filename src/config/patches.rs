@@ -0,0 +1,42 @@
+//! Addons declared in `CfgPatches` and the addons they require
+use sqf::span::{Span, Spanned};
+
+use super::{Config, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Patch {
+    /// the name of the addon, i.e. of its class
+    pub name: Spanned<String>,
+    /// the elements of `requiredAddons[]`, as written (unquoted)
+    pub required: Vec<Spanned<String>>,
+    /// span of the `requiredAddons[]` declaration, if any
+    pub required_span: Option<Span>,
+    /// span of the whole class
+    pub span: Span,
+}
+
+/// Returns the addons declared in the `CfgPatches` of a config
+pub fn patches(config: &Config) -> Vec<Patch> {
+    let Some(cfg_patches) = config.class("CfgPatches") else {
+        return vec![];
+    };
+    cfg_patches
+        .classes()
+        .map(|class| {
+            let property = class.property("requiredAddons");
+            let required = match property.map(|x| &x.value.inner) {
+                Some(Value::Array(values)) => values
+                    .iter()
+                    .filter_map(|x| Some(Spanned::new(x.inner.as_str()?.to_string(), x.span)))
+                    .collect(),
+                _ => vec![],
+            };
+            Patch {
+                name: class.name.clone(),
+                required,
+                required_span: property.map(|x| x.span),
+                span: class.span,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,71 @@
+use sqf::span::Span;
+use tower_lsp::lsp_types::SymbolKind;
+
+use super::{Config, Entry, Value};
+
+/// A symbol of a config document (a class or a property), with its nested symbols
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub detail: Option<String>,
+    pub kind: SymbolKind,
+    /// span of the whole declaration
+    pub span: Span,
+    /// span of the name
+    pub selection_span: Span,
+    pub children: Vec<Symbol>,
+}
+
+const MAX_DETAIL: usize = 40;
+
+fn detail(value: &Value) -> Option<String> {
+    let value = match value {
+        Value::String(value) => format!("\"{value}\""),
+        Value::Expression(value) => value.clone(),
+        Value::Array(items) => return Some(format!("{} items", items.len())),
+    };
+    Some(if value.chars().count() > MAX_DETAIL {
+        format!("{}…", value.chars().take(MAX_DETAIL).collect::<String>())
+    } else {
+        value
+    })
+}
+
+fn symbols_(entries: &[Entry]) -> Vec<Symbol> {
+    entries
+        .iter()
+        .filter_map(|entry| match entry {
+            Entry::Class(class) => Some(Symbol {
+                name: class.name.inner.clone(),
+                detail: class.parent.as_ref().map(|x| format!(": {}", x.inner)),
+                kind: SymbolKind::CLASS,
+                span: class.span,
+                selection_span: class.name.span,
+                children: class.body.as_deref().map(symbols_).unwrap_or_default(),
+            }),
+            Entry::Property(property) => Some(Symbol {
+                name: if property.is_array {
+                    format!("{}[]", property.name.inner)
+                } else {
+                    property.name.inner.clone()
+                },
+                detail: detail(&property.value.inner),
+                kind: if property.is_array {
+                    SymbolKind::ARRAY
+                } else {
+                    SymbolKind::PROPERTY
+                },
+                span: property.span,
+                selection_span: property.name.span,
+                children: vec![],
+            }),
+            Entry::Delete(_) => None,
+        })
+        .collect()
+}
+
+/// Returns the hierarchy of classes and properties of a config, e.g.
+/// `CfgFunctions → TAG → category → function`
+pub fn symbols(config: &Config) -> Vec<Symbol> {
+    symbols_(&config.entries)
+}
@@ -0,0 +1,56 @@
+//! The data flow of a local variable within its scope: where it is declared, written and read
+use serde::Serialize;
+use sqf::analyzer::{Origin, State};
+use sqf::span::{Span, Spanned};
+
+fn in_span((start, end): Span, offset: usize) -> bool {
+    offset >= start && offset < end
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DataFlow {
+    pub name: String,
+    /// span of the declaration (e.g. `_a` in `private _a = 1`, or `"_a"` in `params ["_a"]`)
+    pub declaration: Span,
+    /// spans of the assignments, `_a = ...`, other than the declaration
+    pub writes: Vec<Span>,
+    pub reads: Vec<Span>,
+}
+
+/// Returns the data flow of the variable at `offset`, declared or used there, when it is declared in
+/// the file
+pub fn data_flow(state: &State, terms: &[Spanned<&str>], offset: usize) -> Option<DataFlow> {
+    let file = &state.configuration.file_path;
+    let origin = state
+        .origins
+        .iter()
+        .find_map(|(usage, origin)| {
+            let is_at = in_span(*usage, offset) || origin.1.is_some_and(|x| in_span(x, offset));
+            is_at.then(|| origin.clone())
+        })
+        .filter(|x| &x.0 == file)?;
+    let Origin(_, Some(declaration)) = origin else {
+        return None;
+    };
+
+    let term = |span: Span| terms.iter().position(|x| x.span == span);
+    let name = terms[term(declaration)?].inner.trim_matches(['"', '\'']);
+    let mut usages = state
+        .origins
+        .iter()
+        .filter(|(usage, x)| **x == origin && **usage != declaration)
+        .map(|(usage, _)| *usage)
+        .collect::<Vec<_>>();
+    usages.sort();
+    let (writes, reads) = usages.into_iter().partition(|usage| {
+        term(*usage)
+            .and_then(|i| terms.get(i + 1))
+            .is_some_and(|x| x.inner == "=")
+    });
+    Some(DataFlow {
+        name: name.to_string(),
+        declaration,
+        writes,
+        reads,
+    })
+}
@@ -0,0 +1,63 @@
+//! Experimental bridge to a running game through a debugger extension (e.g. the community's
+//! `debug_console`), which evaluates SQF in the game.
+//!
+//! The extension, or a proxy to it, listens at a TCP address and exchanges one JSON object per line:
+//! `{"command": "evaluate", "code": "..."}` or `{"command": "getVariable", "name": "..."}`, answered
+//! by `{"result": "..."}` or `{"error": "..."}`.
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// The time waited for the game to connect and to answer
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "command", rename_all = "camelCase")]
+pub enum Request {
+    /// evaluates code in the mission namespace and returns its value formatted by `str`
+    Evaluate { code: String },
+    /// returns the value of a global variable of the mission namespace formatted by `str`
+    GetVariable { name: String },
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    result: Option<String>,
+    error: Option<String>,
+}
+
+/// Sends `request` to the game listening at `address` and returns its answer, or an error message
+/// e.g. when the game is not running. This blocks until the game answers.
+pub fn request(address: &str, request: &Request) -> Result<String, String> {
+    let address = address
+        .to_socket_addrs()
+        .map_err(|e| format!("Invalid address \"{address}\": {e}"))?
+        .next()
+        .ok_or_else(|| format!("Invalid address \"{address}\""))?;
+    let mut stream = TcpStream::connect_timeout(&address, TIMEOUT)
+        .map_err(|e| format!("Could not connect to the game at {address}: {e}"))?;
+    stream
+        .set_read_timeout(Some(TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(TIMEOUT)))
+        .map_err(|e| e.to_string())?;
+
+    let mut line = serde_json::to_string(request).map_err(|e| e.to_string())?;
+    line.push('\n');
+    stream
+        .write_all(line.as_bytes())
+        .map_err(|e| format!("Could not send the request to the game: {e}"))?;
+
+    let mut answer = String::new();
+    BufReader::new(stream)
+        .read_line(&mut answer)
+        .map_err(|e| format!("The game did not answer: {e}"))?;
+    let response: Response =
+        serde_json::from_str(&answer).map_err(|e| format!("Invalid answer from the game: {e}"))?;
+    match (response.result, response.error) {
+        (_, Some(error)) => Err(error),
+        (Some(result), None) => Ok(result),
+        (None, None) => Ok(String::new()),
+    }
+}
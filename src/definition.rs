@@ -1,3 +1,4 @@
+use sqf::types::Type;
 use sqf::{analyzer::Origin, analyzer::State, span::Span};
 
 fn in_span((start, end): Span, offset: usize) -> bool {
@@ -10,3 +11,13 @@ pub fn get_definition(state: &State, offset: usize) -> Option<Origin> {
         .iter()
         .find_map(move |(k, v)| in_span(*k, offset).then_some(v.clone()))
 }
+
+/// The type inferred for the smallest expression of `state` containing `offset`, and its span
+pub fn type_at(state: &State, offset: usize) -> Option<(Span, Type)> {
+    state
+        .types
+        .iter()
+        .filter(|(span, _)| in_span(**span, offset))
+        .filter_map(|(span, type_)| Some((*span, (*type_)?)))
+        .min_by_key(|((start, end), _)| (end - start, *start))
+}
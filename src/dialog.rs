@@ -0,0 +1,25 @@
+//! The UI event handlers of dialogs and displays in configs (e.g. `onLoad = "..."`,
+//! `onButtonClick = "..."` or `action = "..."` of buttons), whose values are SQF
+use sqf::span::Span;
+
+use crate::config;
+
+/// Whether the property `name` is a UI event handler
+fn is_handler(name: &str) -> bool {
+    let mut chars = name.chars();
+    let is_event = chars.next().is_some_and(|x| x.eq_ignore_ascii_case(&'o'))
+        && chars.next().is_some_and(|x| x.eq_ignore_ascii_case(&'n'))
+        && chars.next().is_some_and(|x| x.is_ascii_uppercase());
+    is_event || name.eq_ignore_ascii_case("action")
+}
+
+/// Returns the spans of the handlers of a config, including their quotes
+pub fn spans(text: &str) -> Vec<Span> {
+    config::code_spans(&config::parse(text), is_handler)
+}
+
+/// Returns the code of the handlers of a config as a SQF script with the same positions, see
+/// [`config::code`]
+pub fn code(text: &str) -> String {
+    config::code(text, &spans(text))
+}
@@ -0,0 +1,126 @@
+//! The generation of Markdown documentation of the functions of an addon or mission, one page per
+//! function and an index
+use sqf::span::Spanned;
+
+use crate::params;
+use crate::tokens;
+
+/// The comment at the top of a script (e.g. its author, description and example), without its
+/// delimiters and with the leading `*` of each line of a block comment removed
+pub fn header(text: &str) -> String {
+    let text = text.trim_start();
+    let lines = if let Some(rest) = text.strip_prefix("/*") {
+        let Some(end) = rest.find("*/") else {
+            return String::new();
+        };
+        rest[..end]
+            .lines()
+            .map(|x| {
+                let x = x.trim();
+                x.strip_prefix('*').map_or(x, str::trim_start).to_string()
+            })
+            .collect::<Vec<_>>()
+    } else {
+        text.lines()
+            .map(str::trim)
+            .map_while(|x| x.strip_prefix("//"))
+            .map(|x| x.trim_start_matches('/').trim().to_string())
+            .collect()
+    };
+    lines.join("\n").trim().to_string()
+}
+
+/// A parameter declared by the first `params` of a script, with its default value and expected
+/// types as written
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub name: String,
+    pub default: Option<String>,
+    pub types: Option<Vec<String>>,
+}
+
+/// The parameters declared by the first `params` of the script `text` with `terms`
+pub fn parameters(text: &str, terms: &[Spanned<&str>]) -> Vec<Parameter> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let source = |range: &std::ops::Range<usize>| {
+        let span = (terms[range.start].span.0, terms[range.end - 1].span.1);
+        chars
+            .get(span.0..span.1)
+            .map(|x| x.iter().collect::<String>())
+    };
+    params::declarations(terms)
+        .into_iter()
+        .next()
+        .map(|x| x.entries)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|entry| Parameter {
+            name: entry.name.inner.clone(),
+            default: entry.default.as_ref().and_then(source),
+            types: entry.types.as_ref().map(|types| {
+                tokens::elements(terms, types.start)
+                    .iter()
+                    .filter_map(source)
+                    .collect()
+            }),
+        })
+        .collect()
+}
+
+/// The page of the function `name`: its header comment, signature, parameters, and the functions
+/// calling it
+pub fn page(
+    name: &str,
+    header: &str,
+    signature: Option<&str>,
+    parameters: &[Parameter],
+    callers: &[String],
+) -> String {
+    let mut lines = vec![format!("# {name}"), String::new()];
+    if !header.is_empty() {
+        lines.extend(["```".to_string(), header.to_string(), "```".to_string()]);
+        lines.push(String::new());
+    }
+    if let Some(signature) = signature {
+        lines.extend([format!("`{signature}`"), String::new()]);
+    }
+
+    lines.extend(["## Parameters".to_string(), String::new()]);
+    if parameters.is_empty() {
+        lines.push("None declared with `params`.".to_string());
+    } else {
+        lines.push("| # | Name | Default | Types |".to_string());
+        lines.push("|---|------|---------|-------|".to_string());
+        for (index, parameter) in parameters.iter().enumerate() {
+            let code = |x: &str| format!("`{}`", x.replace('|', "\\|"));
+            let default = parameter.default.as_deref().map(code).unwrap_or_default();
+            let types = parameter
+                .types
+                .as_ref()
+                .map(|x| x.iter().map(|x| code(x)).collect::<Vec<_>>().join(", "))
+                .unwrap_or_default();
+            lines.push(format!(
+                "| {index} | `{}` | {default} | {types} |",
+                parameter.name
+            ));
+        }
+    }
+    lines.push(String::new());
+
+    lines.extend(["## Callers".to_string(), String::new()]);
+    if callers.is_empty() {
+        lines.push("None in the project.".to_string());
+    } else {
+        lines.extend(callers.iter().map(|x| format!("* [{x}]({x}.md)")));
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// The index of the pages of the functions `names`
+pub fn index(names: &[String]) -> String {
+    let mut lines = vec!["# Functions".to_string(), String::new()];
+    lines.extend(names.iter().map(|x| format!("* [{x}]({x}.md)")));
+    lines.push(String::new());
+    lines.join("\n")
+}
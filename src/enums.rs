@@ -0,0 +1,212 @@
+//! The meaning of the numbers given to commands whose numeric arguments are effectively
+//! enumerations or bounded ranges, e.g. `_vehicle lock 2` or `_unit setDamage 1`
+use sqf::span::{Span, Spanned};
+
+/// The side of the command an argument is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
+}
+
+/// The numbers accepted by an argument of a command, and their meaning
+struct Argument {
+    command: &'static str,
+    side: Side,
+    values: &'static [(f64, &'static str)],
+    /// the meaning of the other values, if any
+    otherwise: Option<&'static str>,
+}
+
+const CHANNELS: &[(f64, &str)] = &[
+    (0.0, "global channel"),
+    (1.0, "side channel"),
+    (2.0, "command channel"),
+    (3.0, "group channel"),
+    (4.0, "vehicle channel"),
+    (5.0, "direct channel"),
+];
+
+const CUSTOM_CHANNEL: Option<&str> =
+    Some("custom channel (5 + the id returned by `radioChannelCreate`)");
+
+const VOLUME: &[(f64, &str)] = &[(0.0, "silent"), (1.0, "full volume")];
+
+const WEATHER: &[(f64, &str)] = &[(0.0, "none"), (1.0, "maximum")];
+
+const ARGUMENTS: &[Argument] = &[
+    Argument {
+        command: "lock",
+        side: Side::Right,
+        values: &[
+            (0.0, "unlocked"),
+            (1.0, "default"),
+            (2.0, "locked"),
+            (3.0, "locked for players"),
+        ],
+        otherwise: None,
+    },
+    Argument {
+        command: "setDamage",
+        side: Side::Right,
+        values: &[(0.0, "undamaged"), (1.0, "destroyed")],
+        otherwise: Some("partially damaged, from 0 (undamaged) to 1 (destroyed)"),
+    },
+    Argument {
+        command: "setFuel",
+        side: Side::Right,
+        values: &[(0.0, "empty tank"), (1.0, "full tank")],
+        otherwise: Some("fraction of a full tank"),
+    },
+    Argument {
+        command: "setVehicleAmmo",
+        side: Side::Right,
+        values: &[(0.0, "no ammunition"), (1.0, "full ammunition")],
+        otherwise: Some("fraction of the full ammunition"),
+    },
+    Argument {
+        command: "setMarkerAlpha",
+        side: Side::Right,
+        values: &[(0.0, "invisible"), (1.0, "opaque")],
+        otherwise: Some("partially transparent, from 0 (invisible) to 1 (opaque)"),
+    },
+    Argument {
+        command: "setMarkerAlphaLocal",
+        side: Side::Right,
+        values: &[(0.0, "invisible"), (1.0, "opaque")],
+        otherwise: Some("partially transparent, from 0 (invisible) to 1 (opaque)"),
+    },
+    Argument {
+        command: "ctrlSetFade",
+        side: Side::Right,
+        values: &[(0.0, "visible"), (1.0, "invisible")],
+        otherwise: Some("partially faded, from 0 (visible) to 1 (invisible)"),
+    },
+    Argument {
+        command: "setDir",
+        side: Side::Right,
+        values: &[
+            (0.0, "north"),
+            (90.0, "east"),
+            (180.0, "south"),
+            (270.0, "west"),
+        ],
+        otherwise: Some("degrees clockwise from north"),
+    },
+    Argument {
+        command: "setSkill",
+        side: Side::Right,
+        values: &[(0.0, "lowest skill"), (1.0, "highest skill")],
+        otherwise: Some("skill, from 0 (lowest) to 1 (highest)"),
+    },
+    Argument {
+        command: "setAccTime",
+        side: Side::Right,
+        values: &[(1.0, "normal speed")],
+        otherwise: Some("multiple of the normal speed"),
+    },
+    Argument {
+        command: "setTimeMultiplier",
+        side: Side::Right,
+        values: &[(1.0, "normal speed")],
+        otherwise: Some("multiple of the normal speed"),
+    },
+    Argument {
+        command: "enableChannel",
+        side: Side::Left,
+        values: CHANNELS,
+        otherwise: CUSTOM_CHANNEL,
+    },
+    Argument {
+        command: "setCurrentChannel",
+        side: Side::Right,
+        values: CHANNELS,
+        otherwise: CUSTOM_CHANNEL,
+    },
+    Argument {
+        command: "fadeMusic",
+        side: Side::Right,
+        values: VOLUME,
+        otherwise: None,
+    },
+    Argument {
+        command: "fadeSound",
+        side: Side::Right,
+        values: VOLUME,
+        otherwise: None,
+    },
+    Argument {
+        command: "fadeRadio",
+        side: Side::Right,
+        values: VOLUME,
+        otherwise: None,
+    },
+    Argument {
+        command: "fadeSpeech",
+        side: Side::Right,
+        values: VOLUME,
+        otherwise: None,
+    },
+    Argument {
+        command: "setOvercast",
+        side: Side::Right,
+        values: WEATHER,
+        otherwise: None,
+    },
+    Argument {
+        command: "setFog",
+        side: Side::Right,
+        values: WEATHER,
+        otherwise: None,
+    },
+    Argument {
+        command: "setRain",
+        side: Side::Right,
+        values: WEATHER,
+        otherwise: None,
+    },
+    Argument {
+        command: "setFlagAnimationPhase",
+        side: Side::Right,
+        values: &[(0.0, "bottom of the mast"), (1.0, "top of the mast")],
+        otherwise: None,
+    },
+];
+
+/// The meaning of the number `terms[index]` given to the command next to it, with the name of the
+/// command
+pub fn meaning(terms: &[Spanned<&str>], index: usize) -> Option<(&'static str, &'static str)> {
+    let value = terms.get(index)?.inner.parse::<f64>().ok()?;
+    let left = index.checked_sub(1).and_then(|x| terms.get(x));
+    let right = terms.get(index + 1);
+    ARGUMENTS.iter().find_map(|argument| {
+        let command = match argument.side {
+            Side::Right => left,
+            Side::Left => right,
+        }?;
+        if !command.inner.eq_ignore_ascii_case(argument.command) {
+            return None;
+        }
+        let meaning = argument
+            .values
+            .iter()
+            .find(|(x, _)| *x == value)
+            .map(|(_, meaning)| *meaning)
+            .or(argument.otherwise)?;
+        Some((argument.command, meaning))
+    })
+}
+
+/// Markdown explaining the number at `offset`, when it is given to a command of known argument
+/// semantics, with its span
+pub fn hover(terms: &[Spanned<&str>], offset: usize) -> Option<(Span, String)> {
+    let index = terms
+        .iter()
+        .position(|x| x.span.0 <= offset && offset < x.span.1)?;
+    let (command, meaning) = meaning(terms, index)?;
+    let term = &terms[index];
+    Some((
+        term.span,
+        format!("`{}` given to `{command}`: {meaning}", term.inner),
+    ))
+}
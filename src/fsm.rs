@@ -0,0 +1,34 @@
+//! Finite state machines (`.fsm`), whose states and links hold SQF in the attributes `init`,
+//! `precondition`, `condition` and `action`
+use std::borrow::Cow;
+use std::path::Path;
+
+use crate::config;
+
+/// The attributes holding code
+const CODE: &[&str] = &["init", "precondition", "condition", "action"];
+
+/// Whether the file at `path` is an FSM
+pub fn is_fsm(path: &Path) -> bool {
+    path.extension()
+        .map(|x| x.eq_ignore_ascii_case("fsm"))
+        .unwrap_or(false)
+}
+
+/// Returns the code of an FSM as a SQF script with the same positions, see [`config::code`].
+/// The local variables of an FSM are shared by all its states, and so are those of the script.
+pub fn code(text: &str) -> String {
+    let spans = config::code_spans(&config::parse(text), |name| {
+        CODE.iter().any(|x| x.eq_ignore_ascii_case(name))
+    });
+    config::code(text, &spans)
+}
+
+/// The SQF of the file at `path`: its content, or the code of an FSM
+pub fn sqf<'a>(path: &Path, text: &'a str) -> Cow<'a, str> {
+    if is_fsm(path) {
+        Cow::Owned(code(text))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
@@ -0,0 +1,89 @@
+//! The `.gitignore` files of a repository, used to skip ignored directories (build output, releases,
+//! extracted PBOs) when scanning a project
+use std::path::{Path, PathBuf};
+
+use globset::{GlobBuilder, GlobMatcher};
+
+#[derive(Debug)]
+struct Pattern {
+    glob: GlobMatcher,
+    negated: bool,
+    directory_only: bool,
+}
+
+/// The patterns of a `.gitignore`
+#[derive(Debug)]
+pub struct Gitignore {
+    /// the directory containing it, to which its patterns are relative
+    root: PathBuf,
+    patterns: Vec<Pattern>,
+}
+
+impl Gitignore {
+    /// Parses the content of the `.gitignore` in `root`. Invalid patterns are ignored.
+    pub fn parse(content: &str, root: PathBuf) -> Self {
+        let patterns = content
+            .lines()
+            .map(str::trim_end)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let (negated, line) = match line.strip_prefix('!') {
+                    Some(line) => (true, line),
+                    None => (false, line),
+                };
+                let (directory_only, line) = match line.strip_suffix('/') {
+                    Some(line) => (true, line),
+                    None => (false, line),
+                };
+                // patterns with a separator are relative to the root, the others match at any depth
+                let pattern = if line.contains('/') {
+                    line.trim_start_matches('/').to_string()
+                } else {
+                    format!("**/{line}")
+                };
+                let glob = GlobBuilder::new(&pattern)
+                    .literal_separator(true)
+                    .build()
+                    .ok()?
+                    .compile_matcher();
+                Some(Pattern {
+                    glob,
+                    negated,
+                    directory_only,
+                })
+            })
+            .collect();
+        Self { root, patterns }
+    }
+
+    /// Loads the `.gitignore` in `directory`, if any
+    pub fn load(directory: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(directory.join(".gitignore")).ok()?;
+        Some(Self::parse(&content, directory.to_path_buf()))
+    }
+
+    /// Whether the file or directory at `path` is ignored (`Some(true)`) or explicitly not
+    /// (`Some(false)`) by the last pattern matching it, `None` when none does
+    pub fn matches(&self, path: &Path, is_directory: bool) -> Option<bool> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        self.patterns
+            .iter()
+            .rev()
+            .find(|x| (is_directory || !x.directory_only) && x.glob.is_match(relative))
+            .map(|x| !x.negated)
+    }
+}
+
+/// Whether the directory at `path` is ignored by the `.gitignore` of its ancestors within its
+/// repository, the innermost taking precedence
+pub fn is_ignored(path: &Path) -> bool {
+    for ancestor in path.ancestors().skip(1) {
+        if let Some(ignored) = Gitignore::load(ancestor).and_then(|x| x.matches(path, true)) {
+            return ignored;
+        }
+        if ancestor.join(".git").exists() {
+            break;
+        }
+    }
+    false
+}
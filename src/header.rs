@@ -0,0 +1,90 @@
+//! Headers included by scripts (`.inc`, `.h`, and `.hpp` fragments of SQF), analyzed when opened
+//! in the context of a script including them: after the directives that precede their `#include`
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use sqf::preprocessor::Ast;
+
+use crate::{addon, include};
+
+/// The extensions of headers
+const EXTENSIONS: &[&str] = &["inc", "h", "hpp"];
+
+/// Whether the file at `path` may be a header included by scripts
+pub fn is_header(path: &Path) -> bool {
+    path.extension()
+        .and_then(|x| x.to_str())
+        .is_some_and(|x| EXTENSIONS.iter().any(|e| e.eq_ignore_ascii_case(x)))
+}
+
+/// The text of the directive starting at `start`, until the end of its line, joining the lines
+/// continued with `\`
+fn directive(text: &[char], start: usize) -> String {
+    let mut end = start;
+    loop {
+        match text[end..].iter().position(|c| *c == '\n') {
+            Some(line_end) => end += line_end,
+            None => {
+                end = text.len();
+                break;
+            }
+        }
+        let is_continued = text[start..end].iter().rev().find(|c| **c != '\r') == Some(&'\\');
+        if !is_continued {
+            break;
+        }
+        end += 1;
+    }
+    text[start..end]
+        .iter()
+        .collect::<String>()
+        .replace("\\\r\n", " ")
+        .replace("\\\n", " ")
+        .trim_end()
+        .to_string()
+}
+
+/// The directives (`#define` and `#include`) of `ast` preceding the `#include` of the header at
+/// `header`, or `None` when `ast` does not include it
+fn directives_before(
+    ast: &VecDeque<Ast>,
+    text: &[char],
+    path: &Path,
+    header: &Path,
+    addons: &HashMap<Arc<str>, PathBuf>,
+    scope: &addon::Scope,
+) -> Option<Vec<String>> {
+    let mut directives = vec![];
+    for node in ast {
+        match node {
+            Ast::Include(keyword, included) => {
+                let included_path = included.inner.trim_matches(['"', '<', '>']);
+                if include::resolve(included_path, path, addons, scope).as_deref() == Some(header) {
+                    return Some(directives);
+                }
+                directives.push(directive(text, keyword.span.0));
+            }
+            Ast::Define(define) => directives.push(directive(text, define.keyword.span.0)),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The text preceding the header at `header` when analyzed in the context of the script at `path`,
+/// of content `text`: the directives before its `#include`, one per line. `None` when the script
+/// does not include the header.
+pub fn context(
+    text: &str,
+    path: &Path,
+    header: &Path,
+    addons: &HashMap<Arc<str>, PathBuf>,
+    scope: &addon::Scope,
+) -> Option<String> {
+    let header = addon::canonical(header);
+    let chars = text.chars().collect::<Vec<_>>();
+    let ast = sqf::preprocessor::parse(text).ok()?;
+    let directives = directives_before(&ast, &chars, path, &header, addons, scope)?;
+    Some(directives.into_iter().map(|x| format!("{x}\n")).collect())
+}
@@ -0,0 +1,36 @@
+//! The build output of [HEMTT](https://github.com/BrettMayson/HEMTT), `.hemttout`, whose copies of
+//! the files of the project are mapped back to their sources
+use std::path::{Component, Path, PathBuf};
+
+/// The directory HEMTT builds into, at the root of the project
+pub const OUTPUT: &str = ".hemttout";
+
+/// Whether the directory at `path` is the output of HEMTT
+pub fn is_output(path: &Path) -> bool {
+    path.file_name().is_some_and(|x| x == OUTPUT)
+}
+
+/// The source of the file at `path` when it is a copy in the output of HEMTT, i.e.
+/// `<root>/.hemttout/<profile>/[@mod/]<path>` for an existing `<root>/<path>`
+pub fn source(path: &Path) -> Option<PathBuf> {
+    let components = path.components().collect::<Vec<_>>();
+    let output = components
+        .iter()
+        .position(|x| matches!(x, Component::Normal(x) if *x == OUTPUT))?;
+    let root = components[..output].iter().collect::<PathBuf>();
+    // skips the profile (e.g. `dev`, `release`)
+    let rest = components.get(output + 2..)?;
+    let is_mod =
+        |x: &Component| matches!(x, Component::Normal(x) if x.to_string_lossy().starts_with('@'));
+    let rest = match rest.first() {
+        Some(first) if is_mod(first) => &rest[1..],
+        _ => rest,
+    };
+    let source = root.join(rest.iter().collect::<PathBuf>());
+    (!rest.is_empty() && source.exists()).then_some(source)
+}
+
+/// The file at `path`, or its source when it is a copy in the output of HEMTT
+pub fn map(path: &Path) -> PathBuf {
+    source(path).unwrap_or_else(|| path.to_path_buf())
+}
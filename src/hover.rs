@@ -1,4 +1,13 @@
-use sqf::{analyzer::State, span::Span};
+use std::path::Path;
+
+use sqf::{
+    analyzer::{Output, State},
+    span::Span,
+};
+
+use crate::completion::output_to_string;
+use crate::config::functions::Declaration;
+use crate::params::{Entry, TYPE_VALUES};
 
 fn in_span((start, end): Span, offset: usize) -> bool {
     offset >= start && offset < end
@@ -10,3 +19,87 @@ pub fn hover(state: &State, offset: usize) -> Option<&'static str> {
         .iter()
         .find_map(move |(k, v)| in_span(*k, offset).then_some(*v))
 }
+
+/// The signature of a variable or function as shown on hover, e.g. `Number` or
+/// `[_a: Number] -> Boolean`
+pub fn signature(output: &Option<Output>) -> Option<String> {
+    output_to_string(output)
+}
+
+/// Markdown describing a function declared in `CfgFunctions`: its tag and category, the path of its
+/// script, whether it exists, and its signature (when the script was analyzed)
+pub fn function(declaration: &Declaration, path: Option<&Path>, output: Option<Output>) -> String {
+    let mut lines = vec![
+        format!("**{}**", declaration.name),
+        String::new(),
+        format!("* tag: `{}`", declaration.tag),
+        format!("* category: `{}`", declaration.category.inner),
+    ];
+    match path {
+        Some(path) if path.is_file() => lines.push(format!("* file: `{}`", path.display())),
+        Some(path) => lines.push(format!("* file: `{}` (not found)", path.display())),
+        None => lines.push("* file: could not derive a path".to_string()),
+    }
+    if let Some(signature) = output_to_string(&output) {
+        lines.push(format!("* signature: `{signature}`"));
+    }
+    lines.join("\n")
+}
+
+/// Markdown explaining an entry of `params`: the argument it reads, and the meaning of its default
+/// value, expected types and expected array sizes, given as written
+pub fn parameter(
+    entry: &Entry,
+    default: Option<String>,
+    types: Option<Vec<String>>,
+    counts: Option<String>,
+) -> String {
+    if entry.name.inner.is_empty() {
+        return format!(
+            "Argument {} (`_this select {}`) is skipped",
+            entry.index, entry.index
+        );
+    }
+    let mut lines = vec![
+        format!("**{}**", entry.name.inner),
+        String::new(),
+        format!(
+            "Private variable set to argument {} (`_this select {}`)",
+            entry.index, entry.index
+        ),
+        String::new(),
+    ];
+    match default {
+        Some(default) => lines.push(format!(
+            "* default: `{default}`, used when the argument is missing or `nil`"
+        )),
+        None => lines
+            .push("* no default: the variable is `nil` when the argument is missing".to_string()),
+    }
+    if let Some(types) = types {
+        let names = types
+            .iter()
+            .map(|value| {
+                TYPE_VALUES
+                    .iter()
+                    .find(|(x, _)| x.eq_ignore_ascii_case(value))
+                    .map(|(_, type_)| type_.to_string())
+                    .unwrap_or_else(|| format!("`{value}`"))
+            })
+            .collect::<Vec<_>>();
+        if names.is_empty() {
+            lines.push("* expected types: any".to_string());
+        } else {
+            lines.push(format!(
+                "* expected types: {}; an argument of another type is replaced by the default, and an error is logged",
+                names.join(", ")
+            ));
+        }
+    }
+    if let Some(counts) = counts {
+        lines.push(format!(
+            "* expected array size: `{counts}`; an array of another size is replaced by the default, and an error is logged"
+        ));
+    }
+    lines.join("\n")
+}
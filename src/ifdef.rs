@@ -0,0 +1,154 @@
+//! Evaluation of `#ifdef` and `#ifndef` with the macros predefined in the settings, and the regions
+//! of the branches that are not compiled.
+//!
+//! Only the conditions decidable from the settings and the file itself are evaluated, the others
+//! (e.g. on macros that may be defined by an include) are left to the preprocessor.
+use std::collections::{HashSet, VecDeque};
+
+use sqf::preprocessor::{Ast, Ifdef};
+use sqf::span::Span;
+
+use crate::lint::Lint;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Settings {
+    /// the macros defined before every file, e.g. `DEBUG_MODE_FULL`
+    pub defines: Vec<String>,
+    /// whether the inactive branches are analyzed as well
+    pub both_branches: bool,
+}
+
+/// The macros known at a point of a file
+#[derive(Debug, Clone)]
+struct Macros {
+    defined: HashSet<String>,
+    undefined: HashSet<String>,
+    /// whether any macro may have been defined by an include
+    included: bool,
+}
+
+impl Macros {
+    fn new(defines: &[String]) -> Self {
+        Self {
+            defined: defines.iter().cloned().collect(),
+            undefined: Default::default(),
+            included: false,
+        }
+    }
+
+    fn define(&mut self, name: &str) {
+        self.undefined.remove(name);
+        self.defined.insert(name.to_string());
+    }
+
+    fn undefine(&mut self, name: &str) {
+        self.defined.remove(name);
+        self.undefined.insert(name.to_string());
+    }
+
+    /// Whether `name` is defined, `None` when it may be defined by an include or by the
+    /// preprocessor itself (e.g. `__ARMA3__`)
+    fn is_defined(&self, name: &str) -> Option<bool> {
+        if self.defined.contains(name) {
+            Some(true)
+        } else if self.undefined.contains(name) || !(self.included || name.starts_with("__")) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+/// The region of each branch, between its directives
+fn regions(ifdef: &Ifdef) -> (Span, Option<Span>) {
+    let end = ifdef.endif_keyword.span.0;
+    match ifdef.else_keyword {
+        Some(else_) => ((ifdef.term.span.1, else_.span.0), Some((else_.span.1, end))),
+        None => ((ifdef.term.span.1, end), None),
+    }
+}
+
+fn evaluate_<'a>(
+    ast: VecDeque<Ast<'a>>,
+    macros: &mut Macros,
+    both_branches: bool,
+    inactive: &mut Vec<Span>,
+) -> VecDeque<Ast<'a>> {
+    let mut result = VecDeque::with_capacity(ast.len());
+    for node in ast {
+        let (ifdef, negated) = match node {
+            Ast::Ifdef(ifdef) => (ifdef, false),
+            Ast::Ifndef(ifdef) => (ifdef, true),
+            node => {
+                match &node {
+                    Ast::Define(define) => macros.define(define.name.inner),
+                    Ast::Undefine(_, name) => macros.undefine(name.inner),
+                    Ast::Include(..) => macros.included = true,
+                    _ => {}
+                }
+                result.push_back(node);
+                continue;
+            }
+        };
+
+        let Some(defined) = macros.is_defined(ifdef.term.inner) else {
+            // left to the preprocessor, whose branches may define anything
+            macros.included = true;
+            result.push_back(if negated {
+                Ast::Ifndef(ifdef)
+            } else {
+                Ast::Ifdef(ifdef)
+            });
+            continue;
+        };
+
+        let (then_region, else_region) = regions(&ifdef);
+        let (active, inactive_branch, region) = if defined != negated {
+            (ifdef.then, ifdef.else_, else_region)
+        } else {
+            (ifdef.else_, ifdef.then, Some(then_region))
+        };
+        inactive.extend(region.filter(|(start, end)| start < end));
+
+        // the inactive branch does not see the macros defined by the active one
+        let mut inactive_macros = macros.clone();
+        result.extend(evaluate_(active, macros, both_branches, inactive));
+        if both_branches {
+            // its nested regions are within its own
+            result.extend(evaluate_(
+                inactive_branch,
+                &mut inactive_macros,
+                both_branches,
+                &mut vec![],
+            ));
+        }
+    }
+    result
+}
+
+/// Evaluates the decidable `#ifdef`s and `#ifndef`s of `ast`, replacing them by the branches that
+/// are analyzed. Returns the new AST and the regions of the branches that are not compiled.
+pub fn evaluate<'a>(ast: VecDeque<Ast<'a>>, settings: &Settings) -> (VecDeque<Ast<'a>>, Vec<Span>) {
+    let mut macros = Macros::new(&settings.defines);
+    let mut inactive = vec![];
+    let ast = evaluate_(ast, &mut macros, settings.both_branches, &mut inactive);
+    inactive.sort();
+    (ast, inactive)
+}
+
+/// The regions of `ast` that are not compiled
+pub fn inactive_regions(ast: &VecDeque<Ast>, settings: &Settings) -> Vec<Span> {
+    evaluate(ast.clone(), settings).1
+}
+
+/// Lints of the regions that are not compiled, shown as faded by editors
+pub fn lint(ast: &VecDeque<Ast>, settings: &Settings) -> Vec<Lint> {
+    inactive_regions(ast, settings)
+        .into_iter()
+        .map(|span| Lint {
+            code: "inactive-region",
+            span,
+            message: "This code is not compiled with the current macros".to_string(),
+        })
+        .collect()
+}
@@ -0,0 +1,183 @@
+//! Resolution of `#include` directives and the tree of the files included by a file
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Serialize;
+use sqf::preprocessor::Ast;
+use sqf::span::{Span, Spanned};
+
+use crate::lint::Lint;
+use crate::{addon, analyze};
+
+/// The maximum number of files followed through includes
+pub const MAX_FILES: usize = 64;
+
+fn recurse<'a>(ast: &Ast<'a>, container: &mut Vec<Spanned<&'a str>>) {
+    match ast {
+        Ast::Ifdef(ifdef) | Ast::Ifndef(ifdef) => {
+            for node in ifdef.then.iter().chain(ifdef.else_.iter()) {
+                recurse(node, container)
+            }
+        }
+        Ast::If(if_) => {
+            for node in if_.then.iter().chain(if_.else_.iter()) {
+                recurse(node, container)
+            }
+        }
+        Ast::Include(_, path) => container.push(*path),
+        _ => {}
+    }
+}
+
+/// Returns the paths included by `ast` as written, without quotes, in both branches of conditions
+pub fn includes(ast: &VecDeque<Ast>) -> Vec<Spanned<String>> {
+    let mut container = vec![];
+    for node in ast {
+        recurse(node, &mut container);
+    }
+    container
+        .into_iter()
+        .map(|x| Spanned::new(x.inner.trim_matches(['"', '<', '>']).to_string(), x.span))
+        .collect()
+}
+
+/// Resolves the path `include` included by the file at `path`, if the analysis may read it
+pub fn resolve(
+    include: &str,
+    path: &Path,
+    addons: &HashMap<Arc<str>, PathBuf>,
+    scope: &addon::Scope,
+) -> Option<PathBuf> {
+    let resolved = addon::canonical(&sqf::get_path(include, path, addons).ok()?);
+    scope.is_allowed(&resolved).then_some(resolved)
+}
+
+/// An `#include` and the files it includes in turn
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Node {
+    /// the path as written
+    pub include: String,
+    /// the line of the directive (0-based)
+    pub line: usize,
+    /// the span of the path in the file including it
+    #[serde(skip)]
+    pub span: Span,
+    /// the resolved path, `None` when it could not be resolved
+    pub path: Option<PathBuf>,
+    /// whether the file includes itself through this directive, whose includes are not followed
+    pub cycle: bool,
+    /// whether the file was already included before, whose includes are not followed again
+    pub duplicate: bool,
+    pub includes: Vec<Node>,
+}
+
+fn children(
+    text: &str,
+    path: &Path,
+    addons: &HashMap<Arc<str>, PathBuf>,
+    scope: &addon::Scope,
+    ancestors: &mut Vec<PathBuf>,
+    seen: &mut HashSet<PathBuf>,
+) -> Vec<Node> {
+    let Ok((includes, _)) = analyze::preprocess(text, |ast| includes(&ast)) else {
+        return vec![];
+    };
+    includes
+        .into_iter()
+        .map(|include| {
+            let line = text
+                .chars()
+                .take(include.span.0)
+                .filter(|c| *c == '\n')
+                .count();
+            let resolved = resolve(&include.inner, path, addons, scope);
+            let mut node = Node {
+                include: include.inner,
+                line,
+                span: include.span,
+                path: resolved.clone(),
+                cycle: false,
+                duplicate: false,
+                includes: vec![],
+            };
+            let Some(resolved) = resolved else {
+                return node;
+            };
+            node.cycle = ancestors.contains(&resolved);
+            node.duplicate = !node.cycle && !seen.insert(resolved.clone());
+            if node.cycle || node.duplicate || seen.len() > MAX_FILES {
+                return node;
+            }
+            if let Ok(content) = std::fs::read_to_string(&resolved) {
+                ancestors.push(resolved.clone());
+                node.includes = children(&content, &resolved, addons, scope, ancestors, seen);
+                ancestors.pop();
+            }
+            node
+        })
+        .collect()
+}
+
+/// Returns the tree of the files included by `text`, the content of the file at `path`
+pub fn tree(
+    text: &str,
+    path: &Path,
+    addons: &HashMap<Arc<str>, PathBuf>,
+    scope: &addon::Scope,
+) -> Vec<Node> {
+    let path = addon::canonical(path);
+    children(
+        text,
+        &path,
+        addons,
+        scope,
+        &mut vec![path.clone()],
+        &mut HashSet::from([path.clone()]),
+    )
+}
+
+/// Returns the cycle reached through `node`, as the files from the first one included twice
+fn cycle(node: &Node, stack: &mut Vec<PathBuf>) -> Option<Vec<PathBuf>> {
+    let path = node.path.as_ref()?;
+    if node.cycle {
+        let start = stack.iter().position(|x| x == path)?;
+        let mut cycle = stack[start..].to_vec();
+        cycle.push(path.clone());
+        return Some(cycle);
+    }
+    stack.push(path.clone());
+    let cycle = node.includes.iter().find_map(|x| cycle(x, stack));
+    stack.pop();
+    cycle
+}
+
+/// Returns the errors of the `#include`s of `text`, the content of the file at `path`, through which
+/// files include themselves, naming the files of each cycle
+pub fn cycles(
+    text: &str,
+    path: &Path,
+    addons: &HashMap<Arc<str>, PathBuf>,
+    scope: &addon::Scope,
+) -> Vec<Lint> {
+    let path = addon::canonical(path);
+    tree(text, &path, addons, scope)
+        .iter()
+        .filter_map(|node| {
+            let cycle = cycle(node, &mut vec![path.clone()])?;
+            let files = cycle
+                .iter()
+                .map(|x| {
+                    x.file_name()
+                        .map(|x| x.to_string_lossy().into_owned())
+                        .unwrap_or_default()
+                })
+                .collect::<Vec<_>>();
+            Some(Lint {
+                code: "include-cycle",
+                span: node.span,
+                message: format!("Circular include: {}", files.join(" → ")),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,218 @@
+//! The type of `_x` in the code blocks iterating an array (`forEach`, `count`, `apply`, `select` and
+//! `findIf`), inferred from the commands returning arrays of a known type and from array literals
+use sqf::span::{Span, Spanned};
+use sqf::types::Type;
+
+use crate::tokens;
+
+/// Commands without arguments returning an array of elements of a known type
+const NULLARY: &[(&str, Type)] = &[
+    ("allUnits", Type::Object),
+    ("allPlayers", Type::Object),
+    ("allDead", Type::Object),
+    ("allDeadMen", Type::Object),
+    ("allCurators", Type::Object),
+    ("allUnitsUAV", Type::Object),
+    ("playableUnits", Type::Object),
+    ("switchableUnits", Type::Object),
+    ("vehicles", Type::Object),
+    ("allGroups", Type::Group),
+    ("allMapMarkers", Type::String),
+];
+
+/// Unary commands returning an array of elements of a known type
+const UNARY: &[(&str, Type)] = &[
+    ("units", Type::Object),
+    ("crew", Type::Object),
+    ("attachedObjects", Type::Object),
+    ("synchronizedObjects", Type::Object),
+    ("allMissionObjects", Type::Object),
+    ("entities", Type::Object),
+    ("nearestObjects", Type::Object),
+    ("allVariables", Type::String),
+    ("weapons", Type::String),
+    ("magazines", Type::String),
+    ("items", Type::String),
+    ("assignedItems", Type::String),
+    ("uniformItems", Type::String),
+    ("vestItems", Type::String),
+    ("backpackItems", Type::String),
+];
+
+/// Binary commands returning an array of elements of a known type
+const BINARY: &[(&str, Type)] = &[
+    ("nearEntities", Type::Object),
+    ("nearObjects", Type::Object),
+];
+
+/// Commands with the code on their left, iterating the array on their right
+const CODE_LEFT: &[&str] = &["forEach", "count"];
+
+/// Commands with the code on their right, iterating the array on their left
+const CODE_RIGHT: &[&str] = &["apply", "select", "findIf"];
+
+fn find(table: &[(&str, Type)], name: &str) -> Option<Type> {
+    table
+        .iter()
+        .find(|(x, _)| x.eq_ignore_ascii_case(name))
+        .map(|(_, type_)| *type_)
+}
+
+fn is_any(names: &[&str], name: &str) -> bool {
+    names.iter().any(|x| x.eq_ignore_ascii_case(name))
+}
+
+/// The index of the bracket closing the one at `open`
+fn closing(terms: &[Spanned<&str>], open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, term) in terms.iter().enumerate().skip(open) {
+        match term.inner {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The index of the bracket opening the one at `close`
+fn opening(terms: &[Spanned<&str>], close: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for i in (0..=close).rev() {
+        match terms[i].inner {
+            ")" | "]" | "}" => depth += 1,
+            "(" | "[" | "{" => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The terms of the left operand of the binary command at `index`, up to the start of the statement
+/// or of the enclosing brackets
+fn left_operand(terms: &[Spanned<&str>], index: usize) -> std::ops::Range<usize> {
+    let mut depth = 0usize;
+    let mut start = index;
+    while start > 0 {
+        match terms[start - 1].inner {
+            ")" | "]" | "}" => depth += 1,
+            "(" | "[" | "{" if depth == 0 => break,
+            "(" | "[" | "{" => depth -= 1,
+            ";" | "," | "=" if depth == 0 => break,
+            _ => {}
+        }
+        start -= 1;
+    }
+    start..index
+}
+
+/// The terms of the right operand of the binary command at `index`, up to the end of the statement
+/// or of the enclosing brackets
+fn right_operand(terms: &[Spanned<&str>], index: usize) -> std::ops::Range<usize> {
+    let mut depth = 0usize;
+    let mut end = index + 1;
+    while let Some(term) = terms.get(end) {
+        match term.inner {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" if depth == 0 => break,
+            ")" | "]" | "}" => depth -= 1,
+            ";" | "," if depth == 0 => break,
+            _ => {}
+        }
+        end += 1;
+    }
+    index + 1..end
+}
+
+/// The type of the single-term element of an array literal, if it is a literal
+fn literal_type(term: &str) -> Option<Type> {
+    if term.parse::<f64>().is_ok() {
+        Some(Type::Number)
+    } else if tokens::unquote(term).is_some() {
+        Some(Type::String)
+    } else if term.eq_ignore_ascii_case("true") || term.eq_ignore_ascii_case("false") {
+        Some(Type::Boolean)
+    } else {
+        None
+    }
+}
+
+/// The type of the elements of the array the expression `terms` evaluates to, if known
+pub fn element_type(terms: &[Spanned<&str>]) -> Option<Type> {
+    let first = terms.first()?;
+    let last = terms.len() - 1;
+    let is_enclosed = closing(terms, 0) == Some(last);
+    // `(array)`
+    if first.inner == "(" && is_enclosed {
+        return element_type(&terms[1..last]);
+    }
+    // `[1, 2, 3]`, when all elements are literals of the same type
+    if first.inner == "[" && is_enclosed {
+        let mut types = tokens::elements(terms, 0).into_iter().map(|element| {
+            (element.len() == 1)
+                .then(|| literal_type(terms[element.start].inner))
+                .flatten()
+        });
+        let type_ = types.next()??;
+        return types.all(|x| x == Some(type_)).then_some(type_);
+    }
+    // `array select {...}` keeps the type of the elements of `array`
+    if terms[last].inner == "}" {
+        let open = opening(terms, last)?;
+        let select = open.checked_sub(1)?;
+        return terms[select]
+            .inner
+            .eq_ignore_ascii_case("select")
+            .then(|| element_type(&terms[..select]))
+            .flatten();
+    }
+    if terms.len() == 1 {
+        return find(NULLARY, first.inner);
+    }
+    if let Some(type_) = find(UNARY, first.inner) {
+        return Some(type_);
+    }
+    // `position nearEntities [...]`
+    let mut depth = 0usize;
+    let mut type_ = None;
+    for term in terms {
+        match term.inner {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" => depth = depth.saturating_sub(1),
+            name if depth == 0 => type_ = find(BINARY, name).or(type_),
+            _ => {}
+        }
+    }
+    type_
+}
+
+/// The code blocks iterating an array whose elements are of a known type: the span of their `{`,
+/// and that type
+pub fn blocks(terms: &[Spanned<&str>]) -> Vec<(Span, Type)> {
+    terms
+        .iter()
+        .enumerate()
+        .filter_map(|(i, term)| {
+            if is_any(CODE_LEFT, term.inner) && terms.get(i.checked_sub(1)?)?.inner == "}" {
+                let open = opening(terms, i - 1)?;
+                let type_ = element_type(&terms[right_operand(terms, i)])?;
+                Some((terms[open].span, type_))
+            } else if is_any(CODE_RIGHT, term.inner) && terms.get(i + 1)?.inner == "{" {
+                let type_ = element_type(&terms[left_operand(terms, i)])?;
+                Some((terms[i + 1].span, type_))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
@@ -1,9 +1,42 @@
 pub mod addon;
 pub mod analyze;
+pub mod brackets;
+pub mod builtins;
+pub mod call_graph;
+pub mod casing;
 mod completion;
+pub mod config;
+pub mod data_flow;
+pub mod debug;
 pub mod definition;
+pub mod dialog;
+pub mod docgen;
+pub mod enums;
+pub mod fsm;
+pub mod gitignore;
+pub mod header;
+pub mod hemtt;
 pub mod hover;
+pub mod ifdef;
+pub mod include;
+pub mod iteration;
+pub mod lint;
+pub mod locale;
+pub mod macros;
+pub mod mission;
+pub mod on_type;
+pub mod params;
+pub mod private;
+pub mod project;
+pub mod remote_exec;
+pub mod rename;
+pub mod rules;
 pub mod semantic_token;
+pub mod sqs;
+pub mod suppress;
+pub mod text_pos;
+pub mod todo;
+pub mod tokens;
 
 #[cfg(test)]
 mod tests {
@@ -11,6 +44,18 @@ mod tests {
 
     use super::*;
 
+    /// The terms of `text`, separated by single spaces
+    fn terms(text: &str) -> Vec<sqf::span::Spanned<&str>> {
+        let mut start = 0;
+        text.split(' ')
+            .map(|x| {
+                let term = sqf::span::Spanned::new(x, (start, start + x.len()));
+                start += x.len() + 1;
+                term
+            })
+            .collect()
+    }
+
     #[test]
     fn bla() {
         let path: PathBuf = "./example.sqf".into();
@@ -22,18 +67,643 @@ mod tests {
             ..Default::default()
         };
 
-        let (state_semantic_completion, errors) =
-            match analyze::compute(&content, configuration, Default::default()) {
-                Ok((state, semantic, completion, errors)) => {
-                    (Some((state, semantic, completion)), errors)
-                }
-                Err(e) => (None, vec![e]),
+        let (state, errors) = match analyze::compute(
+            &content,
+            configuration,
+            Default::default(),
+            &Default::default(),
+        ) {
+            Ok((state, errors)) => (Some(state), errors),
+            Err(e) => (None, vec![e]),
+        };
+
+        let analysis = analyze::Analysis::new(state.unwrap());
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, analyze::Stage::Analyzer);
+        assert_eq!(analysis.state.explanations.len(), 4);
+        assert_eq!(analysis.semantic_tokens(&content).len(), 33);
+    }
+
+    #[test]
+    fn mission_sqm() {
+        let content = r#"
+version=53;
+class Mission
+{
+    class Entities
+    {
+        items=2;
+        class Item0
+        {
+            dataType="Marker";
+            position[]={1,2,3};
+            name="respawn_west";
+            type="mil_dot";
+        };
+        class Item1
+        {
+            dataType="Layer";
+            class Entities
+            {
+                class Item0
+                {
+                    dataType="Trigger";
+                    class Attributes
+                    {
+                        name="trg_end";
+                        condition="this && {alive ""a""}";
+                    };
+                };
             };
+        };
+    };
+};
+"#;
+        let mission = mission::index(content, "mission.sqm".into());
+
+        assert_eq!(mission.entities.len(), 2);
+        assert!(mission
+            .get("RESPAWN_WEST", mission::EntityKind::Marker)
+            .is_some());
+        assert!(mission
+            .get("trg_end", mission::EntityKind::Trigger)
+            .is_some());
+    }
+
+    #[test]
+    fn project() {
+        let content = r#"
+arma_version = "2.14"
+exclude = ["include/**"]
+
+[rules]
+unused-variable = "off"
+
+[addons]
+'\x\cba\addons' = "include/x/cba/addons"
+
+[naming]
+global_prefixes = ["MY_"]
+"#;
+        let root: PathBuf = "/mod".into();
+        let project = project::Project::parse(content, root.clone()).unwrap();
 
-        let (state, semantic_tokens, _) = state_semantic_completion.unwrap();
+        assert!(project.is_excluded(&root.join("include/x/cba/addons/main/script_macros.hpp")));
+        assert!(!project.is_excluded(&root.join("addons/main/fnc_a.sqf")));
+        assert_eq!(
+            project.rules.get("unused-variable"),
+            Some(&rules::Level::Off)
+        );
+        assert_eq!(
+            project.addons.get("\\x\\cba\\addons"),
+            Some(&root.join("include/x/cba/addons"))
+        );
+        assert_eq!(project.naming.global_prefixes, vec!["MY_".to_string()]);
 
+        assert!(project::Project::parse("[rules]\na = \"loud\"", root).is_err());
+    }
+    #[test]
+    fn preprocess_recovery() {
+        // the `#ifdef` is never closed
+        let text = "private _a = 1;\n#ifdef A\nprivate _b = _a;\n";
+        let (terms, errors) = analyze::preprocess(text, |ast| {
+            tokens::terms(&ast)
+                .iter()
+                .map(|x| (x.inner.to_string(), x.span))
+                .collect::<Vec<_>>()
+        })
+        .unwrap();
         assert_eq!(errors.len(), 1);
-        assert_eq!(state.explanations.len(), 4);
-        assert_eq!(semantic_tokens.len(), 33);
+        assert!(terms.contains(&("_b".to_string(), (33, 35))));
+    }
+    #[test]
+    fn text_pos() {
+        use tower_lsp::lsp_types::Position;
+
+        // "я" is 2 bytes and 1 UTF-16 unit, "😀" 4 bytes and 2 UTF-16 units
+        let text = "a = 1;\n// я😀\n_b = 2;";
+        let rope = ropey::Rope::from_str(text);
+
+        assert_eq!(text_pos::byte_to_char(text, 12), Some(11));
+        assert_eq!(text_pos::byte_to_char(text, 11), None);
+        assert_eq!(text_pos::char_to_byte(text, 11), Some(12));
+        assert_eq!(text_pos::char_to_byte(text, 100), None);
+        assert_eq!(text_pos::utf16_len("я😀"), 3);
+
+        // `_b`, after the multibyte characters
+        let offset = text.chars().position(|c| c == '_').unwrap();
+        assert_eq!(text_pos::position(offset, &rope), Some(Position::new(2, 0)));
+        // `😀`, after `// я`
+        assert_eq!(text_pos::position(11, &rope), Some(Position::new(1, 4)));
+        assert_eq!(text_pos::position(12, &rope), Some(Position::new(1, 6)));
+        assert_eq!(text_pos::offset(Position::new(1, 6), &rope), Some(12));
+        // past the end of the line
+        assert_eq!(text_pos::offset(Position::new(0, 100), &rope), Some(6));
+        assert_eq!(text_pos::utf16_length(10, 2, &rope), Some(3));
+    }
+
+    #[test]
+    fn unicode() {
+        use tower_lsp::lsp_types::Position;
+
+        // conversions round-trip on every offset of pseudo-random texts
+        let alphabet = ["a", "_", " ", "\n", "я", "中", "😀", "\r\n"];
+        let mut seed = 42u64;
+        for _ in 0..50 {
+            let text = (0..40)
+                .map(|_| {
+                    seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                    alphabet[(seed >> 33) as usize % alphabet.len()]
+                })
+                .collect::<String>();
+            let rope = ropey::Rope::from_str(&text);
+            let chars = text.chars().collect::<Vec<_>>();
+            for offset in 0..=chars.len() {
+                let byte = text_pos::char_to_byte(&text, offset).unwrap();
+                assert_eq!(text_pos::byte_to_char(&text, byte), Some(offset));
+                if offset > 0 && chars[offset - 1] == '\r' && chars.get(offset) == Some(&'\n') {
+                    // within a line break, which has no position
+                    continue;
+                }
+                let position = text_pos::position(offset, &rope).unwrap();
+                assert_eq!(text_pos::offset(position, &rope), Some(offset), "{text:?}");
+            }
+        }
+
+        // spans of configs and todos count chars
+        let text = "// комментарий\nclass A {};";
+        let config = config::parse(text);
+        let start = text.chars().position(|c| c == 'A').unwrap();
+        assert_eq!(config.entries.len(), 1);
+        let config::Entry::Class(class) = &config.entries[0] else {
+            panic!()
+        };
+        assert_eq!(class.name.span, (start, start + 1));
+
+        let text = "a = 1; // я TODO: x";
+        let todos = todo::collect(text, &["TODO".to_string()]).unwrap();
+        let start = text.chars().position(|c| c == 'T').unwrap();
+        assert_eq!(todos[0].span, (start, text.chars().count()));
+        let rope = ropey::Rope::from_str(text);
+        assert_eq!(
+            text_pos::position(todos[0].span.0, &rope),
+            Some(Position::new(0, 12))
+        );
+    }
+
+    #[test]
+    fn crlf() {
+        use tower_lsp::lsp_types::Position;
+
+        let text = "#define A \\\r\n    1\r\nclass B {};\r\n\nclass C {};\rclass D {};";
+        let rope = ropey::Rope::from_str(text);
+
+        // a directive continued on the next line is skipped as a whole
+        let config = config::parse(text);
+        let names = config
+            .classes()
+            .map(|x| x.name.inner.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["B", "C", "D"]);
+        let class = config.class("B").unwrap();
+        let range = text_pos::range(class.name.span, &rope).unwrap();
+        assert_eq!(range.start, Position::new(2, 6));
+        let class = config.class("D").unwrap();
+        let range = text_pos::range(class.name.span, &rope).unwrap();
+        assert_eq!(range.start, Position::new(5, 6));
+
+        // positions past the end of a line are before its line break
+        let end = text.find("\r\n\n").unwrap() + 1;
+        assert_eq!(
+            text_pos::offset(Position::new(2, 100), &rope),
+            Some(end - 1)
+        );
+        assert_eq!(
+            text_pos::offset(Position::new(3, 100), &rope),
+            Some(end + 1)
+        );
+        assert_eq!(
+            text_pos::offset(Position::new(4, 100), &rope),
+            Some(end + 13)
+        );
+    }
+
+    #[test]
+    fn fsm() {
+        let text = r#"class FSM
+{
+  class States
+  {
+    class Init
+    {
+      name = "Init";
+      init = /*%FSM<STATEINIT""">*/"_a = ""b"";"/*%FSM</STATEINIT""">*/;
+      class Links
+      {
+        class True
+        {
+          condition=/*%FSM<CONDITION""">*/"true"/*%FSM</CONDITION""">*/;
+        };
+      };
+    };
+  };
+};"#;
+        let code = fsm::code(text);
+        assert_eq!(code.chars().count(), text.chars().count());
+        assert_eq!(code.lines().count(), text.lines().count());
+        // the code is at the same positions, with its quotes unescaped
+        let start = text.find("_a").unwrap();
+        assert_eq!(&code[start..start + 12], "_a =  \"b\" ;;");
+        let start = text.find("true").unwrap();
+        assert_eq!(&code[start..start + 5], "true;");
+        assert!(!code.contains("Init"));
+    }
+
+    #[test]
+    fn brackets() {
+        use sqf::span::Spanned;
+
+        // `if (a then { b };`
+        let terms = ["if", "(", "a", "then", "{", "b", "}", ";"]
+            .iter()
+            .enumerate()
+            .map(|(i, x)| Spanned::new(*x, (i * 2, i * 2 + x.len())))
+            .collect::<Vec<_>>();
+        let unbalanced = brackets::check(&terms);
+        assert_eq!(unbalanced.len(), 1);
+        assert_eq!(unbalanced[0].opened.map(|x| x.span), Some((2, 3)));
+        assert_eq!(unbalanced[0].span, (15, 15));
+
+        // `{ a ) }`
+        let terms = ["{", "a", ")", "}"]
+            .iter()
+            .enumerate()
+            .map(|(i, x)| Spanned::new(*x, (i, i + 1)))
+            .collect::<Vec<_>>();
+        let unbalanced = brackets::check(&terms);
+        assert_eq!(unbalanced.len(), 1);
+        assert_eq!(unbalanced[0].span, (2, 3));
+        assert_eq!(unbalanced[0].opened, None);
+        assert_eq!(brackets::repair("{a)}", &unbalanced), "{a }");
+    }
+
+    #[test]
+    fn dead_store() {
+        let text = "private _a = 1 ; _a = 2 ; hint str _a ;";
+        let lints = lint::dead_store::lint(text, &terms(text));
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].span, (8, 10));
+
+        // read before, in a nested block or by the new value
+        for text in [
+            "_a = 1 ; if ( b ) then { hint _a } ; _a = 2 ;",
+            "_a = 1 ; _a = _a + 1 ;",
+            "_a = 1 ; call b ; _a = 2 ;",
+            "_a = 1 ; if ( b ) then { _a = 2 } ;",
+        ] {
+            assert_eq!(lint::dead_store::lint(text, &terms(text)), vec![], "{text}");
+        }
+    }
+
+    #[test]
+    fn index_out_of_range() {
+        let lints = lint::index::lint(&terms("_b = [ 1 , [ 2 , 3 ] ] select 2 ;"));
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].span, (30, 31));
+        assert_eq!(
+            lint::index::lint(&terms("_b = [ 1 , 2 ] select 1 ;")),
+            vec![]
+        );
+
+        let text = "private _a = [ 1 , 2 ] ; _b = _a # 2 ; _c = _a param [ 1 , 0 ] ;";
+        assert_eq!(lint::index::lint(&terms(text)).len(), 1);
+        // the size of a modified array is unknown
+        let text = "private _a = [ 1 , 2 ] ; _a pushBack 3 ; _b = _a select 2 ;";
+        assert_eq!(lint::index::lint(&terms(text)), vec![]);
+    }
+
+    #[test]
+    fn config_format() {
+        let text = "class CfgPatches\n{\nclass A {\n  units[]={ \"a\",\"b\" };\n    requiredVersion=1;\n    name = QUOTE(a,b);\n};\n};\n";
+        let mut options = config::format::Options {
+            indent: "    ".to_string(),
+            align_assignments: false,
+        };
+        let formatted = config::format::format(text, &options).unwrap();
+        assert_eq!(
+            formatted,
+            "class CfgPatches\n{\n    class A {\n        units[] = {\"a\", \"b\"};\n        requiredVersion = 1;\n        name = QUOTE(a,b);\n    };\n};\n"
+        );
+        assert_eq!(
+            config::format::format(&formatted, &options).unwrap(),
+            formatted
+        );
+
+        options.align_assignments = true;
+        let formatted = config::format::format(text, &options).unwrap();
+        assert!(formatted.contains("        units[]         = {"));
+        assert!(formatted.contains("        requiredVersion = 1;"));
+        assert!(formatted.contains("        name            = QUOTE"));
+
+        // syntax errors
+        assert_eq!(config::format::format("class A {", &options), None);
+    }
+
+    #[test]
+    fn private_styles() {
+        let terms_ = terms("private [ \"_a\" , \"_b\" ] ; _a = 1 ; hint _b ; _b = 2 ;");
+        let edits = private::to_keyword(&terms_, 0..terms_.len());
+        assert_eq!(
+            edits,
+            vec![
+                ((26, 26), "private ".to_string()),
+                ((0, 25), "private [\"_b\"];".to_string())
+            ]
+        );
+
+        let terms_ = terms("_c = 0 ; private _a = 1 ; private _b = _b + 1 ;");
+        let (edits, declaration) = private::to_array(&terms_, 0..terms_.len()).unwrap();
+        assert_eq!(edits, vec![((9, 17), String::new())]);
+        assert_eq!(declaration, ((0, 0), "private [\"_a\"];".to_string()));
+    }
+
+    #[test]
+    fn call_graph() {
+        let graph = call_graph::Graph::new([
+            (
+                "A_fnc_a".to_string(),
+                terms("[ ] call a_fnc_b ; [ ] remoteExec [ \"A_fnc_b\" , 2 ] ; call _x"),
+            ),
+            ("A_fnc_b".to_string(), terms("[ ] spawn A_fnc_a")),
+        ]);
+        assert_eq!(graph.nodes, vec!["A_fnc_a", "A_fnc_b"]);
+        assert_eq!(graph.edges.len(), 3);
+        assert!(graph
+            .to_dot()
+            .contains("\"A_fnc_a\" -> \"A_fnc_b\" [label=\"remoteExec\", style=dotted];"));
+    }
+
+    #[test]
+    fn required_addons() {
+        use lint::dependencies::{self, Component, Usage};
+
+        assert_eq!(
+            dependencies::macro_components("[] call EFUNC(common,log); QEGVAR(ui , open)"),
+            vec!["common", "ui"]
+        );
+
+        let config = config::parse(
+            "class CfgPatches { class my_main { requiredAddons[] = {\"my_ui\"}; }; };",
+        );
+        let component = Component {
+            patches: config::patches::patches(&config),
+            functions: vec![],
+        };
+        let usages = [Usage {
+            patches: vec!["my_common".to_string()],
+            reason: "`EFUNC(common, ...)`".to_string(),
+        }];
+        let project = ["my_common".to_string(), "my_ui".to_string()];
+        let lints = dependencies::requirements(&component, &usages, &project);
+        let codes = lints.iter().map(|x| x.code).collect::<Vec<_>>();
+        assert_eq!(
+            codes,
+            vec!["missing-required-addon", "unused-required-addon"]
+        );
+    }
+
+    #[test]
+    fn expand_macros() {
+        let define = |name: &str, arguments: Option<&[&str]>, body: &str| macros::Macro {
+            name: name.to_string(),
+            span: (0, 0),
+            path: None,
+            arguments: arguments.map(|x| x.iter().map(|x| x.to_string()).collect()),
+            body: body.to_string(),
+        };
+        let defined = [
+            define("COMPONENT", None, "main"),
+            define("PREFIX", None, "tag"),
+            define("DOUBLES", Some(&["a", "b"]), "a##_##b"),
+            define("ADDON", None, "DOUBLES(PREFIX,COMPONENT)"),
+            define("QUOTE", Some(&["a"]), "#a"),
+            define("GVAR", Some(&["a"]), "DOUBLES(ADDON,a)"),
+        ];
+        assert_eq!(macros::expand("ADDON", &defined), "tag_main");
+        assert_eq!(macros::expand("QUOTE(ADDON)", &defined), "\"tag_main\"");
+        assert_eq!(macros::expand("GVAR(x) = 1", &defined), "tag_main_x = 1");
+        assert_eq!(
+            macros::invocation("a = QUOTE(ADDON);", 4).as_deref(),
+            Some("QUOTE(ADDON)")
+        );
+    }
+
+    #[test]
+    fn unused_macros() {
+        let header = "#define USED 1\n#define IN_BODY 2\n#define STRINGIFIED 3\n#define PASTED_X 4\n#define UNUSED 5\n#define WRAP(a) IN_BODY + a\n#define NAME #STRINGIFIED\n#define PASTE(a) PASTED_##a\n";
+        let script = "x = USED;\ny = NAME;\nz = PASTE(X);\n";
+        let files = [(std::path::PathBuf::from("fn_a.sqf"), script.to_string())];
+        let unused = macros::unused(header, &files)
+            .into_iter()
+            .map(|x| x.message)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            unused,
+            vec![
+                "The macro \"UNUSED\" is never used in the addon",
+                "The macro \"WRAP\" is never used in the addon"
+            ]
+        );
+    }
+
+    #[test]
+    fn severity_overrides() {
+        use tower_lsp::lsp_types::DiagnosticSeverity;
+
+        let overrides = rules::Overrides::from_setting(&serde_json::json!([
+            {"path": "addons/compat_*", "severity": "info"},
+            {"path": "addons/compat_old", "severity": "off"},
+        ]));
+        let path = std::path::Path::new("addons/compat_ace/fnc_a.sqf");
+        assert_eq!(
+            overrides.apply(path, DiagnosticSeverity::ERROR),
+            Some(DiagnosticSeverity::INFORMATION)
+        );
+        assert_eq!(
+            overrides.apply(path, DiagnosticSeverity::HINT),
+            Some(DiagnosticSeverity::HINT)
+        );
+        let path = std::path::Path::new("addons/compat_old/fnc_a.sqf");
+        assert_eq!(overrides.apply(path, DiagnosticSeverity::ERROR), None);
+        let path = std::path::Path::new("addons/main/fnc_a.sqf");
+        assert_eq!(
+            overrides.apply(path, DiagnosticSeverity::ERROR),
+            Some(DiagnosticSeverity::ERROR)
+        );
+    }
+
+    #[test]
+    fn gitignore_patterns() {
+        let root = PathBuf::from("/project");
+        let gitignore = gitignore::Gitignore::parse(
+            "# build output\n/releases/\n.hemttout\naddons/*_tmp/\n!addons/keep_tmp/\n",
+            root.clone(),
+        );
+        let ignored = |path: &str| gitignore.matches(&root.join(path), true);
+        assert_eq!(ignored("releases"), Some(true));
+        assert_eq!(ignored("addons/releases"), None);
+        assert_eq!(ignored("addons/main/.hemttout"), Some(true));
+        assert_eq!(ignored("addons/main_tmp"), Some(true));
+        assert_eq!(ignored("addons/keep_tmp"), Some(false));
+        assert_eq!(ignored("addons/main"), None);
+    }
+
+    #[test]
+    fn hemtt_sources() {
+        let root = std::env::temp_dir().join("sqf_analyzer_hemtt");
+        let source = root.join("addons/main/fnc_a.sqf");
+        std::fs::create_dir_all(source.parent().unwrap()).unwrap();
+        std::fs::write(&source, "").unwrap();
+
+        let copy = root.join(".hemttout/dev/addons/main/fnc_a.sqf");
+        assert_eq!(hemtt::source(&copy), Some(source.clone()));
+        let copy = root.join(".hemttout/release/@mod/addons/main/fnc_a.sqf");
+        assert_eq!(hemtt::source(&copy), Some(source.clone()));
+        assert_eq!(hemtt::source(&source), None);
+        assert_eq!(
+            hemtt::source(&root.join(".hemttout/dev/addons/other.sqf")),
+            None
+        );
+    }
+
+    #[test]
+    fn moved_scripts() {
+        let old = PathBuf::from("/mod/addons/main/functions/fnc_a.sqf");
+        let new = PathBuf::from("/mod/addons/main/functions/common/fnc_a.sqf");
+        assert_eq!(
+            rename::moved_script(r"\x\tag\addons\main\functions\fnc_a.sqf", &old, &new).as_deref(),
+            Some(r"\x\tag\addons\main\functions\common\fnc_a.sqf")
+        );
+        assert_eq!(
+            rename::moved_script(r"functions\fnc_a.sqf", &old, &new).as_deref(),
+            Some(r"functions\common\fnc_a.sqf")
+        );
+        let outside = PathBuf::from("/other/fnc_a.sqf");
+        assert_eq!(
+            rename::moved_script(r"functions\fnc_a.sqf", &old, &outside),
+            None
+        );
+    }
+
+    #[test]
+    fn close_on_type() {
+        use on_type::Construct;
+
+        let all = [Construct::Endif, Construct::Class];
+        let text = "#ifdef DEBUG\n\n";
+        assert_eq!(
+            on_type::closing(text, "#ifdef DEBUG\n", &all, false).as_deref(),
+            Some("#endif")
+        );
+        let text = "#ifdef DEBUG\n\n#endif\n";
+        assert_eq!(on_type::closing(text, "#ifdef DEBUG\n", &all, false), None);
+
+        let text = "class CfgPatches {\n    class a {\n\n};\n";
+        assert_eq!(
+            on_type::closing(text, "    class a {\n", &all, true).as_deref(),
+            Some("    };")
+        );
+        assert_eq!(on_type::closing(text, "    class a {\n", &all, false), None);
+        assert_eq!(
+            on_type::closing(text, "    class a {\n", &[Construct::Endif], true),
+            None
+        );
+    }
+
+    #[test]
+    fn docgen_header() {
+        let text = "/*\n * Author: a\n * Heals a unit\n */\nparams [\"_unit\"];\n";
+        assert_eq!(docgen::header(text), "Author: a\nHeals a unit");
+        let text = "// Heals a unit\n// Returns: nothing\nparams [\"_unit\"];\n";
+        assert_eq!(docgen::header(text), "Heals a unit\nReturns: nothing");
+        assert_eq!(docgen::header("params [\"_unit\"];\n"), "");
+    }
+
+    #[test]
+    fn enum_meanings() {
+        let lock = terms("_vehicle lock 2");
+        assert_eq!(enums::meaning(&lock, 2), Some(("lock", "locked")));
+        assert_eq!(enums::meaning(&lock, 1), None);
+        let damage = terms("_unit setdamage 0.5");
+        assert_eq!(enums::meaning(&damage, 2).map(|x| x.0), Some("setDamage"));
+        let channel = terms("3 enableChannel false");
+        assert_eq!(
+            enums::meaning(&channel, 0),
+            Some(("enableChannel", "group channel"))
+        );
+        assert_eq!(enums::meaning(&terms("_x lock 7"), 2), None);
+    }
+
+    #[test]
+    fn iteration_types() {
+        use sqf::types::Type;
+
+        let types = |x: &str| {
+            iteration::blocks(&terms(x))
+                .into_iter()
+                .map(|(span, type_)| (span.0, type_))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            types("allUnits select { alive _x } apply { name _x }"),
+            vec![(16, Type::Object), (35, Type::Object)]
+        );
+        assert_eq!(
+            types("{ hint _x } forEach [ \"a\" , \"b\" ] ;"),
+            vec![(0, Type::String)]
+        );
+        assert_eq!(
+            types("private _a = units _group findIf { _x == player }"),
+            vec![(33, Type::Object)]
+        );
+        assert_eq!(types("{ hint _x } forEach [ 1 , \"b\" ]"), vec![]);
+        assert_eq!(types("_array apply { _x }"), vec![]);
+    }
+
+    #[test]
+    fn suppression_comments() {
+        let text = "// sqf-analyzer-disable unused-variable\n_a = 1;\n    // sqf-analyzer-disable-next-line undefined-variable, dead-store\nhint str _b;\n// sqf-analyzer-disable-next-line\nhint str _c;\n";
+        let comments = suppress::Comments::parse(text);
+        assert!(comments.is_suppressed("unused-variable", 1));
+        assert!(comments.is_suppressed("undefined-variable", 3));
+        assert!(comments.is_suppressed("dead-store", 3));
+        assert!(!comments.is_suppressed("undefined-variable", 1));
+        assert!(comments.is_suppressed("undefined-variable", 5));
+        assert!(!comments.is_suppressed("undefined-variable", 6));
+    }
+
+    #[test]
+    fn casing_fixes() {
+        let fix = |text: &str| {
+            let macros = macros::defined(
+                text,
+                std::path::Path::new("fn_a.sqf"),
+                &Default::default(),
+                &Default::default(),
+            )
+            .into_iter()
+            .map(|x| x.name)
+            .collect();
+            casing::fixes(text, &macros)
+                .into_iter()
+                .map(|(_, name)| name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(fix("_a = player getvariable \"b\";"), vec!["getVariable"]);
+        assert_eq!(
+            fix("#define LOG(x) diag_log x\nLOG(\"a\");"),
+            Vec::<&str>::new()
+        );
     }
 }
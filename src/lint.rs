@@ -0,0 +1,109 @@
+//! Lints computed by the server on top of the analysis done by `sqf`.
+//! Each lint is identified by the code of a rule in [`crate::rules`].
+use std::collections::{HashMap, HashSet};
+
+use sqf::span::Span;
+use sqf::types::Type;
+
+use crate::mission::MissionSqm;
+use crate::{ifdef, macros, params, sqs, tokens};
+
+pub mod comparison;
+pub mod dead_store;
+pub mod dependencies;
+pub mod events;
+pub mod functions;
+pub mod index;
+pub mod loops;
+pub mod markers;
+pub mod naming;
+pub mod switch;
+pub mod types;
+pub mod variable_keys;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lint {
+    pub code: &'static str,
+    pub span: Span,
+    pub message: String,
+}
+
+/// Information about the project that lints may use
+#[derive(Debug, Clone, Copy)]
+pub struct Context<'a> {
+    /// the `mission.sqm` of the mission the file belongs to, if any
+    pub mission: Option<&'a MissionSqm>,
+    /// the keys used with `setVariable`/`getVariable` over the project
+    pub variable_keys: Option<&'a variable_keys::Index>,
+    pub variable_keys_mode: variable_keys::Mode,
+    /// the events raised and handled over the project, if unmatched events should be reported
+    pub events: Option<&'a events::Index>,
+    /// prefixes that global variables must start with, if any
+    pub global_prefixes: &'a [String],
+    /// the macros defined by the file, its includes and the settings, if undefined macros should be
+    /// reported
+    pub macros: Option<&'a HashSet<String>>,
+    /// the functions whose script is the file
+    pub functions: &'a [String],
+    /// whether switches must have a `default` branch
+    pub require_switch_default: bool,
+    /// the types of the expressions inferred by the analysis, if it completed
+    pub types: Option<&'a HashMap<Span, Option<Type>>>,
+    /// the macros predefined in the settings, which decide the regions that are not compiled
+    pub ifdef: &'a ifdef::Settings,
+}
+
+/// The lint of a file larger than `limit` bytes, which is not analyzed
+pub fn too_large(text: &str, limit: Option<usize>) -> Option<Lint> {
+    let limit = limit.filter(|limit| text.len() > *limit)?;
+    Some(Lint {
+        code: "file-too-large",
+        span: (0, 0),
+        message: format!(
+            "This file ({} KB) is larger than {} KB and was not analyzed",
+            text.len() / 1024,
+            limit / 1024
+        ),
+    })
+}
+
+/// Runs all lints on `text`
+pub fn lint(text: &str, context: &Context) -> Vec<Lint> {
+    let Ok(ast) = sqf::preprocessor::parse(text) else {
+        return vec![];
+    };
+    let terms = tokens::terms(&ast);
+
+    let mut lints = vec![];
+    if let Some(mission) = context.mission {
+        lints.extend(markers::lint(&terms, mission));
+    }
+    if let Some(index) = context.variable_keys {
+        let usages = variable_keys::usages(&terms);
+        lints.extend(variable_keys::lint(
+            &usages,
+            index,
+            context.variable_keys_mode,
+        ));
+    }
+    if let Some(index) = context.events {
+        lints.extend(events::lint(&events::usages(&terms), index));
+    }
+    lints.extend(naming::lint(&terms, context.global_prefixes));
+    lints.extend(dead_store::lint(text, &terms));
+    lints.extend(comparison::lint(&terms));
+    lints.extend(index::lint(&terms));
+    lints.extend(switch::lint(&terms, context.require_switch_default));
+    lints.extend(loops::infinite_loops(&terms));
+    lints.extend(loops::recursion(&terms, context.functions));
+    lints.extend(params::lint(&terms));
+    if let Some(types) = context.types {
+        lints.extend(types::lint(&terms, types));
+    }
+    lints.extend(sqs::lint(&terms));
+    lints.extend(ifdef::lint(&ast, context.ifdef));
+    if let Some(defined) = context.macros {
+        lints.extend(macros::lint(&ast, defined));
+    }
+    lints
+}
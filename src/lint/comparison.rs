@@ -0,0 +1,62 @@
+//! Comparisons of strings with `==` and `!=`, which ignore case, of values whose case was changed
+//! with `toLower`/`toUpper`: either the conversion is useless, or an exact comparison was intended
+use sqf::span::Spanned;
+
+use super::Lint;
+
+/// Commands changing the case of a string
+const CASE_COMMANDS: &[&str] = &["toLower", "toUpper", "toLowerANSI", "toUpperANSI"];
+
+/// Terms delimiting the operands of a comparison
+fn is_boundary(term: &str) -> bool {
+    matches!(
+        term,
+        ";" | "," | "(" | ")" | "{" | "}" | "[" | "]" | "&&" | "||" | "!" | "==" | "!="
+    ) || ["and", "or", "not", "then", "exitWith", "do"]
+        .iter()
+        .any(|x| x.eq_ignore_ascii_case(term))
+}
+
+fn is_case_command(term: &str) -> bool {
+    CASE_COMMANDS.iter().any(|x| x.eq_ignore_ascii_case(term))
+}
+
+/// The exact comparison equivalent to the operator, i.e. `isEqualTo` or `isNotEqualTo`
+pub fn exact(operator: &str) -> Option<&'static str> {
+    match operator {
+        "==" => Some("isEqualTo"),
+        "!=" => Some("isNotEqualTo"),
+        _ => None,
+    }
+}
+
+/// Returns the comparisons with `==`/`!=` of which an operand starts with `toLower`/`toUpper`
+pub fn lint(terms: &[Spanned<&str>]) -> Vec<Lint> {
+    terms
+        .iter()
+        .enumerate()
+        .filter(|(_, term)| exact(term.inner).is_some())
+        .filter_map(|(i, operator)| {
+            // the first term of the left operand
+            let left = terms[..i]
+                .iter()
+                .rposition(|x| is_boundary(x.inner))
+                .map(|x| x + 1)
+                .unwrap_or(0);
+            let command = [terms.get(left), terms.get(i + 1)]
+                .into_iter()
+                .flatten()
+                .find(|x| is_case_command(x.inner))?;
+            Some(Lint {
+                code: "case-insensitive-comparison",
+                span: operator.span,
+                message: format!(
+                    "`{}` compares strings ignoring their case, so `{}` has no effect; use `{}` to compare them exactly",
+                    operator.inner,
+                    command.inner,
+                    exact(operator.inner)?
+                ),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,110 @@
+//! Dead stores: local variables assigned a value that is always overwritten before being read
+use sqf::span::Spanned;
+
+use super::Lint;
+
+/// Whether the term at `i` assigns a local variable as a statement (`_a = ...` or `private _a = ...`)
+fn is_assignment(terms: &[Spanned<&str>], i: usize) -> bool {
+    let is_statement = |i: usize| {
+        i.checked_sub(1)
+            .and_then(|i| terms.get(i))
+            .map(|x| matches!(x.inner, ";" | "{"))
+            .unwrap_or(true)
+    };
+    terms[i].inner.starts_with('_')
+        && terms.get(i + 1).is_some_and(|x| x.inner == "=")
+        && (is_statement(i)
+            || (i > 0 && terms[i - 1].inner.eq_ignore_ascii_case("private") && is_statement(i - 1)))
+}
+
+/// Whether `term` may read the variable `name`: the variable itself, or a string that may contain
+/// code using it (e.g. `isNil "_a"`)
+fn reads(term: &str, name: &str) -> bool {
+    term.eq_ignore_ascii_case(name)
+        || (term.starts_with(['"', '\''])
+            && term
+                .to_ascii_lowercase()
+                .contains(&name.to_ascii_lowercase()))
+}
+
+/// Whether the text between two chars may read `name` in a way invisible in the terms: in a macro
+/// argument, or in a branch of `#ifdef`
+fn may_read(chars: &[char], (start, end): (usize, usize), name: &str) -> bool {
+    let text = chars[start.min(chars.len())..end.min(chars.len())]
+        .iter()
+        .collect::<String>();
+    text.lines().any(|x| x.trim_start().starts_with('#'))
+        || text
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .any(|x| x.eq_ignore_ascii_case(name))
+}
+
+/// Returns the assignments of local variables that are overwritten in the same scope before any read.
+///
+/// The statements between the two assignments must not read the variable, even conditionally (e.g.
+/// in a nested block), nor `call` code, which shares the scope of the caller.
+pub fn lint(text: &str, terms: &[Spanned<&str>]) -> Vec<Lint> {
+    let chars = text.chars().collect::<Vec<_>>();
+
+    // the block of each term: the index of its innermost `{`
+    let mut blocks = Vec::with_capacity(terms.len());
+    let mut stack = vec![usize::MAX];
+    for (i, term) in terms.iter().enumerate() {
+        blocks.push(*stack.last().unwrap_or(&usize::MAX));
+        match term.inner {
+            "{" => stack.push(i),
+            "}" if stack.len() > 1 => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+
+    (0..terms.len())
+        .filter(|i| is_assignment(terms, *i))
+        .filter_map(|i| {
+            let name = terms[i].inner;
+            // the next term that may read the variable or call code
+            let next = (i + 2..terms.len()).find(|k| {
+                reads(terms[*k].inner, name) || terms[*k].inner.eq_ignore_ascii_case("call")
+            })?;
+            let is_overwritten = terms[next].inner.eq_ignore_ascii_case(name)
+                && blocks[next] == blocks[i]
+                && is_assignment(terms, next);
+            if !is_overwritten || may_read(&chars, (terms[i + 1].span.1, terms[next].span.0), name)
+            {
+                return None;
+            }
+            // the value assigned may read the previous one, e.g. `_a = _a + 1`
+            let mut depth = 0usize;
+            let value = terms[next + 2..]
+                .iter()
+                .take_while(|x| {
+                    match x.inner {
+                        "{" | "[" | "(" => depth += 1,
+                        "}" | "]" | ")" if depth == 0 => return false,
+                        "}" | "]" | ")" => depth -= 1,
+                        ";" if depth == 0 => return false,
+                        _ => {}
+                    }
+                    true
+                })
+                .collect::<Vec<_>>();
+            let end = value
+                .last()
+                .map(|x| x.span.1)
+                .unwrap_or(terms[next + 1].span.1);
+            let value_reads = value
+                .iter()
+                .any(|x| reads(x.inner, name) || x.inner.eq_ignore_ascii_case("call"))
+                || may_read(&chars, (terms[next + 1].span.1, end), name);
+            (!value_reads).then(|| Lint {
+                code: "dead-store",
+                span: terms[i].span,
+                message: format!(
+                    "The value assigned to \"{name}\" is overwritten before being read"
+                ),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,180 @@
+//! Cycles of calls between the components (addons) of a project that their `requiredAddons` do not
+//! declare: when the functions of a component call the ones of another, and the other way around
+use std::collections::HashMap;
+
+use super::Lint;
+use crate::call_graph::Graph;
+use crate::config::patches::Patch;
+
+/// A component of a project: the addons of its `CfgPatches` and the functions of its `CfgFunctions`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Component {
+    pub patches: Vec<Patch>,
+    pub functions: Vec<String>,
+}
+
+impl Component {
+    fn name(&self) -> String {
+        self.patches
+            .first()
+            .map(|x| x.name.inner.clone())
+            .unwrap_or_else(|| "(unnamed)".to_string())
+    }
+
+    fn requires(&self, other: &Component) -> bool {
+        self.patches
+            .iter()
+            .flat_map(|x| &x.required)
+            .any(|required| {
+                other
+                    .patches
+                    .iter()
+                    .any(|x| x.name.inner.eq_ignore_ascii_case(&required.inner))
+            })
+    }
+}
+
+/// Returns the lints of the component `components[index]`: for each component it calls without
+/// requiring it, and that calls it back
+pub fn cycles(graph: &Graph, components: &[Component], index: usize) -> Vec<Lint> {
+    let component_of = components
+        .iter()
+        .enumerate()
+        .flat_map(|(i, x)| x.functions.iter().map(move |f| (f.to_ascii_lowercase(), i)))
+        .collect::<HashMap<_, _>>();
+    // an example of call from a component to another
+    let mut calls = HashMap::<(usize, usize), (&str, &str)>::new();
+    for edge in &graph.edges {
+        let from = component_of.get(&edge.from.to_ascii_lowercase());
+        let to = component_of.get(&edge.to.to_ascii_lowercase());
+        if let (Some(from), Some(to)) = (from, to) {
+            if from != to {
+                calls.entry((*from, *to)).or_insert((&edge.from, &edge.to));
+            }
+        }
+    }
+
+    let component = &components[index];
+    let Some(patch) = component.patches.first() else {
+        return vec![];
+    };
+    let span = patch.required_span.unwrap_or(patch.name.span);
+    let mut others = calls
+        .keys()
+        .filter(|(from, _)| *from == index)
+        .map(|(_, to)| *to)
+        .collect::<Vec<_>>();
+    others.sort();
+    others
+        .into_iter()
+        .filter(|other| !component.requires(&components[*other]))
+        .filter_map(|other| {
+            let (from, to) = calls.get(&(index, other))?;
+            let (back_from, back_to) = calls.get(&(other, index))?;
+            Some(Lint {
+                code: "dependency-cycle",
+                span,
+                message: format!(
+                    "This addon calls \"{}\" ({from} calls {to}), which calls it back ({back_from} calls {back_to}), but does not require it in `requiredAddons`",
+                    components[other].name(),
+                ),
+            })
+        })
+        .collect()
+}
+
+/// Macros of CBA referring to another component of the same mod, e.g. `EFUNC(common,log)`
+const COMPONENT_MACROS: &[&str] = &["EFUNC", "QEFUNC", "EGVAR", "QEGVAR", "QQEGVAR", "LINKEFUNC"];
+
+/// Returns the components referred to by the macros of CBA of a text, e.g. `common` in
+/// `EFUNC(common,log)`
+pub fn macro_components(text: &str) -> Vec<String> {
+    let mut components = vec![];
+    for macro_ in COMPONENT_MACROS {
+        let pattern = format!("{macro_}(");
+        for (start, _) in text.match_indices(&pattern) {
+            // not the end of another identifier, e.g. `QEFUNC(` for `EFUNC(`
+            let previous = text[..start].chars().next_back();
+            if previous.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                continue;
+            }
+            let arguments = &text[start + pattern.len()..];
+            if let Some((component, _)) = arguments.split_once(',') {
+                let component = component.trim();
+                if !component.is_empty() && !components.iter().any(|x| x == component) {
+                    components.push(component.to_string());
+                }
+            }
+        }
+    }
+    components
+}
+
+/// An addon used by the scripts of a component: the names of its patches, and why it is used
+#[derive(Debug, Clone, PartialEq)]
+pub struct Usage {
+    pub patches: Vec<String>,
+    pub reason: String,
+}
+
+/// Returns the addons used by `component` that its `requiredAddons[]` lack, and the addons of the
+/// project (of patches `project`) it requires without using them
+pub fn requirements(component: &Component, usages: &[Usage], project: &[String]) -> Vec<Lint> {
+    let Some(patch) = component.patches.first() else {
+        return vec![];
+    };
+    let own = |name: &str| {
+        component
+            .patches
+            .iter()
+            .any(|x| x.name.inner.eq_ignore_ascii_case(name))
+    };
+    let required = component
+        .patches
+        .iter()
+        .flat_map(|x| &x.required)
+        .collect::<Vec<_>>();
+    let is_required = |name: &String| required.iter().any(|x| x.inner.eq_ignore_ascii_case(name));
+
+    let mut lints = vec![];
+    let mut reported: Vec<&str> = vec![];
+    for usage in usages {
+        let Some(name) = usage.patches.first() else {
+            continue;
+        };
+        if usage.patches.iter().any(|x| own(x) || is_required(x)) || reported.contains(&&**name) {
+            continue;
+        }
+        reported.push(name);
+        lints.push(Lint {
+            code: "missing-required-addon",
+            span: patch.required_span.unwrap_or(patch.name.span),
+            message: format!(
+                "This addon uses \"{name}\" ({}), which is missing from `requiredAddons`",
+                usage.reason
+            ),
+        });
+    }
+    for required in required {
+        let is_project = project
+            .iter()
+            .any(|x| x.eq_ignore_ascii_case(&required.inner));
+        let is_used = usages.iter().any(|usage| {
+            usage
+                .patches
+                .iter()
+                .any(|x| x.eq_ignore_ascii_case(&required.inner))
+        });
+        if is_project && !is_used {
+            lints.push(Lint {
+                code: "unused-required-addon",
+                span: required.span,
+                message: format!(
+                    "\"{}\" is required, but no script of this addon uses it",
+                    required.inner
+                ),
+            });
+        }
+    }
+    lints
+}
@@ -0,0 +1,202 @@
+//! Index of the events raised and handled via `publicVariable` and CBA's event system
+use std::collections::HashMap;
+
+use sqf::span::{Span, Spanned};
+
+use super::Lint;
+use crate::tokens;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    /// `publicVariable "name"` and `"name" addPublicVariableEventHandler {}`
+    PublicVariable,
+    /// `["name", {}] call CBA_fnc_addEventHandler` and `["name", []] call CBA_fnc_localEvent`
+    Cba,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Raise,
+    Handle,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventUsage {
+    pub kind: Kind,
+    pub name: String,
+    pub access: Access,
+    pub span: Span,
+}
+
+/// unary commands raising a public variable event
+const PUBLIC_VARIABLE_UNARY: &[&str] = &["publicVariable", "publicVariableServer"];
+/// binary commands raising a public variable event
+const PUBLIC_VARIABLE_BINARY: &[&str] = &["publicVariableClient"];
+
+/// CBA functions raising an event, called as `["name", ...] call CBA_fnc_x`
+const CBA_RAISE: &[&str] = &[
+    "CBA_fnc_localEvent",
+    "CBA_fnc_globalEvent",
+    "CBA_fnc_globalEventJIP",
+    "CBA_fnc_serverEvent",
+    "CBA_fnc_targetEvent",
+    "CBA_fnc_ownerEvent",
+    "CBA_fnc_turretEvent",
+];
+
+/// CBA functions handling an event, called as `["name", {}] call CBA_fnc_x`
+const CBA_HANDLE: &[&str] = &["CBA_fnc_addEventHandler", "CBA_fnc_addEventHandlerArgs"];
+
+/// Number of raises and handlers of each (kind, lowercase name) over a project
+pub type Index = HashMap<(Kind, String), (usize, usize)>;
+
+fn is_any(token: &str, commands: &[&str]) -> bool {
+    commands.iter().any(|x| x.eq_ignore_ascii_case(token))
+}
+
+/// Returns the first element of the array that ends right before `end` (exclusive), if it is a string
+fn first_of_array_before<'a>(terms: &[Spanned<&'a str>], end: usize) -> Option<Spanned<&'a str>> {
+    if terms.get(end.checked_sub(1)?)?.inner != "]" {
+        return None;
+    }
+    let mut depth = 0usize;
+    for i in (0..end).rev() {
+        match terms[i].inner {
+            "]" => depth += 1,
+            "[" => {
+                depth -= 1;
+                if depth == 0 {
+                    let first = terms.get(i + 1)?;
+                    return tokens::is_string(first.inner).then_some(*first);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn usage(name: Spanned<&str>, kind: Kind, access: Access) -> Option<EventUsage> {
+    Some(EventUsage {
+        kind,
+        name: tokens::unquote(name.inner)?,
+        access,
+        span: name.span,
+    })
+}
+
+/// Returns the events raised and handled with literal names
+pub fn usages(terms: &[Spanned<&str>]) -> Vec<EventUsage> {
+    terms
+        .iter()
+        .enumerate()
+        .filter_map(|(i, term)| {
+            let previous = || i.checked_sub(1).and_then(|i| terms.get(i)).copied();
+            let next = || terms.get(i + 1).copied();
+            if is_any(term.inner, PUBLIC_VARIABLE_UNARY)
+                || is_any(term.inner, PUBLIC_VARIABLE_BINARY)
+            {
+                usage(next()?, Kind::PublicVariable, Access::Raise)
+            } else if term
+                .inner
+                .eq_ignore_ascii_case("addPublicVariableEventHandler")
+            {
+                usage(previous()?, Kind::PublicVariable, Access::Handle)
+            } else if is_any(term.inner, CBA_RAISE) || is_any(term.inner, CBA_HANDLE) {
+                if !previous()?.inner.eq_ignore_ascii_case("call") {
+                    return None;
+                }
+                let access = if is_any(term.inner, CBA_RAISE) {
+                    Access::Raise
+                } else {
+                    Access::Handle
+                };
+                usage(first_of_array_before(terms, i - 1)?, Kind::Cba, access)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Adds usages to the index
+pub fn extend<'a>(index: &mut Index, usages: impl Iterator<Item = &'a EventUsage>) {
+    for usage in usages {
+        let entry = index
+            .entry((usage.kind, usage.name.to_lowercase()))
+            .or_default();
+        match usage.access {
+            Access::Raise => entry.0 += 1,
+            Access::Handle => entry.1 += 1,
+        }
+    }
+}
+
+/// Returns the events of `usages` that, over the whole project (`index`), are handled but never
+/// raised, or (for CBA events) raised but never handled.
+/// Public variables are often broadcasted without handlers and are thus not reported when unhandled.
+pub fn lint(usages: &[EventUsage], index: &Index) -> Vec<Lint> {
+    usages
+        .iter()
+        .filter_map(|usage| {
+            let (raises, handlers) = index
+                .get(&(usage.kind, usage.name.to_lowercase()))
+                .copied()
+                .unwrap_or_default();
+            let (code, message) = match (usage.access, usage.kind) {
+                (Access::Handle, _) if raises == 0 => (
+                    "event-never-raised",
+                    format!(
+                        "The event \"{}\" is handled but never raised in the project",
+                        usage.name
+                    ),
+                ),
+                (Access::Raise, Kind::Cba) if handlers == 0 => (
+                    "event-never-handled",
+                    format!(
+                        "The event \"{}\" is raised but never handled in the project",
+                        usage.name
+                    ),
+                ),
+                _ => return None,
+            };
+            Some(Lint {
+                code,
+                span: usage.span,
+                message,
+            })
+        })
+        .collect()
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// If the cursor, at the end of `before` and followed by `after` in the same line, is inside the
+/// name of an event, returns the kind of event.
+/// This is text-based since the document is often incomplete while the name is being typed.
+pub fn event_kind_at(before: &str, after: &str) -> Option<Kind> {
+    let quote = before.rfind(['"', '\''])?;
+    if before[..quote].matches(['"', '\'']).count() % 2 == 1 {
+        // the cursor is after a closed string
+        return None;
+    }
+    let prefix = before[..quote].trim_end();
+    if prefix.ends_with('[') {
+        // `["name", ...] call CBA_fnc_x`
+        let after = after.to_lowercase();
+        let is_cba = CBA_RAISE
+            .iter()
+            .chain(CBA_HANDLE.iter())
+            .any(|x| after.contains(&x.to_lowercase()));
+        return is_cba.then_some(Kind::Cba);
+    }
+    let command_start = prefix
+        .rfind(|c: char| !is_word(c))
+        .map(|x| x + 1)
+        .unwrap_or(0);
+    let command = &prefix[command_start..];
+    (is_any(command, PUBLIC_VARIABLE_UNARY) || is_any(command, PUBLIC_VARIABLE_BINARY))
+        .then_some(Kind::PublicVariable)
+}
@@ -0,0 +1,83 @@
+//! Index of the references to functions declared in `CfgFunctions`, including the ones by name in
+//! strings (e.g. `remoteExec ["TAG_fnc_name"]`, `kbAddTopic` or `call compile "..."`)
+use std::collections::HashSet;
+
+use sqf::span::Spanned;
+
+use super::Lint;
+use crate::config::functions::Declaration;
+use crate::{text_pos, tokens};
+
+/// The names of the functions referred to over a project, lowercase
+pub type Index = HashSet<String>;
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn is_function(name: &str) -> bool {
+    name.to_ascii_lowercase().contains("_fnc_")
+}
+
+/// The words of `text` and their byte offset
+fn words(text: &str) -> Vec<(usize, &str)> {
+    let mut words = vec![];
+    let mut start = None;
+    for (index, c) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        if is_word(c) {
+            start.get_or_insert(index);
+        } else if let Some(start) = start.take() {
+            words.push((start, &text[start..index]));
+        }
+    }
+    words
+}
+
+/// Returns the references to functions in the terms of a file: the variables named like a function
+/// and the words of strings named like one
+pub fn usages(terms: &[Spanned<&str>]) -> Vec<Spanned<String>> {
+    terms
+        .iter()
+        .flat_map(|term| {
+            if !tokens::is_string(term.inner) {
+                return is_function(term.inner)
+                    .then(|| Spanned::new(term.inner.to_string(), term.span))
+                    .into_iter()
+                    .collect::<Vec<_>>();
+            }
+            words(term.inner)
+                .into_iter()
+                .filter(|(_, word)| is_function(word))
+                .filter_map(|(index, word)| {
+                    let start = term.span.0 + text_pos::byte_to_char(term.inner, index)?;
+                    Some(Spanned::new(
+                        word.to_string(),
+                        (start, start + word.chars().count()),
+                    ))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Adds the references of a file to the index
+pub fn extend<'a>(index: &mut Index, usages: impl Iterator<Item = &'a Spanned<String>>) {
+    index.extend(usages.map(|x| x.inner.to_lowercase()));
+}
+
+/// Lints the functions declared in a config that are never referred to over the project. The ones
+/// called by the engine at initialization (`preInit`, `postInit`, `preStart`) are used.
+pub fn unused(declarations: &[Declaration], index: &Index) -> Vec<Lint> {
+    declarations
+        .iter()
+        .filter(|x| !x.init && !index.contains(&x.name.to_lowercase()))
+        .map(|x| Lint {
+            code: "unused-function",
+            span: x.class.span,
+            message: format!("The function \"{}\" is never used in the project", x.name),
+        })
+        .collect()
+}
@@ -0,0 +1,150 @@
+//! Literal indices out of the range of arrays whose size is known: literal arrays, and local
+//! variables assigned a literal array and only read afterwards
+use std::collections::HashMap;
+
+use sqf::span::Spanned;
+
+use super::Lint;
+use crate::tokens;
+
+/// Commands reading an element of an array by its index
+const COMMANDS: &[&str] = &["select", "param", "#"];
+
+/// Commands that read an array without modifying it
+const READS: &[&str] = &["select", "param", "#", "count"];
+
+fn is_one_of(term: &str, commands: &[&str]) -> bool {
+    commands.iter().any(|x| x.eq_ignore_ascii_case(term))
+}
+
+/// The index of the `[` matching the `]` at `end`
+fn array_start(terms: &[Spanned<&str>], end: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for i in (0..=end).rev() {
+        match terms[i].inner {
+            "]" | ")" | "}" => depth += 1,
+            "[" | "(" | "{" => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return (terms[i].inner == "[").then_some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The index of the `]` matching the `[` at `start`
+fn array_end(terms: &[Spanned<&str>], start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, term) in terms.iter().enumerate().skip(start) {
+        match term.inner {
+            "[" | "(" | "{" => depth += 1,
+            "]" | ")" | "}" => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The size of the literal array starting at `start`, if it is a whole operand
+fn literal_size(terms: &[Spanned<&str>], start: usize) -> Option<usize> {
+    let is_operand = start
+        .checked_sub(1)
+        .map(|i| matches!(terms[i].inner, ";" | "," | "(" | "[" | "{" | "="))
+        .unwrap_or(true);
+    is_operand.then(|| tokens::elements(terms, start).len())
+}
+
+/// The size of the arrays assigned to local variables that are assigned once and only read by
+/// [`READS`] afterwards
+fn variable_sizes(terms: &[Spanned<&str>]) -> HashMap<String, usize> {
+    let mut sizes = HashMap::<String, Option<usize>>::new();
+    for (i, term) in terms.iter().enumerate() {
+        if !term.inner.starts_with('_') {
+            continue;
+        }
+        let name = term.inner.to_ascii_lowercase();
+        let is_assignment = terms.get(i + 1).is_some_and(|x| x.inner == "=");
+        let is_read = terms.get(i + 1).is_some_and(|x| is_one_of(x.inner, READS));
+        match (sizes.get(&name), is_assignment) {
+            (None, true) => {
+                let size = terms
+                    .get(i + 2)
+                    .filter(|x| x.inner == "[")
+                    .and_then(|_| literal_size(terms, i + 2))
+                    .filter(|_| {
+                        // the whole value is the array
+                        array_end(terms, i + 2)
+                            .and_then(|end| terms.get(end + 1))
+                            .is_some_and(|x| x.inner == ";")
+                    });
+                sizes.insert(name, size);
+            }
+            (Some(_), _) if is_assignment || !is_read => {
+                sizes.insert(name, None);
+            }
+            // first read before being assigned, e.g. a variable of the caller
+            (None, false) => {
+                sizes.insert(name, None);
+            }
+            _ => {}
+        }
+    }
+    sizes
+        .into_iter()
+        .filter_map(|(name, size)| Some((name, size?)))
+        .collect()
+}
+
+/// Returns the literal indices out of the range of arrays whose size is known
+pub fn lint(terms: &[Spanned<&str>]) -> Vec<Lint> {
+    let sizes = variable_sizes(terms);
+    terms
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(_, term)| is_one_of(term.inner, COMMANDS))
+        .filter_map(|(i, command)| {
+            let receiver = &terms[i - 1];
+            let size = if receiver.inner == "]" {
+                literal_size(terms, array_start(terms, i - 1)?)?
+            } else {
+                *sizes.get(&receiver.inner.to_ascii_lowercase())?
+            };
+            // `param [index, default]` or `select index`
+            let index = if command.inner.eq_ignore_ascii_case("param") {
+                terms.get(i + 1).filter(|x| x.inner == "[")?;
+                terms.get(i + 2)?
+            } else {
+                terms.get(i + 1)?
+            };
+            let value = index.inner.parse::<usize>().ok()?;
+            let valid = match size {
+                0 => "the array is empty".to_string(),
+                1 => "the only valid index is 0".to_string(),
+                size => format!("valid indices are 0 to {}", size - 1),
+            };
+            let consequence = if command.inner.eq_ignore_ascii_case("param") {
+                "always returns its default value"
+            } else {
+                "is out of range"
+            };
+            (value >= size).then(|| Lint {
+                code: "index-out-of-range",
+                span: index.span,
+                message: format!(
+                    "`{} {value}` {consequence}: the array has {size} element{}, {valid}",
+                    command.inner,
+                    if size == 1 { "" } else { "s" }
+                ),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,113 @@
+//! Code that may never end, freezing the game when run unscheduled: `while {true}` loops that never
+//! suspend nor exit, and functions calling themselves unconditionally
+use sqf::span::Spanned;
+
+use super::Lint;
+
+/// Commands that suspend or exit a loop
+const LOOP_EXITS: &[&str] = &[
+    "sleep",
+    "uiSleep",
+    "waitUntil",
+    "breakOut",
+    "breakTo",
+    "break",
+    "exitWith",
+    "throw",
+    "terminate",
+];
+
+/// Terms making the execution of code conditional
+const CONDITIONALS: &[&str] = &[
+    "if",
+    "exitWith",
+    "switch",
+    "breakOut",
+    "breakTo",
+    "throw",
+    "&&",
+    "||",
+    "and",
+    "or",
+    "while",
+    "for",
+    "forEach",
+    "count",
+    "apply",
+    "select",
+    "findIf",
+    "waitUntil",
+];
+
+fn is_any(term: &str, list: &[&str]) -> bool {
+    list.iter().any(|x| x.eq_ignore_ascii_case(term))
+}
+
+/// The index of the term closing the bracket at `start`
+fn closing(terms: &[Spanned<&str>], start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, term) in terms.iter().enumerate().skip(start) {
+        match term.inner {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the `while {true} do {...}` whose body never suspends nor exits
+pub fn infinite_loops(terms: &[Spanned<&str>]) -> Vec<Lint> {
+    terms
+        .windows(4)
+        .enumerate()
+        .filter(|(_, x)| {
+            x[0].inner.eq_ignore_ascii_case("while")
+                && x[1].inner == "{"
+                && x[2].inner.eq_ignore_ascii_case("true")
+                && x[3].inner == "}"
+        })
+        .filter_map(|(i, x)| {
+            terms
+                .get(i + 4)
+                .filter(|x| x.inner.eq_ignore_ascii_case("do"))?;
+            let start = i + 5;
+            terms.get(start).filter(|x| x.inner == "{")?;
+            let end = closing(terms, start)?;
+            let exits = terms[start..end]
+                .iter()
+                .any(|x| is_any(x.inner, LOOP_EXITS));
+            (!exits).then(|| Lint {
+                code: "infinite-loop",
+                span: (x[0].span.0, x[3].span.1),
+                message: "This loop never ends: its body never suspends (`sleep`, `waitUntil`) nor exits (`breakOut`, `exitWith`), which freezes the game when run unscheduled".to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Returns the calls of the script of the functions `functions` to themselves, when the script has
+/// no conditional code that could stop the recursion
+pub fn recursion(terms: &[Spanned<&str>], functions: &[String]) -> Vec<Lint> {
+    if functions.is_empty() || terms.iter().any(|x| is_any(x.inner, CONDITIONALS)) {
+        return vec![];
+    }
+    terms
+        .windows(2)
+        .filter(|x| x[0].inner.eq_ignore_ascii_case("call"))
+        .filter(|x| functions.iter().any(|f| f.eq_ignore_ascii_case(x[1].inner)))
+        .map(|x| Lint {
+            code: "unconditional-recursion",
+            span: x[1].span,
+            message: format!(
+                "\"{}\" calls itself unconditionally, and never returns",
+                x[1].inner
+            ),
+        })
+        .collect()
+}
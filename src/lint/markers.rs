@@ -0,0 +1,114 @@
+use std::collections::HashSet;
+
+use sqf::span::Spanned;
+
+use super::Lint;
+use crate::mission::{EntityKind, MissionSqm};
+use crate::tokens;
+
+/// unary commands whose argument is the name of a marker
+const UNARY: &[&str] = &[
+    "deleteMarker",
+    "deleteMarkerLocal",
+    "getMarkerColor",
+    "getMarkerPos",
+    "getMarkerSize",
+    "getMarkerType",
+    "markerAlpha",
+    "markerBrush",
+    "markerChannel",
+    "markerColor",
+    "markerDir",
+    "markerPolyline",
+    "markerPos",
+    "markerShadow",
+    "markerShape",
+    "markerSize",
+    "markerText",
+    "markerType",
+];
+
+/// binary commands whose left argument is the name of a marker
+const BINARY: &[&str] = &[
+    "setMarkerAlpha",
+    "setMarkerAlphaLocal",
+    "setMarkerBrush",
+    "setMarkerBrushLocal",
+    "setMarkerColor",
+    "setMarkerColorLocal",
+    "setMarkerDir",
+    "setMarkerDirLocal",
+    "setMarkerPolyline",
+    "setMarkerPolylineLocal",
+    "setMarkerPos",
+    "setMarkerPosLocal",
+    "setMarkerShadow",
+    "setMarkerShadowLocal",
+    "setMarkerShape",
+    "setMarkerShapeLocal",
+    "setMarkerSize",
+    "setMarkerSizeLocal",
+    "setMarkerText",
+    "setMarkerTextLocal",
+    "setMarkerType",
+    "setMarkerTypeLocal",
+];
+
+/// commands that create markers, `createMarker ["name", position]`
+const CREATE: &[&str] = &["createMarker", "createMarkerLocal"];
+
+fn is_any(token: &str, commands: &[&str]) -> bool {
+    commands.iter().any(|x| x.eq_ignore_ascii_case(token))
+}
+
+/// Returns the string literal right after `index`, skipping an opening `[` or `(`
+fn string_after<'a>(terms: &[Spanned<&'a str>], index: usize) -> Option<Spanned<&'a str>> {
+    let mut next = terms.get(index + 1)?;
+    if next.inner == "[" || next.inner == "(" {
+        next = terms.get(index + 2)?;
+    }
+    tokens::is_string(next.inner).then_some(*next)
+}
+
+/// Returns references to markers by name (e.g. `getMarkerPos "marker_1"`) that do not exist in
+/// `mission.sqm` nor are created via `createMarker` in the same file.
+pub fn lint(terms: &[Spanned<&str>], mission: &MissionSqm) -> Vec<Lint> {
+    let created = terms
+        .iter()
+        .enumerate()
+        .filter(|(_, term)| is_any(term.inner, CREATE))
+        .filter_map(|(i, _)| string_after(terms, i))
+        .filter_map(|name| tokens::unquote(name.inner))
+        .map(|name| name.to_lowercase())
+        .collect::<HashSet<_>>();
+
+    let references = terms.iter().enumerate().filter_map(|(i, term)| {
+        if is_any(term.inner, UNARY) {
+            string_after(terms, i)
+        } else if is_any(term.inner, BINARY) {
+            let previous = terms.get(i.checked_sub(1)?)?;
+            tokens::is_string(previous.inner).then_some(*previous)
+        } else {
+            None
+        }
+    });
+
+    references
+        .filter_map(|reference| {
+            let name = tokens::unquote(reference.inner)?;
+            if name.is_empty()
+                || created.contains(&name.to_lowercase())
+                || mission.get(&name, EntityKind::Marker).is_some()
+            {
+                return None;
+            }
+            Some(Lint {
+                code: "unknown-marker",
+                span: reference.span,
+                message: format!(
+                    "The marker \"{name}\" is not placed in the mission nor created in this file"
+                ),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,57 @@
+//! Naming conventions of the project (see [`crate::project::Naming`])
+use sqf::span::Spanned;
+
+use super::Lint;
+
+fn is_identifier(token: &str) -> bool {
+    let mut chars = token.chars();
+    chars
+        .next()
+        .map(|c| c.is_alphabetic() || c == '_')
+        .unwrap_or(false)
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Returns the global variables assigned (`name = ...`) whose name does not start with any of `prefixes`
+pub fn lint(terms: &[Spanned<&str>], prefixes: &[String]) -> Vec<Lint> {
+    if prefixes.is_empty() {
+        return vec![];
+    }
+    terms
+        .iter()
+        .enumerate()
+        .filter(|(i, term)| {
+            // an assignment is a statement, i.e. at the start or after `;`, `,`, `{` or `(`
+            let is_statement = i
+                .checked_sub(1)
+                .and_then(|i| terms.get(i))
+                .map(|x| matches!(x.inner, ";" | "," | "{" | "("))
+                .unwrap_or(true);
+            is_statement
+                && terms.get(i + 1).map(|x| x.inner == "=").unwrap_or(false)
+                && is_identifier(term.inner)
+                && !term.inner.starts_with('_')
+        })
+        .filter(|(_, term)| {
+            !prefixes.iter().any(|prefix| {
+                term.inner
+                    .get(..prefix.len())
+                    .map(|x| x.eq_ignore_ascii_case(prefix))
+                    .unwrap_or(false)
+            })
+        })
+        .map(|(_, term)| Lint {
+            code: "naming-convention",
+            span: term.span,
+            message: format!(
+                "The global variable \"{}\" does not start with {}",
+                term.inner,
+                prefixes
+                    .iter()
+                    .map(|x| format!("\"{x}\""))
+                    .collect::<Vec<_>>()
+                    .join(" or ")
+            ),
+        })
+        .collect()
+}
@@ -0,0 +1,141 @@
+//! `switch do` constructs: their duplicated `case` values and missing `default` branch
+use sqf::span::{Span, Spanned};
+
+use super::Lint;
+use crate::tokens;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Case {
+    /// the literal value, as written
+    pub value: Spanned<String>,
+    /// the value compared to the ones of the other cases: unquoted strings and parsed numbers
+    pub key: String,
+    /// span of the whole statement, from `case` to its `;` (if any)
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Switch {
+    /// span of `switch`
+    pub keyword: Span,
+    /// span of the `}` closing the body
+    pub end: Span,
+    pub cases: Vec<Case>,
+    pub default: Option<Span>,
+}
+
+/// The index of the term closing the bracket at `start`
+fn closing(terms: &[Spanned<&str>], start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, term) in terms.iter().enumerate().skip(start) {
+        match term.inner {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The value of a literal (number or string) comparable to others
+fn literal(term: &str) -> Option<String> {
+    tokens::unquote(term)
+        .map(|x| format!("string {x}"))
+        .or_else(|| term.parse::<f64>().ok().map(|x| format!("number {x}")))
+}
+
+/// Returns the `switch (...) do {...}` of the terms of a file
+pub fn switches(terms: &[Spanned<&str>]) -> Vec<Switch> {
+    terms
+        .iter()
+        .enumerate()
+        .filter(|(_, term)| term.inner.eq_ignore_ascii_case("switch"))
+        .filter_map(|(i, keyword)| {
+            let condition_end = closing(terms, i + 1)?;
+            terms
+                .get(condition_end + 1)
+                .filter(|x| x.inner.eq_ignore_ascii_case("do"))?;
+            let start = condition_end + 2;
+            terms.get(start).filter(|x| x.inner == "{")?;
+            let end = closing(terms, start)?;
+
+            let mut switch = Switch {
+                keyword: keyword.span,
+                end: terms[end].span,
+                cases: vec![],
+                default: None,
+            };
+            let mut j = start + 1;
+            while j < end {
+                let term = terms[j];
+                let statement_end = if matches!(term.inner, "(" | "[" | "{") {
+                    closing(terms, j)?
+                } else {
+                    j
+                };
+                if term.inner.eq_ignore_ascii_case("default") {
+                    switch.default = Some(term.span);
+                } else if term.inner.eq_ignore_ascii_case("case") {
+                    // until the `;` ending the statement, or the end of the body
+                    let mut k = j + 1;
+                    while k < end && terms[k].inner != ";" {
+                        if matches!(terms[k].inner, "(" | "[" | "{") {
+                            k = closing(terms, k)?;
+                        }
+                        k += 1;
+                    }
+                    let last = k.min(end - 1);
+                    if let Some((value, key)) =
+                        terms.get(j + 1).and_then(|x| Some((x, literal(x.inner)?)))
+                    {
+                        switch.cases.push(Case {
+                            value: Spanned::new(value.inner.to_string(), value.span),
+                            key,
+                            span: (term.span.0, terms[last].span.1),
+                        });
+                    }
+                    j = k;
+                    continue;
+                }
+                j = statement_end + 1;
+            }
+            Some(switch)
+        })
+        .collect()
+}
+
+/// Returns the duplicated `case` values and, if `require_default`, the switches without `default`
+pub fn lint(terms: &[Spanned<&str>], require_default: bool) -> Vec<Lint> {
+    switches(terms)
+        .into_iter()
+        .flat_map(|switch| {
+            let duplicates = switch
+                .cases
+                .iter()
+                .enumerate()
+                .filter_map(|(i, case)| {
+                    switch.cases[..i].iter().find(|x| x.key == case.key)?;
+                    Some(Lint {
+                        code: "duplicate-case",
+                        span: case.value.span,
+                        message: format!(
+                            "The case {} is never reached: a previous case of this switch has the same value",
+                            case.value.inner
+                        ),
+                    })
+                })
+                .collect::<Vec<_>>();
+            let missing_default = (require_default && switch.default.is_none()).then(|| Lint {
+                code: "missing-switch-default",
+                span: switch.keyword,
+                message: "This switch has no default branch".to_string(),
+            });
+            duplicates.into_iter().chain(missing_default)
+        })
+        .collect()
+}
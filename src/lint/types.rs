@@ -0,0 +1,102 @@
+//! Binary commands and operators applied to operands whose types, inferred by the analysis, match
+//! none of their signatures (e.g. `"a" + 1`, or a group where an object is expected)
+use std::collections::HashMap;
+
+use sqf::analyzer::BINARY;
+use sqf::span::{Span, Spanned};
+use sqf::types::Type;
+use sqf::UncasedStr;
+
+use super::Lint;
+
+/// Terms around an operand made of a single term or bracketed group, so that no operator of a
+/// higher precedence takes part of it
+fn is_boundary(term: Option<&Spanned<&str>>) -> bool {
+    term.map(|x| matches!(x.inner, ";" | "," | "(" | ")" | "[" | "]" | "{" | "}" | "="))
+        .unwrap_or(true)
+}
+
+/// The index of the bracket matching the one at `i`, searching forward or backward
+fn matching(terms: &[Spanned<&str>], i: usize, forward: bool) -> Option<usize> {
+    let mut depth = 0isize;
+    let mut i = i as isize;
+    while let Some(term) = terms.get(usize::try_from(i).ok()?) {
+        match term.inner {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return Some(i as usize);
+        }
+        i += if forward { 1 } else { -1 };
+    }
+    None
+}
+
+/// The span and type of the operand ending (or starting) at `i`, and the index of its other end
+fn operand(
+    terms: &[Spanned<&str>],
+    types: &HashMap<Span, Option<Type>>,
+    i: usize,
+    forward: bool,
+) -> Option<(usize, Type)> {
+    let term = terms.get(i)?;
+    let other = if matches!(term.inner, "(" | "[" | "{" | ")" | "]" | "}") {
+        matching(terms, i, forward)?
+    } else {
+        i
+    };
+    let (start, end) = if forward { (i, other) } else { (other, i) };
+    let type_ = (*types.get(&(terms[start].span.0, terms[end].span.1))?)?;
+    Some((other, type_))
+}
+
+/// Whether the signature `(left, right)` accepts the operands
+fn accepts(signature: (Type, Type), left: Type, right: Type) -> bool {
+    (signature.0 == left || signature.0 == Type::Anything)
+        && (signature.1 == right || signature.1 == Type::Anything)
+}
+
+/// Returns the binary commands whose operands, of known types, match none of their signatures
+pub fn lint(terms: &[Spanned<&str>], types: &HashMap<Span, Option<Type>>) -> Vec<Lint> {
+    terms
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter_map(|(i, command)| {
+            let signatures = BINARY.get(UncasedStr::new(command.inner))?;
+            let (left_start, left) = operand(terms, types, i - 1, false)?;
+            let (right_end, right) = operand(terms, types, i + 1, true)?;
+            let is_unknown = |x: Type| matches!(x, Type::Anything | Type::Nothing);
+            if is_unknown(left) || is_unknown(right) {
+                return None;
+            }
+            // the operands are whole
+            if !is_boundary(left_start.checked_sub(1).and_then(|x| terms.get(x)))
+                || !is_boundary(terms.get(right_end + 1))
+            {
+                return None;
+            }
+            if signatures.keys().any(|x| accepts(*x, left, right)) {
+                return None;
+            }
+            let mut expected = signatures.keys().copied().collect::<Vec<_>>();
+            expected.sort();
+            let expected = expected
+                .iter()
+                .take(4)
+                .map(|(l, r)| format!("{l:?} {} {r:?}", command.inner))
+                .collect::<Vec<_>>()
+                .join(", ");
+            Some(Lint {
+                code: "type-mismatch",
+                span: command.span,
+                message: format!(
+                    "`{}` cannot be applied to {left:?} (left) and {right:?} (right); expected {expected}",
+                    command.inner
+                ),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,180 @@
+//! Tracking of the keys used with `setVariable` and `getVariable` on each namespace
+use std::collections::HashMap;
+
+use sqf::span::{Span, Spanned};
+
+use super::Lint;
+use crate::tokens;
+
+const NAMESPACES: &[&str] = &[
+    "missionNamespace",
+    "missionProfileNamespace",
+    "uiNamespace",
+    "profileNamespace",
+    "parsingNamespace",
+    "serverNamespace",
+    "localNamespace",
+];
+
+/// namespace used for keys set on objects, groups, locations, etc.
+const OBJECT: &str = "object";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyUsage {
+    /// the (lowercase) namespace, e.g. `missionnamespace` or `object`
+    pub namespace: String,
+    pub key: String,
+    pub access: Access,
+    pub span: Span,
+}
+
+/// Which keys are reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    #[default]
+    Off,
+    /// keys that are read but never written
+    Read,
+    /// keys that are written but never read
+    Written,
+    Both,
+}
+
+impl Mode {
+    pub fn from_setting(value: &str) -> Self {
+        match value {
+            "read" => Mode::Read,
+            "written" => Mode::Written,
+            "both" => Mode::Both,
+            _ => Mode::Off,
+        }
+    }
+}
+
+/// Number of reads and writes of each (namespace, lowercase key) over a project
+pub type Index = HashMap<(String, String), (usize, usize)>;
+
+fn namespace(term: Option<&str>) -> String {
+    term.and_then(|term| {
+        NAMESPACES
+            .iter()
+            .find(|x| x.eq_ignore_ascii_case(term))
+            .map(|x| x.to_lowercase())
+    })
+    .unwrap_or_else(|| OBJECT.to_string())
+}
+
+/// Returns the usages of keys with `setVariable` and `getVariable` with literal strings
+pub fn usages(terms: &[Spanned<&str>]) -> Vec<KeyUsage> {
+    terms
+        .iter()
+        .enumerate()
+        .filter_map(|(i, term)| {
+            let access = if term.inner.eq_ignore_ascii_case("setVariable") {
+                Access::Write
+            } else if term.inner.eq_ignore_ascii_case("getVariable") {
+                Access::Read
+            } else {
+                return None;
+            };
+            let mut key = terms.get(i + 1)?;
+            if key.inner == "[" {
+                key = terms.get(i + 2)?;
+            }
+            let value = tokens::unquote(key.inner)?;
+            Some(KeyUsage {
+                namespace: namespace(i.checked_sub(1).and_then(|i| terms.get(i)).map(|x| x.inner)),
+                key: value,
+                access,
+                span: key.span,
+            })
+        })
+        .collect()
+}
+
+/// Adds usages to the index
+pub fn extend<'a>(index: &mut Index, usages: impl Iterator<Item = &'a KeyUsage>) {
+    for usage in usages {
+        let entry = index
+            .entry((usage.namespace.clone(), usage.key.to_lowercase()))
+            .or_default();
+        match usage.access {
+            Access::Read => entry.0 += 1,
+            Access::Write => entry.1 += 1,
+        }
+    }
+}
+
+/// Returns the keys of `usages` that, over the whole project (`index`), are only ever read or only ever written
+pub fn lint(usages: &[KeyUsage], index: &Index, mode: Mode) -> Vec<Lint> {
+    usages
+        .iter()
+        .filter_map(|usage| {
+            let (reads, writes) = index
+                .get(&(usage.namespace.clone(), usage.key.to_lowercase()))
+                .copied()
+                .unwrap_or_default();
+            let (code, message) = match usage.access {
+                Access::Read if writes == 0 && matches!(mode, Mode::Read | Mode::Both) => (
+                    "variable-key-never-set",
+                    format!(
+                        "The key \"{}\" is read from {} but never set in the project",
+                        usage.key, usage.namespace
+                    ),
+                ),
+                Access::Write if reads == 0 && matches!(mode, Mode::Written | Mode::Both) => (
+                    "variable-key-never-read",
+                    format!(
+                        "The key \"{}\" is set on {} but never read in the project",
+                        usage.key, usage.namespace
+                    ),
+                ),
+                _ => return None,
+            };
+            Some(Lint {
+                code,
+                span: usage.span,
+                message,
+            })
+        })
+        .collect()
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// If `line` (the text of a line up to the cursor) ends inside the key argument of `setVariable`
+/// or `getVariable`, returns the (lowercase) namespace of the key.
+/// This is text-based since the document is often incomplete while the key is being typed.
+pub fn key_namespace_at(line: &str) -> Option<String> {
+    // the opening quote of the string being typed
+    let quote = line.rfind(['"', '\''])?;
+    if line[..quote].matches(['"', '\'']).count() % 2 == 1 {
+        // the cursor is after a closed string
+        return None;
+    }
+    let before = line[..quote].trim_end();
+    let before = before.strip_suffix('[').unwrap_or(before).trim_end();
+    let command_start = before
+        .rfind(|c: char| !is_word(c))
+        .map(|x| x + 1)
+        .unwrap_or(0);
+    let command = &before[command_start..];
+    if !(command.eq_ignore_ascii_case("setVariable") || command.eq_ignore_ascii_case("getVariable"))
+    {
+        return None;
+    }
+    let before = before[..command_start].trim_end();
+    let namespace_start = before
+        .rfind(|c: char| !is_word(c))
+        .map(|x| x + 1)
+        .unwrap_or(0);
+    Some(namespace(Some(&before[namespace_start..])))
+}
@@ -0,0 +1,35 @@
+//! Translations of the explanations of the commands shown by hover and completion, loaded from a
+//! JSON object mapping the name of a command to its explanation (e.g.
+//! `{"setDamage": "Définit les dégâts d'un objet"}`) and merged over the English ones
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The translated explanations, by lowercase name of command
+pub type Explanations = HashMap<String, String>;
+
+/// Parses a file of translations. Entries whose value is not a string are ignored.
+pub fn parse(content: &str) -> Result<Explanations, serde_json::Error> {
+    let entries = serde_json::from_str::<HashMap<String, serde_json::Value>>(content)?;
+    Ok(entries
+        .into_iter()
+        .filter_map(|(name, value)| Some((name.to_lowercase(), value.as_str()?.to_string())))
+        .collect())
+}
+
+/// Reads the translations of the file at `path`, or none (i.e. English) with `None`
+pub fn load(path: Option<&Path>) -> Result<Explanations, String> {
+    Ok(match path {
+        Some(path) => {
+            let content = std::fs::read_to_string(path)
+                .map_err(|error| format!("could not read {}: {error}", path.display()))?;
+            parse(&content)
+                .map_err(|error| format!("could not parse {}: {error}", path.display()))?
+        }
+        None => Default::default(),
+    })
+}
+
+/// The translated explanation of the command `name`, if any
+pub fn explanation(explanations: &Explanations, name: &str) -> Option<String> {
+    explanations.get(&name.to_lowercase()).cloned()
+}
@@ -0,0 +1,665 @@
+//! Macros defined with `#define` in a file and in the files it includes, i.e. its compilation
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use sqf::preprocessor::{Ast, Define};
+use sqf::span::{Span, Spanned};
+
+use crate::lint::Lint;
+use crate::{addon, analyze, include, text_pos, tokens};
+
+/// A `#define`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Macro {
+    pub name: String,
+    /// the span of its name
+    pub span: Span,
+    /// the file defining it, `None` for the file itself
+    pub path: Option<Arc<Path>>,
+    pub arguments: Option<Vec<String>>,
+    /// the body as written
+    pub body: String,
+}
+
+impl Macro {
+    /// The definition, as written in Markdown
+    pub fn markdown(&self) -> String {
+        let arguments = self
+            .arguments
+            .as_ref()
+            .map(|x| format!("({})", x.join(",")))
+            .unwrap_or_default();
+        let mut value = format!(
+            "```sqf\n#define {}{arguments} {}\n```",
+            self.name, self.body
+        );
+        if let Some(file_name) = self.path.as_ref().and_then(|x| x.file_name()) {
+            value.push_str(&format!("\n\nDefined in `{}`", file_name.to_string_lossy()));
+        }
+        value
+    }
+}
+
+/// The directives of a file
+#[derive(Debug, Default)]
+struct Directives<'a> {
+    defines: Vec<&'a Define<'a>>,
+    undefines: Vec<Spanned<&'a str>>,
+    /// the macros referenced by `#if`, `#ifdef` and `#ifndef`
+    conditions: Vec<Spanned<&'a str>>,
+}
+
+fn is_name(term: &str) -> bool {
+    term.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && term.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn recurse<'a>(ast: &'a Ast<'a>, directives: &mut Directives<'a>) {
+    match ast {
+        Ast::Ifdef(ifdef) | Ast::Ifndef(ifdef) => {
+            directives.conditions.push(ifdef.term);
+            for node in ifdef.then.iter().chain(ifdef.else_.iter()) {
+                recurse(node, directives)
+            }
+        }
+        Ast::If(if_) => {
+            for node in &if_.expr {
+                if let Ast::Term(term) = node {
+                    if is_name(term.inner) && term.inner != "defined" {
+                        directives.conditions.push(*term);
+                    }
+                }
+            }
+            for node in if_.then.iter().chain(if_.else_.iter()) {
+                recurse(node, directives)
+            }
+        }
+        Ast::Define(define) => directives.defines.push(define),
+        Ast::Undefine(_, name) => directives.undefines.push(*name),
+        _ => {}
+    }
+}
+
+fn directives<'a>(ast: &'a VecDeque<Ast<'a>>) -> Directives<'a> {
+    let mut directives = Directives::default();
+    for node in ast {
+        recurse(node, &mut directives);
+    }
+    directives
+}
+
+/// The text of `text` within `span` (in chars)
+fn slice(text: &str, (start, end): Span) -> &str {
+    let start = text_pos::char_to_byte(text, start).unwrap_or(text.len());
+    let end = text_pos::char_to_byte(text, end).unwrap_or(text.len());
+    text.get(start..end).unwrap_or_default()
+}
+
+fn to_macro(define: &Define, text: &str, path: Option<Arc<Path>>) -> Macro {
+    Macro {
+        name: define.name.inner.to_string(),
+        span: define.name.span,
+        path,
+        arguments: define
+            .arguments
+            .as_ref()
+            .map(|x| x.iter().map(|x| x.inner.to_string()).collect()),
+        body: match (define.body.first(), define.body.last()) {
+            (Some(first), Some(last)) => {
+                slice(text, (first.span.0, last.span.1)).trim().to_string()
+            }
+            _ => String::new(),
+        },
+    }
+}
+
+/// The macros defined in `text`, as if they were defined in the file at `path`
+fn definitions(text: &str, path: Option<Arc<Path>>) -> Vec<Macro> {
+    analyze::preprocess(text, |ast| {
+        directives(&ast)
+            .defines
+            .into_iter()
+            .map(|define| to_macro(define, text, path.clone()))
+            .collect()
+    })
+    .map(|(macros, _)| macros)
+    .unwrap_or_default()
+}
+
+fn collect(
+    text: &str,
+    path: &Path,
+    addons: &HashMap<Arc<str>, PathBuf>,
+    scope: &addon::Scope,
+    files: &mut Vec<(PathBuf, String)>,
+) {
+    let includes = analyze::preprocess(text, |ast| include::includes(&ast))
+        .map(|(includes, _)| includes)
+        .unwrap_or_default();
+    for include in includes {
+        if files.len() > include::MAX_FILES {
+            return;
+        }
+        let Some(included) = include::resolve(&include.inner, path, addons, scope) else {
+            continue;
+        };
+        if files.iter().any(|(x, _)| *x == included) {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(&included) else {
+            continue;
+        };
+        files.push((included.clone(), content.clone()));
+        collect(&content, &included, addons, scope, files);
+    }
+}
+
+/// Returns the files included by `text`, the content of the file at `path`, directly or not, and
+/// their content
+pub fn included(
+    text: &str,
+    path: &Path,
+    addons: &HashMap<Arc<str>, PathBuf>,
+    scope: &addon::Scope,
+) -> Vec<(PathBuf, String)> {
+    let mut files = vec![(addon::canonical(path), String::new())];
+    collect(text, path, addons, scope, &mut files);
+    files.remove(0);
+    files
+}
+
+/// Returns the macros defined in `text`, the content of the file at `path`, and in the files it
+/// includes
+pub fn defined(
+    text: &str,
+    path: &Path,
+    addons: &HashMap<Arc<str>, PathBuf>,
+    scope: &addon::Scope,
+) -> Vec<Macro> {
+    definitions(text, None)
+        .into_iter()
+        .chain(
+            included(text, path, addons, scope)
+                .into_iter()
+                .flat_map(|(path, text)| definitions(&text, Some(path.into()))),
+        )
+        .collect()
+}
+
+/// Returns the spans of the references to the macro `name` in `text`: its usages, definitions,
+/// `#undef`s and conditions
+pub fn references(text: &str, name: &str) -> Vec<Span> {
+    analyze::preprocess(text, |ast| {
+        let directives = directives(&ast);
+        let mut spans = tokens::terms(&ast)
+            .into_iter()
+            .filter(|x| x.inner == name)
+            .map(|x| x.span)
+            .chain(directives.defines.iter().flat_map(|define| {
+                std::iter::once(define.name.span)
+                    .filter(|_| define.name.inner == name)
+                    .chain(
+                        define
+                            .body
+                            .iter()
+                            .filter(|x| x.inner.as_ref() == name)
+                            .map(|x| x.span),
+                    )
+            }))
+            .chain(
+                directives
+                    .undefines
+                    .iter()
+                    .chain(directives.conditions.iter())
+                    .filter(|x| x.inner == name)
+                    .map(|x| x.span),
+            )
+            .collect::<Vec<_>>();
+        spans.sort();
+        spans.dedup();
+        spans
+    })
+    .map(|(spans, _)| spans)
+    .unwrap_or_default()
+}
+
+/// If `line` (the text of a line up to the cursor) is a `#if`, `#ifdef` or `#ifndef` directive,
+/// returns the start of the macro name being typed
+pub fn condition_prefix(line: &str) -> Option<&str> {
+    let directive = line.trim_start().strip_prefix('#')?.trim_start();
+    let keyword_end = directive
+        .find(char::is_whitespace)
+        .unwrap_or(directive.len());
+    if !matches!(&directive[..keyword_end], "if" | "ifdef" | "ifndef")
+        || keyword_end == directive.len()
+    {
+        return None;
+    }
+    let start = line
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !(c.is_alphanumeric() || *c == '_'))
+        .map(|(i, c)| i + c.len_utf8())
+        .unwrap_or(0);
+    Some(&line[start..])
+}
+
+/// The state of the macros while walking through a compilation in order
+struct Walk<'b> {
+    addons: &'b HashMap<Arc<str>, PathBuf>,
+    scope: &'b addon::Scope,
+    defined: HashMap<String, Macro>,
+    visited: HashSet<PathBuf>,
+    /// the redefinitions in the file itself and the definitions they replace
+    redefinitions: Vec<(Macro, Macro)>,
+}
+
+impl Walk<'_> {
+    fn file(&mut self, text: &str, path: &Path, origin: Option<Arc<Path>>) {
+        let _ = analyze::preprocess(text, |ast| self.nodes(&ast, text, path, &origin));
+    }
+
+    fn nodes(&mut self, ast: &VecDeque<Ast>, text: &str, path: &Path, origin: &Option<Arc<Path>>) {
+        for node in ast {
+            match node {
+                Ast::Define(define) => {
+                    let new = to_macro(define, text, origin.clone());
+                    if let Some(previous) = self.defined.insert(new.name.clone(), new.clone()) {
+                        let is_same = previous.arguments == new.arguments
+                            && previous
+                                .body
+                                .split_whitespace()
+                                .eq(new.body.split_whitespace());
+                        if !is_same && origin.is_none() {
+                            self.redefinitions.push((new, previous));
+                        }
+                    }
+                }
+                Ast::Undefine(_, name) => {
+                    self.defined.remove(name.inner);
+                }
+                Ast::Include(_, include) => {
+                    let include = include.inner.trim_matches(['"', '<', '>']);
+                    let Some(included) = include::resolve(include, path, self.addons, self.scope)
+                    else {
+                        continue;
+                    };
+                    if self.visited.len() > include::MAX_FILES
+                        || !self.visited.insert(included.clone())
+                    {
+                        continue;
+                    }
+                    let Ok(content) = std::fs::read_to_string(&included) else {
+                        continue;
+                    };
+                    self.file(&content, &included, Some(included.as_path().into()));
+                }
+                Ast::Ifdef(ifdef) | Ast::Ifndef(ifdef) => {
+                    self.branches(&ifdef.then, &ifdef.else_, text, path, origin)
+                }
+                Ast::If(if_) => self.branches(&if_.then, &if_.else_, text, path, origin),
+                _ => {}
+            }
+        }
+    }
+
+    /// Walks through both branches of a condition, which are exclusive: the macros defined by either
+    /// are defined afterwards
+    fn branches(
+        &mut self,
+        then: &VecDeque<Ast>,
+        else_: &VecDeque<Ast>,
+        text: &str,
+        path: &Path,
+        origin: &Option<Arc<Path>>,
+    ) {
+        let before = self.defined.clone();
+        self.nodes(then, text, path, origin);
+        let then = std::mem::replace(&mut self.defined, before);
+        self.nodes(else_, text, path, origin);
+        for (name, definition) in then {
+            self.defined.entry(name).or_insert(definition);
+        }
+    }
+}
+
+/// Returns the macros of `text`, the content of the file at `path`, that are redefined with a
+/// different body without an `#undef`, and the definitions (possibly in an include) they replace
+pub fn redefinitions(
+    text: &str,
+    path: &Path,
+    addons: &HashMap<Arc<str>, PathBuf>,
+    scope: &addon::Scope,
+) -> Vec<(Macro, Macro)> {
+    let mut walk = Walk {
+        addons,
+        scope,
+        defined: Default::default(),
+        visited: HashSet::from([addon::canonical(path)]),
+        redefinitions: vec![],
+    };
+    walk.file(text, path, None);
+    walk.redefinitions
+}
+
+/// The lint of a macro redefined by `new`
+pub fn redefinition_lint(new: &Macro) -> Lint {
+    Lint {
+        code: "macro-redefined",
+        span: new.span,
+        message: format!(
+            "The macro \"{}\" is redefined with a different body without #undef",
+            new.name
+        ),
+    }
+}
+
+/// The names that the macros of a file may be used by: its terms, the tokens of the bodies of its
+/// macros (also the operands of `#` and `##`) and of its conditions, and the pieces pasted with `##`,
+/// which may form any name starting or ending with them
+#[derive(Debug, Default)]
+pub struct Usages {
+    names: HashSet<String>,
+    pasted: Vec<String>,
+}
+
+impl Usages {
+    /// The usages of `text`, `None` when it cannot be preprocessed
+    pub fn new(text: &str) -> Option<Self> {
+        analyze::preprocess(text, |ast| {
+            let directives = directives(&ast);
+            let mut usages = Usages::default();
+            usages
+                .names
+                .extend(tokens::terms(&ast).iter().map(|x| x.inner.to_string()));
+            usages
+                .names
+                .extend(directives.conditions.iter().map(|x| x.inner.to_string()));
+            for define in &directives.defines {
+                let body = &define.body;
+                // the arguments pasted are used by the invocations, whose terms are names already
+                let is_argument = |piece: &str| {
+                    define
+                        .arguments
+                        .iter()
+                        .flatten()
+                        .any(|x| x.inner.as_ref() == piece)
+                };
+                for (i, token) in body.iter().enumerate() {
+                    let inner = token.inner.as_ref();
+                    let is_pasted = |j: Option<usize>| {
+                        j.and_then(|j| body.get(j))
+                            .is_some_and(|x| x.inner.as_ref() == "##")
+                    };
+                    if inner.contains("##") || is_pasted(i.checked_sub(1)) || is_pasted(Some(i + 1))
+                    {
+                        usages.pasted.extend(
+                            inner
+                                .split("##")
+                                .map(|x| x.trim().trim_start_matches('#'))
+                                .filter(|x| !x.is_empty() && !is_argument(x))
+                                .map(str::to_string),
+                        );
+                    }
+                    usages.names.extend(
+                        inner
+                            .split("##")
+                            .map(|x| x.trim().trim_start_matches('#').to_string()),
+                    );
+                }
+            }
+            usages
+        })
+        .ok()
+        .map(|(usages, _)| usages)
+    }
+
+    /// Whether the macro `name` may be used
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.contains(name)
+            || self
+                .pasted
+                .iter()
+                .any(|piece| name.starts_with(piece.as_str()) || name.ends_with(piece.as_str()))
+    }
+}
+
+/// The lints of the macros defined in `text` that are used neither in it nor in any of `files`,
+/// typically the files of its addon and the files they include. Nothing is reported when any of
+/// them cannot be preprocessed.
+pub fn unused(text: &str, files: &[(PathBuf, String)]) -> Vec<Lint> {
+    let Some(usages) = std::iter::once(text)
+        .chain(files.iter().map(|(_, content)| content.as_str()))
+        .map(Usages::new)
+        .collect::<Option<Vec<_>>>()
+    else {
+        return vec![];
+    };
+    let mut names = HashSet::new();
+    definitions(text, None)
+        .into_iter()
+        .filter(|x| names.insert(x.name.clone()))
+        .filter(|x| !usages.iter().any(|usages| usages.contains(&x.name)))
+        .map(|x| Lint {
+            code: "unused-macro",
+            span: x.span,
+            message: format!("The macro \"{}\" is never used in the addon", x.name),
+        })
+        .collect()
+}
+
+/// Returns an error message if `name` is not a valid name for a macro
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if is_name(name) {
+        Ok(())
+    } else {
+        Err(format!("\"{name}\" is not a valid macro name"))
+    }
+}
+
+/// Returns the span of a token of `text` pasted with `##` that may form the name of the macro `name`,
+/// making the renaming of its usages ambiguous
+pub fn pasted(text: &str, name: &str) -> Option<Span> {
+    let may_form = |piece: &str| {
+        let piece = piece.trim();
+        !piece.is_empty() && (name.starts_with(piece) || name.ends_with(piece))
+    };
+    analyze::preprocess(text, |ast| {
+        directives(&ast).defines.iter().find_map(|define| {
+            let body = &define.body;
+            body.iter().enumerate().find_map(|(i, token)| {
+                let inner = token.inner.as_ref();
+                if inner.contains("##") {
+                    inner.split("##").any(may_form).then_some(token.span)
+                } else {
+                    let is_pasted = |j: Option<usize>| {
+                        j.and_then(|j| body.get(j))
+                            .map(|x| x.inner.as_ref() == "##")
+                            .unwrap_or(false)
+                    };
+                    ((is_pasted(i.checked_sub(1)) || is_pasted(Some(i + 1))) && may_form(inner))
+                        .then_some(token.span)
+                }
+            })
+        })
+    })
+    .ok()
+    .and_then(|(span, _)| span)
+}
+
+/// Hints of the macros referenced by `#if`, `#ifdef` and `#ifndef` that are never defined, i.e. not in
+/// `defined` (the macros of the file, of its includes and of the settings).
+/// Macros of the preprocessor itself (e.g. `__ARMA3__`) are not reported.
+pub fn lint(ast: &VecDeque<Ast>, defined: &HashSet<String>) -> Vec<Lint> {
+    directives(ast)
+        .conditions
+        .into_iter()
+        .filter(|name| !name.inner.starts_with("__") && !defined.contains(name.inner))
+        .map(|name| Lint {
+            code: "undefined-macro",
+            span: name.span,
+            message: format!("The macro \"{}\" is never defined", name.inner),
+        })
+        .collect()
+}
+
+/// The maximum depth of nested expansions, guarding against recursive macros
+const MAX_EXPANSION_DEPTH: usize = 16;
+
+fn is_name_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// The arguments of a macro invocation starting at `chars[start]` (`(`), split at the top-level
+/// commas, and the index after its `)`
+fn arguments(chars: &[char], start: usize) -> Option<(Vec<String>, usize)> {
+    let mut arguments = vec![String::new()];
+    let mut depth = 0usize;
+    for (i, c) in chars.iter().enumerate().skip(start) {
+        match c {
+            '(' | '[' | '{' => {
+                depth += 1;
+                if depth == 1 {
+                    continue;
+                }
+            }
+            ')' | ']' | '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((arguments, i + 1));
+                }
+            }
+            ',' if depth == 1 => {
+                arguments.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        arguments.last_mut()?.push(*c);
+    }
+    None
+}
+
+/// The body of a macro with its parameters replaced by `arguments`, stringified by `#` and pasted
+/// by `##`
+fn substitute(body: &str, parameters: &[String], arguments: &[String]) -> String {
+    let chars = body.chars().collect::<Vec<_>>();
+    let mut result = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if !is_name_start(chars[i]) {
+            result.push(chars[i]);
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < chars.len() && is_name_char(chars[i]) {
+            i += 1;
+        }
+        let name = chars[start..i].iter().collect::<String>();
+        match parameters.iter().position(|x| *x == name) {
+            Some(index) => {
+                let argument = arguments.get(index).map(|x| x.trim()).unwrap_or_default();
+                if result.ends_with('#') && !result.ends_with("##") {
+                    result.pop();
+                    result.push_str(&format!("\"{argument}\""));
+                } else {
+                    result.push_str(argument);
+                }
+            }
+            None => result.push_str(&name),
+        }
+    }
+    result
+        .split("##")
+        .enumerate()
+        .map(|(i, x)| if i == 0 { x.trim_end() } else { x.trim() })
+        .collect()
+}
+
+fn expand_(text: &str, macros: &[Macro], depth: usize) -> String {
+    if depth > MAX_EXPANSION_DEPTH {
+        return text.to_string();
+    }
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut result = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '"' || c == '\'' {
+            // strings are not expanded
+            let end = chars[i + 1..]
+                .iter()
+                .position(|x| *x == c)
+                .map_or(chars.len(), |x| i + x + 2);
+            result.extend(&chars[i..end]);
+            i = end;
+            continue;
+        }
+        if !is_name_start(c) {
+            result.push(c);
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < chars.len() && is_name_char(chars[i]) {
+            i += 1;
+        }
+        let name = chars[start..i].iter().collect::<String>();
+        let Some(macro_) = macros.iter().find(|x| x.name == name) else {
+            result.push_str(&name);
+            continue;
+        };
+        match &macro_.arguments {
+            None => result.push_str(&expand_(&macro_.body, macros, depth + 1)),
+            Some(parameters) => {
+                let invocation = (chars.get(i) == Some(&'('))
+                    .then(|| arguments(&chars, i))
+                    .flatten();
+                let Some((arguments, end)) = invocation else {
+                    result.push_str(&name);
+                    continue;
+                };
+                // the arguments are expanded before being substituted, also when stringified
+                let arguments = arguments
+                    .iter()
+                    .map(|x| expand_(x, macros, depth + 1))
+                    .collect::<Vec<_>>();
+                let body = substitute(&macro_.body, parameters, &arguments);
+                result.push_str(&expand_(&body, macros, depth + 1));
+                i = end;
+            }
+        }
+    }
+    result
+}
+
+/// Expands the macros `macros` in `text` like the preprocessor of the game, e.g.
+/// `QUOTE(ADDON)` to `"tag_component"` with the `script_component.hpp` of CBA
+pub fn expand(text: &str, macros: &[Macro]) -> String {
+    expand_(text, macros, 0)
+}
+
+/// The invocation of a macro starting at `start` in `text`: its name, and its arguments when followed
+/// by `(`
+pub fn invocation(text: &str, start: usize) -> Option<String> {
+    let chars = text.chars().skip(start).collect::<Vec<_>>();
+    let name_end = chars
+        .iter()
+        .position(|c| !is_name_char(*c))
+        .unwrap_or(chars.len());
+    if name_end == 0 {
+        return None;
+    }
+    let end = if chars.get(name_end) == Some(&'(') {
+        arguments(&chars, name_end)?.1
+    } else {
+        name_end
+    };
+    Some(chars[..end].iter().collect())
+}
@@ -1,35 +1,80 @@
-use std::collections::HashMap;
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
-use dashmap::DashMap;
+use dashmap::{DashMap, DashSet};
 
 use ropey::Rope;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use sqf::analyzer::{State, BINARY, NULLARY, UNARY};
-use sqf::error::{Error, ErrorType};
+use sqf::preprocessor::Ast;
+use sqf::span::Spanned;
 use sqf::UncasedStr;
-use sqf_analyzer_server::{addon, hover};
+use sqf_analyzer_server::config::{self, symbols::Symbol};
+use sqf_analyzer_server::lint::events::{self, EventUsage};
+use sqf_analyzer_server::lint::functions;
+use sqf_analyzer_server::lint::variable_keys::{self, KeyUsage};
+use sqf_analyzer_server::mission::{self, MissionSqm};
+use sqf_analyzer_server::project::{self, Project};
+use sqf_analyzer_server::{
+    addon, brackets, builtins, casing, debug, dialog, docgen, enums, fsm, header, hemtt, hover,
+    ifdef, include, iteration, lint, locale, macros, on_type, params, private, remote_exec, rename,
+    rules, sqs, suppress, text_pos, todo, tokens,
+};
 use tower_lsp::jsonrpc::Result;
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer, LspService, Server};
 
-use sqf_analyzer_server::semantic_token::SemanticTokenLocation;
-use sqf_analyzer_server::{analyze::compute, definition, semantic_token::LEGEND_TYPE};
+use sqf_analyzer_server::analyze::{self, compute_within, Analysis, Outcome, Stage, StagedError};
+use sqf_analyzer_server::{call_graph, data_flow, definition, semantic_token::LEGEND_TYPE};
 
-type States = DashMap<
-    Url,
-    (
-        (State, Vec<SemanticTokenLocation>, Vec<CompletionItem>),
-        Option<Arc<UncasedStr>>,
-    ),
->;
+type States = DashMap<Url, (Analysis, Option<Arc<UncasedStr>>)>;
+
+const DEFAULT_ANALYSIS_TIMEOUT_MS: u64 = 10_000;
+const DEFAULT_COMPLETION_LIMIT: u64 = 200;
+const DEFAULT_MAX_FILE_SIZE_KB: u64 = 1024;
+const DEFAULT_MAX_DIAGNOSTICS: u64 = 500;
+const DEFAULT_DEBOUNCE_MS: u64 = 300;
+
+/// The renames of scripts and of directories, which may contain scripts
+fn script_renames() -> FileOperationRegistrationOptions {
+    let filter = |glob: &str, matches| FileOperationFilter {
+        scheme: Some("file".to_string()),
+        pattern: FileOperationPattern {
+            glob: glob.to_string(),
+            matches: Some(matches),
+            options: None,
+        },
+    };
+    FileOperationRegistrationOptions {
+        filters: vec![
+            filter("**/*.sqf", FileOperationPatternKind::File),
+            filter("**", FileOperationPatternKind::Folder),
+        ],
+    }
+}
 
 fn clean(url: Url) -> Url {
-    Url::from_file_path(url.to_file_path().unwrap()).unwrap()
+    Url::from_file_path(addon::canonical(&url.to_file_path().unwrap())).unwrap()
+}
+
+/// Moves the entry of the file `old` to `new` after it was renamed, unless `new` already has one
+/// (e.g. it was opened under its new name first)
+fn move_entry<V>(map: &DashMap<Url, V>, old: &Url, new: &Url) {
+    if let Some((_, value)) = map.remove(old) {
+        map.entry(new.clone()).or_insert(value);
+    }
+}
+
+/// A hash of the content of a file, to tell whether it changed
+fn content_hash(text: &str) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
 }
 
 #[derive(Debug)]
@@ -37,33 +82,215 @@ struct Backend {
     client: Client,
     states: States,
     documents: DashMap<Url, Rope>,
+    /// the hash of the content of the files analyzed when the project was loaded and not opened
+    /// since, whose analysis is reused when they are opened unchanged
+    preloaded: DashMap<Url, u64>,
     undefined_variables_are_error: AtomicBool,
     private_variables_in_mission_are_error: AtomicBool,
     error_on_unused: AtomicBool,
     is_loaded: AtomicBool,
-    addon_paths: RwLock<HashMap<Arc<str>, PathBuf>>,
+    addon_paths: RwLock<addon::Prefixes>,
+    todo_markers: RwLock<Vec<String>>,
+    /// the address of the debugger extension of a running game, empty when disabled
+    debug_bridge: RwLock<String>,
+    /// `mission.sqm` of each mission, keyed by the path of its `description.ext`
+    missions: DashMap<PathBuf, Option<Arc<MissionSqm>>>,
+    /// keys used with `setVariable`/`getVariable` of each file
+    variable_keys: DashMap<Url, Vec<KeyUsage>>,
+    unmatched_variable_keys: RwLock<variable_keys::Mode>,
+    /// events raised and handled in each file
+    events: DashMap<Url, Vec<EventUsage>>,
+    /// references to functions in each file, including the ones by name in strings
+    functions: DashMap<Url, Vec<Spanned<String>>>,
+    /// the functions called by each file, the edges of the call graph (see [`Backend::call_graph`])
+    calls: DashMap<Url, Vec<(String, call_graph::Kind)>>,
+    /// the configs of the other addons each file uses by include or `EFUNC`-like macros, and how
+    addon_usages: DashMap<Url, Vec<(PathBuf, String)>>,
+    /// the diagnostics of the UI event handlers of each config document
+    handler_diagnostics: DashMap<Url, Vec<Diagnostic>>,
+    unmatched_events: AtomicBool,
+    /// whether files outside of the workspace folders are never read (untrusted workspaces)
+    restricted: AtomicBool,
+    /// the files the analysis may read and how the addons and missions within them are found
+    scope: RwLock<addon::Scope>,
+    /// the macros predefined in the settings and whether the inactive branches are analyzed
+    ifdef: RwLock<ifdef::Settings>,
+    /// the translated explanations of the commands, from the `locale` setting
+    explanations: RwLock<locale::Explanations>,
+    /// whether the client handles the `sqfAnalyzer/inactiveRegions` notification
+    inactive_regions: AtomicBool,
+    /// the time budget of the analysis of a file
+    analysis_timeout_ms: AtomicU64,
+    /// the maximum number of completion items returned, `0` for no limit
+    completion_limit: AtomicU64,
+    /// the size above which files are not analyzed, `0` for no limit
+    max_file_size_kb: AtomicU64,
+    /// the maximum number of diagnostics published per file, `0` for no limit
+    max_diagnostics: AtomicU64,
+    /// whether documents are only fully analyzed when saved, and only checked for syntax on change
+    run_on_save: AtomicBool,
+    /// the time without changes after which a document is fully analyzed
+    debounce_ms: AtomicU64,
+    /// the last analysis of each document waiting for the typing to pause
+    debounces: DashMap<Url, tokio::task::AbortHandle>,
+    /// whether renaming a function also renames its occurrences in strings, to be confirmed
+    rename_string_references: AtomicBool,
+    /// whether the formatting of configs aligns the `=` of consecutive assignments
+    config_align_assignments: AtomicBool,
+    /// whether documents are formatted (configs) and their commands' casing fixed (scripts) on save
+    fix_on_save: AtomicBool,
+    /// the constructs closed when a new line follows their opening
+    on_type_close: RwLock<Vec<on_type::Construct>>,
+    /// whether switches without a `default` branch are reported
+    switch_require_default: AtomicBool,
+    /// the `.hpp` documents analyzed as headers of scripts rather than as configs
+    script_headers: DashSet<Url>,
+    /// script of each function, keyed by the path of the config declaring them
+    function_files: DashMap<PathBuf, HashMap<Arc<UncasedStr>, Arc<Path>>>,
+    /// the config of the addon or mission (empty if none) of the files of each directory, so that
+    /// identifying it does not walk the directory tree on every change
+    addon_roots: DashMap<PathBuf, PathBuf>,
+    workspace_folders: RwLock<Vec<PathBuf>>,
+    /// the `.sqf-analyzer.toml` of the workspace folders
+    projects: RwLock<Vec<Project>>,
+    /// the `.sqf-analyzer-baseline.json` of the workspace folders, with their folder
+    baselines: RwLock<Vec<(PathBuf, suppress::Baseline)>>,
+    /// the suppression comments of each open document, and the version they were parsed from
+    suppressions: DashMap<Url, (i32, suppress::Comments)>,
+    /// the level of each rule set in the editor settings, which takes precedence over the project's
+    rule_levels: RwLock<HashMap<String, rules::Level>>,
+    /// the level of each rule set by the command `sqf-analyzer.setRule`, which takes precedence over
+    /// the settings until the server restarts
+    command_rule_levels: RwLock<HashMap<String, rules::Level>>,
+    /// the warnings reported as errors, applied after the levels of the rules
+    warnings_as_errors: RwLock<rules::WarningsAsErrors>,
+    /// the highest severity of the diagnostics of files by path, applied last
+    overrides: RwLock<rules::Overrides>,
+    /// the diagnostics of each file before the settings are applied, and their generation
+    diagnostics: DashMap<Url, (Vec<Diagnostic>, u64)>,
+    /// the generation of the last published diagnostics of each file
+    generations: DashMap<Url, u64>,
+    generation: AtomicU64,
+    /// the latest version of each open document
+    versions: DashMap<Url, i32>,
+    started: Instant,
+    /// the duration of the last scan of the project and the size of the files it read
+    last_scan: RwLock<Option<(Duration, usize)>>,
+    statistics: Statistics,
+}
+
+/// Counters of the work of the server, reported by `sqfAnalyzer/statistics`
+#[derive(Debug, Default)]
+struct Statistics {
+    /// analyses of documents, and their total duration
+    analyses: AtomicU64,
+    analyses_us: AtomicU64,
+    /// lookups of the addon of a file, and the ones answered by the cache
+    addon_lookups: AtomicU64,
+    addon_cache_hits: AtomicU64,
+}
+
+/// The backend, shared with the tasks it spawns (e.g. the analyses run once the typing pauses)
+#[derive(Debug)]
+struct Handle(Arc<Backend>);
+
+impl std::ops::Deref for Handle {
+    type Target = Backend;
+
+    fn deref(&self) -> &Backend {
+        &self.0
+    }
+}
+
+impl Handle {
+    async fn statistics(&self) -> Result<Value> {
+        self.0.statistics().await
+    }
+
+    async fn type_at(&self, params: TextDocumentPositionParams) -> Result<Option<Value>> {
+        self.0.type_at(params).await
+    }
+
+    async fn globals(&self, params: GlobalsParams) -> Result<Value> {
+        self.0.globals(params).await
+    }
 }
 
 #[tower_lsp::async_trait]
-impl LanguageServer for Backend {
-    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+impl LanguageServer for Handle {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        #[allow(deprecated)]
+        let folders = params
+            .workspace_folders
+            .map(|folders| folders.into_iter().map(|x| x.uri).collect::<Vec<_>>())
+            .or_else(|| params.root_uri.map(|x| vec![x]))
+            .unwrap_or_default();
+        let folders = folders
+            .into_iter()
+            .filter_map(|x| x.to_file_path().ok())
+            .collect::<Vec<_>>();
+        let restricted = params
+            .initialization_options
+            .as_ref()
+            .and_then(|x| x.get("restricted"))
+            .and_then(|x| x.as_bool())
+            .unwrap_or(false);
+        self.restricted.store(restricted, Ordering::Relaxed);
+        self.inactive_regions.store(
+            params
+                .initialization_options
+                .as_ref()
+                .and_then(|x| x.get("inactive_regions"))
+                .and_then(|x| x.as_bool())
+                .unwrap_or(false),
+            Ordering::Relaxed,
+        );
+        self.scope.write().unwrap().allowed = restricted.then(|| folders.clone());
+        *self.workspace_folders.write().unwrap() = folders;
+
         Ok(InitializeResult {
             server_info: None,
             offset_encoding: None,
             capabilities: ServerCapabilities {
                 inlay_hint_provider: Some(OneOf::Left(true)),
-                text_document_sync: Some(TextDocumentSyncCapability::Kind(
-                    TextDocumentSyncKind::FULL,
+                document_on_type_formatting_provider: Some(DocumentOnTypeFormattingOptions {
+                    first_trigger_character: "\n".to_string(),
+                    more_trigger_character: None,
+                }),
+                text_document_sync: Some(TextDocumentSyncCapability::Options(
+                    TextDocumentSyncOptions {
+                        open_close: Some(true),
+                        change: Some(TextDocumentSyncKind::FULL),
+                        will_save_wait_until: Some(true),
+                        save: Some(TextDocumentSyncSaveOptions::Supported(true)),
+                        ..Default::default()
+                    },
                 )),
                 completion_provider: Some(CompletionOptions {
                     resolve_provider: Some(false),
-                    trigger_characters: Some(vec![".".to_string()]),
+                    trigger_characters: Some(vec![
+                        ".".to_string(),
+                        "\"".to_string(),
+                        "[".to_string(),
+                    ]),
                     work_done_progress_options: Default::default(),
                     all_commit_characters: None,
                     completion_item: None,
                 }),
                 execute_command_provider: Some(ExecuteCommandOptions {
-                    commands: vec!["dummy.do_something".to_string()],
+                    commands: vec![
+                        "sqf-analyzer.todos".to_string(),
+                        "sqf-analyzer.explain".to_string(),
+                        "sqf-analyzer.serverStatus".to_string(),
+                        "sqf-analyzer.includeTree".to_string(),
+                        "sqf-analyzer.evaluateInGame".to_string(),
+                        "sqf-analyzer.getVariable".to_string(),
+                        "sqf-analyzer.dataFlow".to_string(),
+                        "sqf-analyzer.callGraph".to_string(),
+                        "sqf-analyzer.setRule".to_string(),
+                        "sqf-analyzer.docgen".to_string(),
+                        "sqf-analyzer.problemsByRule".to_string(),
+                    ],
                     work_done_progress_options: Default::default(),
                 }),
 
@@ -72,18 +299,30 @@ impl LanguageServer for Backend {
                         supported: Some(true),
                         change_notifications: Some(OneOf::Left(true)),
                     }),
-                    file_operations: None,
+                    file_operations: Some(WorkspaceFileOperationsServerCapabilities {
+                        will_rename: Some(script_renames()),
+                        did_rename: Some(script_renames()),
+                        ..Default::default()
+                    }),
                 }),
                 semantic_tokens_provider: Some(
                     SemanticTokensServerCapabilities::SemanticTokensRegistrationOptions(
                         SemanticTokensRegistrationOptions {
                             text_document_registration_options: {
                                 TextDocumentRegistrationOptions {
-                                    document_selector: Some(vec![DocumentFilter {
-                                        language: Some("sqf".to_string()),
-                                        scheme: Some("file".to_string()),
-                                        pattern: None,
-                                    }]),
+                                    document_selector: Some(vec![
+                                        DocumentFilter {
+                                            language: Some("sqf".to_string()),
+                                            scheme: Some("file".to_string()),
+                                            pattern: None,
+                                        },
+                                        // headers of scripts
+                                        DocumentFilter {
+                                            language: None,
+                                            scheme: Some("file".to_string()),
+                                            pattern: Some("**/*.{inc,h,hpp}".to_string()),
+                                        },
+                                    ]),
                                 }
                             },
                             semantic_tokens_options: SemanticTokensOptions {
@@ -100,9 +339,18 @@ impl LanguageServer for Backend {
                     ),
                 ),
                 definition_provider: Some(OneOf::Left(true)),
-                references_provider: Some(OneOf::Left(false)),
-                rename_provider: Some(OneOf::Left(false)),
+                document_symbol_provider: Some(OneOf::Left(true)),
+                workspace_symbol_provider: Some(OneOf::Left(true)),
+                code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+                references_provider: Some(OneOf::Left(true)),
+                rename_provider: Some(OneOf::Right(RenameOptions {
+                    prepare_provider: Some(true),
+                    work_done_progress_options: Default::default(),
+                })),
                 hover_provider: Some(true.into()),
+                document_highlight_provider: Some(OneOf::Left(true)),
+                inline_value_provider: Some(OneOf::Left(true)),
+                document_formatting_provider: Some(OneOf::Left(true)),
                 ..ServerCapabilities::default()
             },
         })
@@ -112,6 +360,7 @@ impl LanguageServer for Backend {
         self.client
             .log_message(MessageType::INFO, "initialized!")
             .await;
+        self.load_projects().await;
     }
 
     async fn shutdown(&self) -> Result<()> {
@@ -125,12 +374,14 @@ impl LanguageServer for Backend {
                 format!("did_open({})", &params.text_document.uri),
             )
             .await;
-        self.on_change(TextDocumentItem {
+        let item = TextDocumentItem {
             uri: params.text_document.uri,
             text: params.text_document.text,
             version: params.text_document.version,
-        })
-        .await
+        };
+        if !self.warm_start(&item).await {
+            self.on_change(item, false).await
+        }
     }
 
     async fn did_change(&self, mut params: DidChangeTextDocumentParams) {
@@ -140,12 +391,38 @@ impl LanguageServer for Backend {
                 format!("did_change({})", &params.text_document.uri),
             )
             .await;
-        self.on_change(TextDocumentItem {
+        let item = TextDocumentItem {
             uri: params.text_document.uri,
             text: std::mem::take(&mut params.content_changes[0].text),
             version: params.text_document.version,
-        })
-        .await
+        };
+        if self.run_on_save.load(Ordering::Relaxed) {
+            self.on_syntax_change(item, true).await
+        } else {
+            // the syntax is checked on every change, the (slower) analysis once the typing pauses
+            let (uri, version) = (clean(item.uri.clone()), item.version);
+            self.on_syntax_change(item.clone(), false).await;
+            let debounce = self.debounce_ms.load(Ordering::Relaxed);
+            let backend = self.0.clone();
+            let wait = tokio::spawn({
+                let uri = uri.clone();
+                async move {
+                    tokio::time::sleep(Duration::from_millis(debounce)).await;
+                    // on its own task, which newer changes do not abort once started
+                    tokio::spawn(async move {
+                        // closed documents are no longer versioned
+                        let is_open = backend.versions.contains_key(&uri);
+                        if is_open && !backend.is_outdated(&uri, version) {
+                            backend.on_change(item, true).await
+                        }
+                    });
+                }
+            });
+            // the analysis of the previous version, if still waiting, is superseded
+            if let Some(previous) = self.debounces.insert(uri, wait.abort_handle()) {
+                previous.abort();
+            }
+        }
     }
 
     async fn did_save(&self, params: DidSaveTextDocumentParams) {
@@ -155,6 +432,35 @@ impl LanguageServer for Backend {
                 format!("did_save({})", &params.text_document.uri),
             )
             .await;
+        let uri = clean(params.text_document.uri);
+        if let Ok(path) = uri.to_file_path() {
+            if config::is_config(&path) {
+                self.sync_functions(&uri, path).await;
+            } else if path.file_name() == Some(project::FILE_NAME.as_ref()) {
+                self.load_projects().await;
+                self.republish().await;
+                self.reanalyze().await;
+            } else if self.run_on_save.load(Ordering::Relaxed) {
+                let Some(text) = self.documents.get(&uri).map(|x| x.to_string()) else {
+                    return;
+                };
+                let version = self.versions.get(&uri).map(|x| *x).unwrap_or_default();
+                // its syntax was checked when it last changed
+                self.on_change(TextDocumentItem { uri, text, version }, true)
+                    .await;
+            }
+        }
+    }
+
+    async fn will_save_wait_until(
+        &self,
+        params: WillSaveTextDocumentParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        let uri = clean(params.text_document.uri);
+        if !self.fix_on_save.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+        Ok(self.save_edits(&uri))
     }
 
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
@@ -164,6 +470,11 @@ impl LanguageServer for Backend {
                 format!("did_close({})", &params.text_document.uri),
             )
             .await;
+        // diagnostics of closed documents no longer refer to a version
+        let uri = clean(params.text_document.uri);
+        self.cancel_debounce(&uri);
+        self.versions.remove(&uri);
+        self.suppressions.remove(&uri);
     }
 
     async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
@@ -172,7 +483,19 @@ impl LanguageServer for Backend {
             .log_message(MessageType::INFO, format!("hover({})", &uri))
             .await;
         let position = params.text_document_position_params.position;
-        Ok(self.hover(uri, position))
+        Ok(self.0.hover(uri, position))
+    }
+
+    async fn document_highlight(
+        &self,
+        params: DocumentHighlightParams,
+    ) -> Result<Option<Vec<DocumentHighlight>>> {
+        let uri = clean(params.text_document_position_params.text_document.uri);
+        self.client
+            .log_message(MessageType::INFO, format!("document_highlight({})", &uri))
+            .await;
+        let position = params.text_document_position_params.position;
+        Ok(self.document_highlights(&uri, position))
     }
 
     async fn goto_definition(
@@ -187,6 +510,87 @@ impl LanguageServer for Backend {
         Ok(self.get_definition(uri, position))
     }
 
+    async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+        let uri = clean(params.text_document_position.text_document.uri);
+        self.client
+            .log_message(MessageType::INFO, format!("references({})", &uri))
+            .await;
+        let position = params.text_document_position.position;
+        Ok(self.macro_references(&uri, position).or_else(|| {
+            self.function_references(&uri, position, params.context.include_declaration)
+        }))
+    }
+
+    async fn inline_value(&self, params: InlineValueParams) -> Result<Option<Vec<InlineValue>>> {
+        let uri = clean(params.text_document.uri);
+        self.client
+            .log_message(MessageType::INFO, format!("inline_value({})", &uri))
+            .await;
+        Ok(self.inline_values(&uri, params.range, params.context.stopped_location))
+    }
+
+    async fn document_symbol(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> Result<Option<DocumentSymbolResponse>> {
+        let uri = clean(params.text_document.uri);
+        self.client
+            .log_message(MessageType::INFO, format!("document_symbol({})", &uri))
+            .await;
+        Ok(self.document_symbols(uri))
+    }
+
+    async fn symbol(
+        &self,
+        params: WorkspaceSymbolParams,
+    ) -> Result<Option<Vec<SymbolInformation>>> {
+        self.client
+            .log_message(MessageType::INFO, format!("symbol({})", &params.query))
+            .await;
+        Ok(Some(self.workspace_symbols(&params.query)))
+    }
+
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        let uri = clean(params.text_document.uri);
+        self.client
+            .log_message(MessageType::INFO, format!("code_action({})", &uri))
+            .await;
+        Ok(self.code_actions(uri, params.range, params.context.diagnostics))
+    }
+
+    async fn prepare_rename(
+        &self,
+        params: TextDocumentPositionParams,
+    ) -> Result<Option<PrepareRenameResponse>> {
+        let uri = clean(params.text_document.uri);
+        self.client
+            .log_message(MessageType::INFO, format!("prepare_rename({})", &uri))
+            .await;
+        self.0
+            .prepare_rename(&uri, params.position)
+            .map_err(tower_lsp::jsonrpc::Error::invalid_params)
+    }
+
+    async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+        let uri = clean(params.text_document_position.text_document.uri);
+        self.client
+            .log_message(MessageType::INFO, format!("rename({})", &uri))
+            .await;
+        let position = params.text_document_position.position;
+        if let Some((name, definitions)) = self.macro_at(&uri, position) {
+            return self
+                .rename_macro(&uri, &name, &definitions, &params.new_name)
+                .map(Some)
+                .map_err(tower_lsp::jsonrpc::Error::invalid_params);
+        }
+        let Some((config_path, name)) = self.function_at(&uri, position) else {
+            return Ok(None);
+        };
+        self.rename_function(&config_path, &name, &params.new_name)
+            .map(Some)
+            .map_err(tower_lsp::jsonrpc::Error::invalid_params)
+    }
+
     async fn semantic_tokens_full(
         &self,
         params: SemanticTokensParams,
@@ -214,6 +618,50 @@ impl LanguageServer for Backend {
         Ok(self.inlay(uri))
     }
 
+    async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        let uri = clean(params.text_document.uri);
+        self.client
+            .log_message(MessageType::INFO, format!("formatting({})", &uri))
+            .await;
+        Ok(self.format_config(&uri, &params.options))
+    }
+
+    async fn on_type_formatting(
+        &self,
+        params: DocumentOnTypeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        let uri = clean(params.text_document_position.text_document.uri);
+        let position = params.text_document_position.position;
+        if params.ch != "\n" || position.line == 0 {
+            return Ok(None);
+        }
+        let Some(rope) = self.documents.get(&uri).map(|x| x.clone()) else {
+            return Ok(None);
+        };
+        let is_config = uri
+            .to_file_path()
+            .is_ok_and(|x| config::is_config(&x) && !self.script_headers.contains(&uri));
+        let previous = rope
+            .get_line(position.line as usize - 1)
+            .map(|x| x.to_string())
+            .unwrap_or_default();
+        let constructs = self.on_type_close.read().unwrap().clone();
+        let Some(closing) = on_type::closing(&rope.to_string(), &previous, &constructs, is_config)
+        else {
+            return Ok(None);
+        };
+        // after the new line, so that the cursor stays on it
+        let line = position.line as usize;
+        let edit = if line + 1 < rope.len_lines() {
+            let start = Position::new(position.line + 1, 0);
+            TextEdit::new(Range::new(start, start), format!("{closing}\n"))
+        } else {
+            let end = text_pos::position(rope.len_chars(), &rope).unwrap_or(position);
+            TextEdit::new(Range::new(end, end), format!("\n{closing}"))
+        };
+        Ok(Some(vec![edit]))
+    }
+
     async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
         self.client
             .log_message(MessageType::INFO, format!("{:?}", params.settings))
@@ -252,86 +700,569 @@ impl LanguageServer for Backend {
         let addon_paths = server_settings
             .and_then(|x| x.get("addons"))
             .and_then(|x| x.as_object())
+            .map(addon::Prefixes::from_setting)
+            .unwrap_or_default();
+        let prefixes_changed = {
+            let mut w = self.addon_paths.write().unwrap();
+            let changed = *w != addon_paths;
+            *w = addon_paths;
+            changed
+        };
+
+        let mode = server_settings
+            .and_then(|x| x.get("unmatched_variable_keys"))
+            .and_then(|x| x.as_str())
+            .map(variable_keys::Mode::from_setting)
+            .unwrap_or_default();
+        *self.unmatched_variable_keys.write().unwrap() = mode;
+
+        self.unmatched_events.store(
+            server_settings
+                .and_then(|x| x.get("unmatched_events"))
+                .and_then(|x| x.as_bool())
+                .unwrap_or(false),
+            Ordering::Relaxed,
+        );
+
+        self.analysis_timeout_ms.store(
+            server_settings
+                .and_then(|x| x.get("analysis_timeout_ms"))
+                .and_then(|x| x.as_u64())
+                .unwrap_or(DEFAULT_ANALYSIS_TIMEOUT_MS),
+            Ordering::Relaxed,
+        );
+
+        self.completion_limit.store(
+            server_settings
+                .and_then(|x| x.get("completion_limit"))
+                .and_then(|x| x.as_u64())
+                .unwrap_or(DEFAULT_COMPLETION_LIMIT),
+            Ordering::Relaxed,
+        );
+
+        self.max_diagnostics.store(
+            server_settings
+                .and_then(|x| x.get("max_diagnostics"))
+                .and_then(|x| x.as_u64())
+                .unwrap_or(DEFAULT_MAX_DIAGNOSTICS),
+            Ordering::Relaxed,
+        );
+
+        self.max_file_size_kb.store(
+            server_settings
+                .and_then(|x| x.get("max_file_size_kb"))
+                .and_then(|x| x.as_u64())
+                .unwrap_or(DEFAULT_MAX_FILE_SIZE_KB),
+            Ordering::Relaxed,
+        );
+
+        self.debounce_ms.store(
+            server_settings
+                .and_then(|x| x.get("debounce_ms"))
+                .and_then(|x| x.as_u64())
+                .unwrap_or(DEFAULT_DEBOUNCE_MS),
+            Ordering::Relaxed,
+        );
+
+        {
+            let mut scope = self.scope.write().unwrap();
+            scope.mission_root_markers = server_settings
+                .and_then(|x| x.get("mission_root_markers"))
+                .and_then(|x| x.as_array())
+                .map(|x| {
+                    x.iter()
+                        .filter_map(|x| x.as_str().map(|x| x.to_string()))
+                        .collect()
+                })
+                .unwrap_or_default();
+            scope.respect_gitignore = server_settings
+                .and_then(|x| x.get("respect_gitignore"))
+                .and_then(|x| x.as_bool())
+                .unwrap_or(true);
+        }
+
+        *self.ifdef.write().unwrap() = ifdef::Settings {
+            defines: server_settings
+                .and_then(|x| x.get("defines"))
+                .and_then(|x| x.as_array())
+                .map(|x| {
+                    x.iter()
+                        .filter_map(|x| x.as_str())
+                        .map(|x| x.to_string())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            both_branches: server_settings
+                .and_then(|x| x.get("analyze_inactive_branches"))
+                .and_then(|x| x.as_bool())
+                .unwrap_or(false),
+        };
+
+        self.rename_string_references.store(
+            server_settings
+                .and_then(|x| x.get("rename_string_references"))
+                .and_then(|x| x.as_bool())
+                .unwrap_or(false),
+            Ordering::Relaxed,
+        );
+
+        self.config_align_assignments.store(
+            server_settings
+                .and_then(|x| x.get("config_align_assignments"))
+                .and_then(|x| x.as_bool())
+                .unwrap_or(false),
+            Ordering::Relaxed,
+        );
+
+        self.fix_on_save.store(
+            server_settings
+                .and_then(|x| x.get("fix_on_save"))
+                .and_then(|x| x.as_bool())
+                .unwrap_or(false),
+            Ordering::Relaxed,
+        );
+
+        *self.on_type_close.write().unwrap() = server_settings
+            .and_then(|x| x.get("on_type_close"))
+            .and_then(|x| x.as_array())
+            .map(|x| {
+                x.iter()
+                    .filter_map(|x| on_type::Construct::from_setting(x.as_str()?))
+                    .collect()
+            })
+            .unwrap_or_else(|| vec![on_type::Construct::Endif, on_type::Construct::Class]);
+
+        self.switch_require_default.store(
+            server_settings
+                .and_then(|x| x.get("switch_require_default"))
+                .and_then(|x| x.as_bool())
+                .unwrap_or(false),
+            Ordering::Relaxed,
+        );
+
+        self.run_on_save.store(
+            server_settings
+                .and_then(|x| x.get("run_on"))
+                .and_then(|x| x.as_str())
+                == Some("save"),
+            Ordering::Relaxed,
+        );
+
+        let rule_levels = server_settings
+            .and_then(|x| x.get("rules"))
+            .and_then(|x| x.as_object())
             .map(|x| {
                 x.iter()
-                    .filter_map(|(key, value)| {
-                        value
-                            .as_str()
-                            .map(PathBuf::from)
-                            .map(|value| (key.clone().into(), value))
+                    .filter_map(|(code, level)| {
+                        Some((code.clone(), rules::Level::from_setting(level.as_str()?)?))
                     })
                     .collect()
             })
             .unwrap_or_default();
+        *self.rule_levels.write().unwrap() = rule_levels;
+
+        *self.warnings_as_errors.write().unwrap() = server_settings
+            .and_then(|x| x.get("warnings_as_errors"))
+            .map(rules::WarningsAsErrors::from_setting)
+            .unwrap_or_default();
+
+        *self.overrides.write().unwrap() = server_settings
+            .and_then(|x| x.get("overrides"))
+            .map(rules::Overrides::from_setting)
+            .unwrap_or_default();
+
+        let todo_markers = server_settings
+            .and_then(|x| x.get("todo_markers"))
+            .and_then(|x| x.as_array())
+            .map(|x| {
+                x.iter()
+                    .filter_map(|x| x.as_str().map(|x| x.to_string()))
+                    .collect()
+            })
+            .unwrap_or_else(|| {
+                todo::DEFAULT_MARKERS
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect()
+            });
         {
-            let mut w = self.addon_paths.write().unwrap();
-            *w = addon_paths;
+            let mut w = self.todo_markers.write().unwrap();
+            *w = todo_markers;
+        }
+
+        let locale = server_settings
+            .and_then(|x| x.get("locale"))
+            .and_then(|x| x.as_str())
+            .filter(|x| !x.is_empty())
+            .map(|x| {
+                // relative to the first workspace folder
+                let path = PathBuf::from(x);
+                match self.workspace_folders.read().unwrap().first() {
+                    Some(folder) if path.is_relative() => folder.join(path),
+                    _ => path,
+                }
+            })
+            // not read outside of the workspace folders in restricted mode
+            .filter(|x| self.scope().is_allowed(x));
+        match locale::load(locale.as_deref()) {
+            Ok(explanations) => *self.explanations.write().unwrap() = explanations,
+            Err(message) => {
+                self.client
+                    .show_message(MessageType::WARNING, message)
+                    .await
+            }
+        }
+
+        *self.debug_bridge.write().unwrap() = server_settings
+            .and_then(|x| x.get("debug_bridge"))
+            .and_then(|x| x.as_str())
+            .unwrap_or_default()
+            .to_string();
+
+        self.republish().await;
+        if prefixes_changed {
+            // paths of the project may now resolve differently
+            self.reanalyze().await;
         }
     }
 
-    async fn did_change_workspace_folders(&self, _: DidChangeWorkspaceFoldersParams) {
+    async fn did_change_workspace_folders(&self, params: DidChangeWorkspaceFoldersParams) {
         self.client
             .log_message(MessageType::INFO, "workspace folders changed!")
             .await;
+        {
+            let mut folders = self.workspace_folders.write().unwrap();
+            let removed = params
+                .event
+                .removed
+                .into_iter()
+                .filter_map(|x| x.uri.to_file_path().ok())
+                .collect::<Vec<_>>();
+            folders.retain(|x| !removed.contains(x));
+            folders.extend(
+                params
+                    .event
+                    .added
+                    .into_iter()
+                    .filter_map(|x| x.uri.to_file_path().ok()),
+            );
+            if self.restricted.load(Ordering::Relaxed) {
+                self.scope.write().unwrap().allowed = Some(folders.clone());
+            }
+        }
+        self.load_projects().await;
     }
 
-    async fn did_change_watched_files(&self, _: DidChangeWatchedFilesParams) {
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
         self.client
             .log_message(MessageType::INFO, "watched files have changed!")
             .await;
+        let is_baseline = |x: &FileEvent| {
+            x.uri
+                .path()
+                .ends_with(&format!("/{}", suppress::BASELINE_FILE_NAME))
+        };
+        if params.changes.iter().any(is_baseline) {
+            self.load_baselines().await;
+            self.republish().await;
+        }
     }
 
-    async fn execute_command(&self, _: ExecuteCommandParams) -> Result<Option<Value>> {
-        self.client
-            .log_message(MessageType::INFO, "command executed!")
-            .await;
-
-        match self.client.apply_edit(WorkspaceEdit::default()).await {
-            Ok(res) if res.applied => self.client.log_message(MessageType::INFO, "applied").await,
-            Ok(_) => self.client.log_message(MessageType::INFO, "rejected").await,
-            Err(err) => self.client.log_message(MessageType::ERROR, err).await,
+    async fn will_rename_files(&self, params: RenameFilesParams) -> Result<Option<WorkspaceEdit>> {
+        let mut changes = HashMap::<Url, Vec<TextEdit>>::new();
+        for file in &params.files {
+            let (Ok(old), Ok(new)) = (Url::parse(&file.old_uri), Url::parse(&file.new_uri)) else {
+                continue;
+            };
+            let (Ok(old), Ok(new)) = (old.to_file_path(), new.to_file_path()) else {
+                continue;
+            };
+            for (url, edit) in self.moved_script_edits(&old, &new) {
+                changes.entry(url).or_default().push(edit);
+            }
         }
+        Ok((!changes.is_empty()).then(|| WorkspaceEdit::new(changes)))
+    }
 
-        Ok(None)
+    async fn did_rename_files(&self, params: RenameFilesParams) {
+        self.addon_roots.clear();
+        for file in params.files {
+            let (Ok(old), Ok(new)) = (Url::parse(&file.old_uri), Url::parse(&file.new_uri)) else {
+                continue;
+            };
+            let (Ok(old_path), Ok(new_path)) = (old.to_file_path(), new.to_file_path()) else {
+                continue;
+            };
+            // the scripts moved, the renamed file itself or the ones of a renamed directory
+            let moved = |url: &Url| {
+                let path = url.to_file_path().ok()?;
+                let relative = path.strip_prefix(&old_path).ok()?;
+                Url::from_file_path(new_path.join(relative)).ok()
+            };
+            let mut urls = self
+                .states
+                .iter()
+                .map(|x| x.key().clone())
+                .chain(self.documents.iter().map(|x| x.key().clone()))
+                .chain(self.diagnostics.iter().map(|x| x.key().clone()))
+                .filter_map(|url| Some((moved(&url)?, url)))
+                .collect::<Vec<_>>();
+            urls.sort();
+            urls.dedup();
+            for (new_url, old_url) in urls {
+                self.move_file(&old_url, &new_url).await;
+            }
+            for mut files in self.function_files.iter_mut() {
+                for script in files.values_mut() {
+                    if let Ok(relative) = script.strip_prefix(&old_path) {
+                        *script = new_path.join(relative).into();
+                    }
+                }
+            }
+        }
     }
 
-    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
-        let uri = clean(params.text_document_position.text_document.uri);
+    async fn execute_command(&self, params: ExecuteCommandParams) -> Result<Option<Value>> {
         self.client
-            .log_message(MessageType::INFO, format!("completion({})", &uri))
+            .log_message(
+                MessageType::INFO,
+                format!("execute_command({})", &params.command),
+            )
             .await;
-        Ok(self.completion(uri))
-    }
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-struct InlayHintParams {
-    path: String,
-}
 
-struct TextDocumentItem {
+        match params.command.as_str() {
+            "sqf-analyzer.todos" => Ok(Some(self.todos())),
+            "sqf-analyzer.explain" => {
+                let code = params
+                    .arguments
+                    .first()
+                    .and_then(|x| x.as_str())
+                    .ok_or_else(|| {
+                        tower_lsp::jsonrpc::Error::invalid_params("expected the code of a rule")
+                    })?;
+                Ok(rules::explain(code).map(Value::String))
+            }
+            "sqf-analyzer.serverStatus" => Ok(Some(self.server_status())),
+            "sqf-analyzer.problemsByRule" => Ok(Some(Value::String(self.problems_by_rule()))),
+            "sqf-analyzer.includeTree" => {
+                let uri = params
+                    .arguments
+                    .first()
+                    .and_then(|x| x.as_str())
+                    .and_then(|x| Url::parse(x).ok())
+                    .ok_or_else(|| {
+                        tower_lsp::jsonrpc::Error::invalid_params("expected the uri of a file")
+                    })?;
+                Ok(self.include_tree(clean(uri)))
+            }
+            "sqf-analyzer.evaluateInGame" => {
+                let code = params
+                    .arguments
+                    .first()
+                    .and_then(|x| x.as_str())
+                    .ok_or_else(|| {
+                        tower_lsp::jsonrpc::Error::invalid_params("expected the code to evaluate")
+                    })?;
+                self.game_request(debug::Request::Evaluate {
+                    code: code.to_string(),
+                })
+                .await
+            }
+            "sqf-analyzer.getVariable" => {
+                let name = params
+                    .arguments
+                    .first()
+                    .and_then(|x| x.as_str())
+                    .ok_or_else(|| {
+                        tower_lsp::jsonrpc::Error::invalid_params("expected the name of a variable")
+                    })?;
+                self.game_request(debug::Request::GetVariable {
+                    name: name.to_string(),
+                })
+                .await
+            }
+            "sqf-analyzer.dataFlow" => {
+                let (uri, position) = params
+                    .arguments
+                    .first()
+                    .and_then(|x| x.as_str())
+                    .and_then(|x| Url::parse(x).ok())
+                    .zip(
+                        params
+                            .arguments
+                            .get(1)
+                            .and_then(|x| serde_json::from_value::<Position>(x.clone()).ok()),
+                    )
+                    .ok_or_else(|| {
+                        tower_lsp::jsonrpc::Error::invalid_params(
+                            "expected the uri of a file and a position",
+                        )
+                    })?;
+                Ok(self.data_flow(&clean(uri), position))
+            }
+            "sqf-analyzer.callGraph" => {
+                let graph = self.read_call_graph();
+                match params.arguments.first().and_then(|x| x.as_str()) {
+                    Some("dot") => Ok(Some(Value::String(graph.to_dot()))),
+                    None | Some("json") => Ok(serde_json::to_value(graph).ok()),
+                    Some(_) => Err(tower_lsp::jsonrpc::Error::invalid_params(
+                        "expected the format of the graph, \"dot\" or \"json\"",
+                    )),
+                }
+            }
+            "sqf-analyzer.setRule" => {
+                let argument = params.arguments.first();
+                let rule = argument
+                    .and_then(|x| x.get("rule"))
+                    .and_then(|x| x.as_str());
+                let severity = argument
+                    .and_then(|x| x.get("severity"))
+                    .and_then(|x| x.as_str());
+                let (Some(rule), Some(severity)) = (rule, severity) else {
+                    return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                        "expected {\"rule\": code, \"severity\": level}",
+                    ));
+                };
+                // `default` restores the level of the settings
+                if severity == "default" {
+                    self.command_rule_levels.write().unwrap().remove(rule);
+                } else {
+                    let level = rules::Level::from_setting(severity).ok_or_else(|| {
+                        tower_lsp::jsonrpc::Error::invalid_params(format!(
+                            "unknown severity \"{severity}\""
+                        ))
+                    })?;
+                    self.command_rule_levels
+                        .write()
+                        .unwrap()
+                        .insert(rule.to_string(), level);
+                }
+                self.republish().await;
+                Ok(None)
+            }
+            "sqf-analyzer.docgen" => {
+                if self.restricted.load(Ordering::Relaxed) {
+                    let mut error = tower_lsp::jsonrpc::Error::invalid_request();
+                    error.message = "documentation is not written in restricted mode".into();
+                    return Err(error);
+                }
+                let folder = params
+                    .arguments
+                    .first()
+                    .and_then(|x| x.as_str())
+                    .map(PathBuf::from)
+                    .ok_or_else(|| {
+                        tower_lsp::jsonrpc::Error::invalid_params(
+                            "expected the path of the output folder",
+                        )
+                    })?;
+                // relative to the first workspace folder, and never outside of the workspace folders
+                let folders = self.workspace_folders.read().unwrap().clone();
+                let folder = match folders.first() {
+                    Some(root) if folder.is_relative() => root.join(folder),
+                    _ => folder,
+                };
+                let is_within = !folder
+                    .components()
+                    .any(|x| x == std::path::Component::ParentDir)
+                    && folders.iter().any(|root| folder.starts_with(root));
+                if !is_within {
+                    return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                        "the output folder must be within a workspace folder",
+                    ));
+                }
+                let uri = params
+                    .arguments
+                    .get(1)
+                    .and_then(|x| x.as_str())
+                    .and_then(|x| Url::parse(x).ok())
+                    .map(clean);
+                let pages = self.docgen(&folder, uri).map_err(|error| {
+                    let mut result = tower_lsp::jsonrpc::Error::internal_error();
+                    result.message = format!("could not write the documentation: {error}").into();
+                    result
+                })?;
+                Ok(Some(Value::from(pages)))
+            }
+            _ => Err(tower_lsp::jsonrpc::Error::method_not_found()),
+        }
+    }
+
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        let uri = clean(params.text_document_position.text_document.uri);
+        self.client
+            .log_message(MessageType::INFO, format!("completion({})", &uri))
+            .await;
+        let position = params.text_document_position.position;
+        // `[` only triggers the completion of the types of `params`
+        let trigger = params.context.and_then(|x| x.trigger_character);
+        if trigger.as_deref() == Some("[") {
+            return Ok(self.params_type_completion(&uri, position));
+        }
+        Ok(self.0.completion(uri, position))
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct InlayHintParams {
+    path: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct GlobalsParams {
+    /// a document of the mission or addon whose globals are listed, all of the project's if absent
+    uri: Option<Url>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct InactiveRegionsParams {
+    uri: Url,
+    regions: Vec<Range>,
+}
+
+/// The regions of a document that are not compiled (the inactive branches of `#ifdef`), sent after
+/// each analysis
+enum InactiveRegions {}
+
+impl notification::Notification for InactiveRegions {
+    type Params = InactiveRegionsParams;
+    const METHOD: &'static str = "sqfAnalyzer/inactiveRegions";
+}
+
+#[derive(Clone)]
+struct TextDocumentItem {
     uri: Url,
     text: String,
     version: i32,
 }
 
-fn span_to_range((start, end): (usize, usize), rope: &Rope) -> Option<Range> {
-    let start_position = offset_to_position(start, rope)?;
-    let end_position = offset_to_position(end, rope)?;
-
-    Some(Range::new(start_position, end_position))
+fn span_to_range(span: (usize, usize), rope: &Rope) -> Option<Range> {
+    text_pos::range(span, rope)
 }
 
 impl Backend {
     fn get_definition(&self, uri: Url, position: Position) -> Option<GotoDefinitionResponse> {
+        if config::is_config(&uri.to_file_path().ok()?) && !self.script_headers.contains(&uri) {
+            return self.config_definition(&uri, position);
+        }
+        if let Some((_, definitions)) = self.macro_at(&uri, position) {
+            let locations = definitions
+                .into_iter()
+                .filter_map(|x| {
+                    let path = x.path.map(|x| x.to_path_buf());
+                    self.location(path.as_deref(), &uri, x.span)
+                })
+                .collect();
+            return Some(GotoDefinitionResponse::Array(locations));
+        }
         self.states.get(&uri).and_then(|state| {
             let rope = self.documents.get(&uri)?;
             let offset = position_to_offset(position, &rope)?;
 
-            let def = definition::get_definition(&state.0 .0, offset);
+            let def = definition::get_definition(&state.0.state, offset);
 
             def.and_then(|origin| {
-                let url = Url::from_file_path(origin.0.as_ref()).ok()?;
+                let url = Url::from_file_path(hemtt::map(origin.0.as_ref())).ok()?;
                 let range = self
                     .documents
                     .get(&url)
@@ -342,220 +1273,3601 @@ impl Backend {
         })
     }
 
-    /// Loads the project for the first time, publishing any diagnostics it can find during the process
-    async fn load_project(&self, uri: &Url, version: i32) {
-        if self.is_loaded.load(Ordering::Relaxed) {
-            return;
+    /// Definition of a function declared in `CfgFunctions` or of a path to a script in a config,
+    /// which is the script itself
+    fn config_definition(&self, uri: &Url, position: Position) -> Option<GotoDefinitionResponse> {
+        let config_path = uri.to_file_path().ok()?;
+        let addons = self.addons(&config_path);
+
+        let (path, name) = {
+            let rope = self.documents.get(uri)?;
+            let offset = position_to_offset(position, &rope)?;
+            let config = config::parse(&rope.to_string());
+
+            let path = config::value_at(&config, offset).and_then(|value| match &value.inner {
+                config::Value::String(value) if value.contains('.') => Some(value.clone()),
+                _ => None,
+            });
+            let name = config::functions::declarations(&config)
+                .into_iter()
+                .find(|x| x.span.0 <= offset && offset < x.span.1)
+                .map(|x| x.name);
+            (path, name)
         };
-        self.client
-            .log_message(MessageType::INFO, "loading mission or addon")
-            .await;
 
-        let all_addons = addon::find(uri);
-        for (addon_path, functions) in all_addons.iter() {
-            self.client
-                .log_message(
-                    MessageType::INFO,
-                    format!(
-                        "Found addon at \"{}\" with {} functions.",
-                        addon_path.display(),
-                        functions.len()
-                    ),
-                )
-                .await;
+        let path = match (path, name) {
+            (Some(path), _) => sqf::get_path(&path, &config_path, &addons).ok()?,
+            (None, Some(name)) => {
+                addon::function_path(&config_path, &name, &addons, &self.scope())?.ok()?
+            }
+            (None, None) => return None,
+        };
+        if !path.is_file() {
+            return None;
         }
-        if all_addons.is_empty() {
-            self.client
-                .log_message(MessageType::INFO, "neither mission nor addon found")
-                .await;
-            self.is_loaded.store(true, Ordering::Relaxed);
-            return;
+        let url = Url::from_file_path(hemtt::map(&path)).ok()?;
+        Some(GotoDefinitionResponse::Scalar(Location::new(
+            url,
+            Range::default(),
+        )))
+    }
+
+    fn code_actions(
+        &self,
+        uri: Url,
+        range: Range,
+        diagnostics: Vec<Diagnostic>,
+    ) -> Option<CodeActionResponse> {
+        let mut actions = vec![];
+        if config::is_config(&uri.to_file_path().ok()?) {
+            actions.extend(self.create_function_action(&uri, range, &diagnostics));
+        } else {
+            actions.extend(self.exact_comparison_actions(&uri, range, &diagnostics));
+            // the document is parsed once for all the actions, requested on every move of the cursor
+            let rope = self
+                .documents
+                .get(&uri)
+                .map(|x| x.clone())
+                .unwrap_or_default();
+            let text = rope.to_string();
+            if let Ok(ast) = sqf::preprocessor::parse(&text) {
+                let terms = tokens::terms(&ast);
+                let document = (&rope, terms.as_slice());
+                actions.extend(self.convert_sqs_action(&uri, document, range, &diagnostics));
+                actions.extend(self.switch_actions(&uri, document, range, &diagnostics));
+                actions.extend(self.params_action(&uri, document, range, &diagnostics));
+                actions.extend(self.annotate_params_action(&uri, document, range));
+                actions.extend(self.private_actions(&uri, document, range));
+            }
         }
+        actions.extend(self.suppress_actions(&uri, range, &diagnostics));
+        Some(actions)
+    }
 
-        let addon_paths = self.addon_paths.read().unwrap().clone();
+    /// The path of the file at `path` relative to the workspace folder containing it, with `/`
+    /// separators, and that folder
+    fn workspace_relative(&self, path: &Path) -> Option<(PathBuf, String)> {
+        let folders = self.workspace_folders.read().unwrap();
+        folders.iter().find_map(|folder| {
+            let relative = path.strip_prefix(folder).ok()?;
+            let relative = relative
+                .components()
+                .map(|x| x.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            Some((folder.clone(), relative))
+        })
+    }
 
-        let error_on_undefined = self.undefined_variables_are_error.load(Ordering::Relaxed);
-        let private_variables_in_mission_are_error = self
-            .private_variables_in_mission_are_error
-            .load(Ordering::Relaxed);
-        let error_on_unused = self.error_on_unused.load(Ordering::Relaxed);
+    /// Actions suppressing the diagnostics at `range`: on their line and in the file, by a comment,
+    /// and in the baseline of the workspace folder
+    fn suppress_actions(
+        &self,
+        uri: &Url,
+        range: Range,
+        diagnostics: &[Diagnostic],
+    ) -> Vec<CodeActionOrCommand> {
+        let Some(rope) = self.documents.get(uri).map(|x| x.clone()) else {
+            return vec![];
+        };
+        let baseline = uri
+            .to_file_path()
+            .ok()
+            .and_then(|path| self.workspace_relative(&path));
+        let action = |title: String, diagnostic: &Diagnostic, edit: WorkspaceEdit| {
+            CodeActionOrCommand::CodeAction(CodeAction {
+                title,
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![diagnostic.clone()]),
+                edit: Some(edit),
+                ..Default::default()
+            })
+        };
+        let insert = |position: Position, new_text: String| WorkspaceEdit {
+            changes: Some(HashMap::from([(
+                uri.clone(),
+                vec![TextEdit::new(Range::new(position, position), new_text)],
+            )])),
+            ..Default::default()
+        };
 
-        for (addon_path, functions) in all_addons {
-            let (states, originals) =
-                addon::process(addon_path.clone(), addon_paths.clone(), &functions);
+        let mut actions = vec![];
+        for diagnostic in diagnostics
+            .iter()
+            .filter(|x| x.range.start <= range.end && range.start <= x.range.end)
+        {
+            let Some(NumberOrString::String(code)) = &diagnostic.code else {
+                continue;
+            };
+            let line = diagnostic.range.start.line;
+            let indentation = rope
+                .get_line(line as usize)
+                .map(|x| {
+                    x.chars()
+                        .take_while(|c| *c == ' ' || *c == '\t')
+                        .collect::<String>()
+                })
+                .unwrap_or_default();
+            actions.push(action(
+                format!("Suppress `{code}` on this line"),
+                diagnostic,
+                insert(
+                    Position::new(line, 0),
+                    suppress::next_line_comment(&indentation, code),
+                ),
+            ));
+            actions.push(action(
+                format!("Suppress `{code}` in this file"),
+                diagnostic,
+                insert(Position::new(0, 0), suppress::file_comment(code)),
+            ));
 
-            // store the state of each of the functions
-            for (path, (function_name, state_semantic)) in states {
-                if let Ok(url) = Url::from_file_path(path) {
-                    self.states.insert(url, (state_semantic, function_name));
+            let Some((folder, file)) = &baseline else {
+                continue;
+            };
+            let path = folder.join(suppress::BASELINE_FILE_NAME);
+            let Ok(baseline_url) = Url::from_file_path(&path) else {
+                continue;
+            };
+            // the baseline as edited, if it is open
+            let current = self.read(&path);
+            let Ok(baseline) = current
+                .as_deref()
+                .map(suppress::Baseline::parse)
+                .unwrap_or_else(|| Ok(Default::default()))
+            else {
+                continue;
+            };
+            let content = baseline.with(suppress::Entry {
+                file: file.clone(),
+                rule: code.clone(),
+                message: diagnostic.message.clone(),
+            });
+            let mut operations = vec![];
+            let replaced = match &current {
+                Some(current) => {
+                    let current = Rope::from_str(current);
+                    Range::new(
+                        Position::new(0, 0),
+                        offset_to_position(current.len_chars(), &current).unwrap_or_default(),
+                    )
                 }
+                None => {
+                    operations.push(DocumentChangeOperation::Op(ResourceOp::Create(
+                        CreateFile {
+                            uri: baseline_url.clone(),
+                            options: Some(CreateFileOptions {
+                                overwrite: Some(false),
+                                ignore_if_exists: Some(true),
+                            }),
+                            annotation_id: None,
+                        },
+                    )));
+                    Range::default()
+                }
+            };
+            operations.push(DocumentChangeOperation::Edit(TextDocumentEdit {
+                text_document: OptionalVersionedTextDocumentIdentifier {
+                    uri: baseline_url,
+                    version: None,
+                },
+                edits: vec![OneOf::Left(TextEdit::new(replaced, content))],
+            }));
+            actions.push(action(
+                format!("Add `{code}` to the baseline"),
+                diagnostic,
+                WorkspaceEdit {
+                    document_changes: Some(DocumentChanges::Operations(operations)),
+                    ..Default::default()
+                },
+            ));
+        }
+        actions
+    }
+
+    /// Action creating a SQF script converted from the SQS script executed at `range`, and executing
+    /// it with `execVM` instead
+    fn convert_sqs_action(
+        &self,
+        uri: &Url,
+        (rope, terms): (&Rope, &[Spanned<&str>]),
+        range: Range,
+        diagnostics: &[Diagnostic],
+    ) -> Option<CodeActionOrCommand> {
+        let offset = position_to_offset(range.start, rope)?;
+        let usage = sqs::usages(terms)
+            .into_iter()
+            .find(|x| x.exec.0 <= offset && offset <= x.path.span.1)?;
+        let exec_range = span_to_range(usage.exec, rope)?;
+        let path_range = span_to_range(usage.path.span, rope)?;
+
+        let sqs_path = sqs::resolve(&usage.path.inner, &uri.to_file_path().ok()?);
+        let sqf_path = sqs_path.with_extension("sqf");
+        if sqf_path.exists() {
+            return None;
+        }
+        let sqf_url = Url::from_file_path(&sqf_path).ok()?;
+        let content = self.read(&sqs_path).unwrap_or_default();
+        let stem = &usage.path.inner[..usage.path.inner.len() - ".sqs".len()];
+        let script = format!("\"{stem}.sqf\"");
+
+        let edit = WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Operations(vec![
+                DocumentChangeOperation::Op(ResourceOp::Create(CreateFile {
+                    uri: sqf_url.clone(),
+                    options: Some(CreateFileOptions {
+                        overwrite: Some(false),
+                        ignore_if_exists: Some(true),
+                    }),
+                    annotation_id: None,
+                })),
+                DocumentChangeOperation::Edit(TextDocumentEdit {
+                    text_document: OptionalVersionedTextDocumentIdentifier {
+                        uri: sqf_url,
+                        version: None,
+                    },
+                    edits: vec![OneOf::Left(TextEdit {
+                        range: Range::default(),
+                        new_text: sqs::skeleton(&usage.path.inner, &content),
+                    })],
+                }),
+                DocumentChangeOperation::Edit(TextDocumentEdit {
+                    text_document: OptionalVersionedTextDocumentIdentifier {
+                        uri: uri.clone(),
+                        version: None,
+                    },
+                    edits: vec![
+                        OneOf::Left(TextEdit::new(exec_range, "execVM".to_string())),
+                        OneOf::Left(TextEdit::new(path_range, script)),
+                    ],
+                }),
+            ])),
+            ..Default::default()
+        };
+
+        Some(CodeActionOrCommand::CodeAction(CodeAction {
+            title: format!("Convert to \"{}\"", sqf_path.display()),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(
+                diagnostics
+                    .iter()
+                    .filter(|x| x.code == Some(NumberOrString::String("legacy-sqs".to_string())))
+                    .filter(|x| x.range.start.line == range.start.line)
+                    .cloned()
+                    .collect(),
+            ),
+            edit: Some(edit),
+            is_preferred: Some(true),
+            ..Default::default()
+        }))
+    }
+
+    /// Actions replacing the case-insensitive comparisons at `range` by exact ones (`isEqualTo`)
+    fn exact_comparison_actions(
+        &self,
+        uri: &Url,
+        range: Range,
+        diagnostics: &[Diagnostic],
+    ) -> Vec<CodeActionOrCommand> {
+        let Some(rope) = self.documents.get(uri) else {
+            return vec![];
+        };
+        diagnostics
+            .iter()
+            .filter(|x| {
+                x.code
+                    == Some(NumberOrString::String(
+                        "case-insensitive-comparison".to_string(),
+                    ))
+            })
+            .filter(|x| x.range.start <= range.end && range.start <= x.range.end)
+            .filter_map(|diagnostic| {
+                let start = position_to_offset(diagnostic.range.start, &rope)?;
+                let end = position_to_offset(diagnostic.range.end, &rope)?;
+                let operator = rope.get_slice(start..end)?.to_string();
+                let exact = lint::comparison::exact(&operator)?;
+                let edit = WorkspaceEdit {
+                    changes: Some(HashMap::from([(
+                        uri.clone(),
+                        vec![TextEdit::new(diagnostic.range, exact.to_string())],
+                    )])),
+                    ..Default::default()
+                };
+                Some(CodeActionOrCommand::CodeAction(CodeAction {
+                    title: format!("Compare exactly with `{exact}`"),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: Some(vec![diagnostic.clone()]),
+                    edit: Some(edit),
+                    ..Default::default()
+                }))
+            })
+            .collect()
+    }
+
+    /// Action declaring the parameters read with `_this select N` at the top of a script with a
+    /// single `params`
+    fn params_action(
+        &self,
+        uri: &Url,
+        (rope, terms): (&Rope, &[Spanned<&str>]),
+        range: Range,
+        diagnostics: &[Diagnostic],
+    ) -> Option<CodeActionOrCommand> {
+        let selections = params::selections(terms);
+        let span = (selections.first()?.span.0, selections.last()?.span.1);
+        let selection_range = span_to_range(span, rope)?;
+        if range.start > selection_range.end || selection_range.start > range.end {
+            return None;
+        }
+        let edit = WorkspaceEdit {
+            changes: Some(HashMap::from([(
+                uri.clone(),
+                vec![TextEdit::new(
+                    selection_range,
+                    params::declaration(&selections),
+                )],
+            )])),
+            ..Default::default()
+        };
+        Some(CodeActionOrCommand::CodeAction(CodeAction {
+            title: "Declare the parameters with params".to_string(),
+            kind: Some(CodeActionKind::REFACTOR_REWRITE),
+            diagnostics: Some(
+                diagnostics
+                    .iter()
+                    .filter(|x| x.code == Some(NumberOrString::String("this-select".to_string())))
+                    .cloned()
+                    .collect(),
+            ),
+            edit: Some(edit),
+            ..Default::default()
+        }))
+    }
+
+    /// Actions converting the private declarations of the scope at `range` to the keyword form
+    /// (`private _a = 1;`) or merging them in a `private [...]` at its entry, as preferred by the
+    /// project's `style.private` (both when unset)
+    fn private_actions(
+        &self,
+        uri: &Url,
+        (rope, terms): (&Rope, &[Spanned<&str>]),
+        range: Range,
+    ) -> Vec<CodeActionOrCommand> {
+        let Some(offset) = position_to_offset(range.start, rope) else {
+            return vec![];
+        };
+        let scope = private::scope_at(terms, offset);
+        let preferred = uri
+            .to_file_path()
+            .ok()
+            .and_then(|path| self.style(&path).private);
+
+        // removals of whole statements remove their line when alone on it
+        let text_edit = |(span, new_text): private::Edit| {
+            let line = rope.char_to_line(span.0);
+            let line_start = rope.line_to_char(line);
+            let line_end = line_start + rope.line(line).len_chars();
+            let is_alone = new_text.is_empty()
+                && rope
+                    .slice(line_start..span.0)
+                    .chars()
+                    .all(char::is_whitespace)
+                && rope
+                    .slice(span.1..line_end)
+                    .chars()
+                    .all(char::is_whitespace);
+            let span = if is_alone && span.0 != span.1 {
+                (line_start, line_end)
+            } else {
+                span
+            };
+            Some(TextEdit::new(span_to_range(span, rope)?, new_text))
+        };
+        let action = |title: &str, edits: Vec<private::Edit>| {
+            let edits = edits
+                .into_iter()
+                .map(text_edit)
+                .collect::<Option<Vec<_>>>()?;
+            Some(CodeActionOrCommand::CodeAction(CodeAction {
+                title: title.to_string(),
+                kind: Some(CodeActionKind::REFACTOR_REWRITE),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri.clone(), edits)])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }))
+        };
+
+        let mut actions = vec![];
+        if preferred != Some(private::Style::Array) {
+            let edits = private::to_keyword(terms, scope.clone());
+            if !edits.is_empty() {
+                actions.extend(action("Declare the private variables on assignment", edits));
+            }
+        }
+        if preferred != Some(private::Style::Keyword) {
+            if let Some((mut edits, (span, declaration))) = private::to_array(terms, scope) {
+                // a new statement before the first one, on its own line when it starts one
+                let declaration = if span.0 == span.1 {
+                    let line_start = rope.line_to_char(rope.char_to_line(span.0));
+                    let indent = rope.slice(line_start..span.0).to_string();
+                    if indent.chars().all(char::is_whitespace) {
+                        format!("{declaration}\n{indent}")
+                    } else {
+                        format!("{declaration} ")
+                    }
+                } else {
+                    declaration
+                };
+                edits.push((span, declaration));
+                actions.extend(action(
+                    "Merge the private declarations at the entry of the scope",
+                    edits,
+                ));
             }
+        }
+        actions
+    }
 
-            let diagnostics = originals
+    /// Source action adding default values and expected types, inferred from their usage, to the
+    /// parameters declared by the `params` at `range`
+    fn annotate_params_action(
+        &self,
+        uri: &Url,
+        (rope, terms): (&Rope, &[Spanned<&str>]),
+        range: Range,
+    ) -> Option<CodeActionOrCommand> {
+        let start = position_to_offset(range.start, rope)?;
+        let end = position_to_offset(range.end, rope)?;
+        let edits = params::declarations(terms)
+            .iter()
+            .filter(|x| terms[x.keyword].span.0 <= end && start <= terms[x.array.1].span.1)
+            .flat_map(|x| params::annotations(terms, x))
+            .map(|(span, new_text)| Some(TextEdit::new(span_to_range(span, rope)?, new_text)))
+            .collect::<Option<Vec<_>>>()?;
+        if edits.is_empty() {
+            return None;
+        }
+        Some(CodeActionOrCommand::CodeAction(CodeAction {
+            title: "Add default values and types to the parameters".to_string(),
+            kind: Some(CodeActionKind::SOURCE),
+            edit: Some(WorkspaceEdit {
+                changes: Some(HashMap::from([(uri.clone(), edits)])),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }))
+    }
+
+    /// Actions removing the duplicated cases of switches at `range`, and adding a `default` branch to
+    /// the ones without
+    fn switch_actions(
+        &self,
+        uri: &Url,
+        (rope, terms): (&Rope, &[Spanned<&str>]),
+        range: Range,
+        diagnostics: &[Diagnostic],
+    ) -> Vec<CodeActionOrCommand> {
+        let switches = lint::switch::switches(terms);
+        let is = |diagnostic: &Diagnostic, code: &str| {
+            diagnostic.code == Some(NumberOrString::String(code.to_string()))
+                && diagnostic.range.start <= range.end
+                && range.start <= diagnostic.range.end
+        };
+        let action = |title: String, diagnostic: &Diagnostic, edit: TextEdit| {
+            CodeActionOrCommand::CodeAction(CodeAction {
+                title,
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![diagnostic.clone()]),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+        };
+        let line_indent = |offset: usize| {
+            let line = rope.char_to_line(offset);
+            rope.line(line)
+                .chars()
+                .take_while(|c| *c == ' ' || *c == '\t')
+                .collect::<String>()
+        };
+
+        diagnostics
+            .iter()
+            .filter_map(|diagnostic| {
+                let start = position_to_offset(diagnostic.range.start, rope)?;
+                if is(diagnostic, "duplicate-case") {
+                    let case = switches
+                        .iter()
+                        .flat_map(|x| &x.cases)
+                        .find(|x| x.value.span.0 == start)?;
+                    // the whole lines of the case when it is alone on them
+                    let (mut from, mut to) = case.span;
+                    let line_start = rope.line_to_char(rope.char_to_line(from));
+                    let line_end = rope.line_to_char(rope.char_to_line(to) + 1);
+                    let blank = |x: std::ops::Range<usize>| {
+                        rope.slice(x).chars().all(|c| c.is_whitespace())
+                    };
+                    if blank(line_start..from) && blank(to..line_end) {
+                        (from, to) = (line_start, line_end);
+                    }
+                    let edit = TextEdit::new(span_to_range((from, to), rope)?, String::new());
+                    Some(action(
+                        format!("Remove the duplicated case {}", case.value.inner),
+                        diagnostic,
+                        edit,
+                    ))
+                } else if is(diagnostic, "missing-switch-default") {
+                    let switch = switches.iter().find(|x| x.keyword.0 == start)?;
+                    let end = switch.end.0;
+                    let line_start = rope.line_to_char(rope.char_to_line(end));
+                    let is_own_line = rope
+                        .slice(line_start..end)
+                        .chars()
+                        .all(|c| c.is_whitespace());
+                    let (at, new_text) = if is_own_line {
+                        let indent = switch
+                            .cases
+                            .last()
+                            .map(|x| line_indent(x.span.0))
+                            .unwrap_or_else(|| format!("{}    ", line_indent(end)));
+                        (line_start, format!("{indent}default {{}};\n"))
+                    } else {
+                        (end, "default {}; ".to_string())
+                    };
+                    let position = offset_to_position(at, rope)?;
+                    Some(action(
+                        "Add a default branch".to_string(),
+                        diagnostic,
+                        TextEdit::new(Range::new(position, position), new_text),
+                    ))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Action creating the script of a function declared in `CfgFunctions` whose file does not exist
+    fn create_function_action(
+        &self,
+        uri: &Url,
+        range: Range,
+        diagnostics: &[Diagnostic],
+    ) -> Option<CodeActionOrCommand> {
+        let declaration = {
+            let rope = self.documents.get(uri)?;
+            let offset = position_to_offset(range.start, &rope)?;
+            let config = config::parse(&rope.to_string());
+            config::functions::declarations(&config)
                 .into_iter()
-                // convert path to url. This is likely never filtered since originals only contain files that we could open
-                .filter_map(|x| Url::from_file_path(x.0).ok().map(|url| (url, x.1)))
-                // filter the current file because it may have not been saved and thus cannot be analyzed
-                .filter(|(url, _)| url != uri)
-                .flat_map(|(url, (content, errors))| {
-                    let rope = Rope::from_str(&content);
-                    errors
-                        .into_iter()
-                        .filter(|error| {
-                            error_on_undefined
-                                || (!matches!(error.type_, ErrorType::UndefinedVariable(_)))
-                        })
-                        .filter(|error| {
-                            private_variables_in_mission_are_error
-                                || (error.type_ != ErrorType::PrivateAssignedToMission)
-                        })
-                        .filter(|error| {
-                            error_on_unused || (error.type_ != ErrorType::UnusedVariable)
-                        })
-                        .filter_map(|error| {
-                            let origin = error
-                                .origin
-                                .clone()
-                                .and_then(|x| Url::from_file_path(x).ok())
-                                .unwrap_or_else(|| url.clone());
-                            to_diagnostic(error, &rope).map(|x| (origin, x))
-                        })
-                        .collect::<Vec<_>>()
-                })
-                // group errors by files.
-                // files may have errors from other files and thus need to be grouped by file
-                .fold(
-                    std::collections::BTreeMap::<_, Vec<_>>::new(),
-                    |mut acc, (a, b)| {
-                        acc.entry(a).or_default().push(b);
-                        acc
+                .find(|x| x.span.0 <= offset && offset < x.span.1)?
+        };
+
+        let config_path = uri.to_file_path().ok()?;
+        let addons = self.addons(&config_path);
+        let path =
+            addon::function_path(&config_path, &declaration.name, &addons, &self.scope())?.ok()?;
+        if path.exists() {
+            return None;
+        }
+        let url = Url::from_file_path(&path).ok()?;
+
+        let edit = WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Operations(vec![
+                DocumentChangeOperation::Op(ResourceOp::Create(CreateFile {
+                    uri: url.clone(),
+                    options: Some(CreateFileOptions {
+                        overwrite: Some(false),
+                        ignore_if_exists: Some(true),
+                    }),
+                    annotation_id: None,
+                })),
+                DocumentChangeOperation::Edit(TextDocumentEdit {
+                    text_document: OptionalVersionedTextDocumentIdentifier {
+                        uri: url,
+                        version: None,
                     },
-                );
+                    edits: vec![OneOf::Left(TextEdit {
+                        range: Range::default(),
+                        new_text: config::functions::template(&declaration.name),
+                    })],
+                }),
+            ])),
+            ..Default::default()
+        };
+
+        Some(CodeActionOrCommand::CodeAction(CodeAction {
+            title: format!("Create \"{}\"", path.display()),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(
+                diagnostics
+                    .iter()
+                    .filter(|x| x.range.start.line == range.start.line)
+                    .cloned()
+                    .collect(),
+            ),
+            edit: Some(edit),
+            is_preferred: Some(true),
+            ..Default::default()
+        }))
+    }
+
+    /// The addon prefixes applicable to the file at `path`: the ones of its project, overridden by
+    /// the editor settings
+    fn addons(&self, path: &Path) -> HashMap<Arc<str>, PathBuf> {
+        let mut addons = {
+            let projects = self.projects.read().unwrap();
+            project::find(&projects, path)
+                .map(|x| x.addons.clone())
+                .unwrap_or_default()
+        };
+        addons.extend(self.addon_paths.read().unwrap().get(path));
+        // includes via prefixes outside of the workspace are not followed in restricted mode
+        let scope = self.scope();
+        addons.retain(|_, path| scope.is_allowed(path));
+        addons
+    }
+
+    /// The files the analysis may read, see [`addon::Scope`]
+    fn scope(&self) -> addon::Scope {
+        self.scope.read().unwrap().clone()
+    }
+
+    /// The settings of the evaluation of `#ifdef`s
+    fn ifdef(&self) -> ifdef::Settings {
+        self.ifdef.read().unwrap().clone()
+    }
+
+    /// Returns the config declaring the function at `position` and its name, either from its
+    /// declaration in a config or from its usage in a script
+    fn function_at(&self, uri: &Url, position: Position) -> Option<(PathBuf, String)> {
+        let path = uri.to_file_path().ok()?;
+        let rope = self.documents.get(uri)?;
+        let offset = position_to_offset(position, &rope)?;
+        if config::is_config(&path) {
+            let config = config::parse(&rope.to_string());
+            let declaration = config::functions::declarations(&config)
+                .into_iter()
+                .find(|x| x.span.0 <= offset && offset < x.span.1)?;
+            return Some((path, declaration.name));
+        }
+
+        let (start, end) = word_at(offset, &rope)?;
+        let name = rope.slice(start..end).to_string();
+
+        self.function_files.iter().find_map(|x| {
+            x.value()
+                .get(UncasedStr::new(&name))
+                .map(|_| (x.key().clone(), name.clone()))
+        })
+    }
+
+    /// The range of the identifier renamed at `position` and the name to edit, or an error message
+    /// when it cannot be renamed, e.g. a command of the engine
+    fn prepare_rename(
+        &self,
+        uri: &Url,
+        position: Position,
+    ) -> std::result::Result<Option<PrepareRenameResponse>, String> {
+        let Some((range, word)) = (|| {
+            let rope = self.documents.get(uri)?;
+            let offset = position_to_offset(position, &rope)?;
+            let (start, end) = word_at(offset, &rope)?;
+            let word = rope.get_slice(start..end)?.to_string();
+            Some((span_to_range((start, end), &rope)?, word))
+        })() else {
+            return Ok(None);
+        };
+        if word.is_empty() {
+            return Ok(None);
+        }
+
+        if let Some((name, definitions)) = self.macro_at(uri, position) {
+            let text = |path: &Path| self.read(path);
+            let document = uri.to_file_path().map_err(|_| "Invalid document path")?;
+            let files = definitions
+                .iter()
+                .filter_map(|x| x.path.as_deref())
+                .chain(std::iter::once(document.as_path()));
+            for path in files {
+                if text(path).is_some_and(|content| macros::pasted(&content, &name).is_some()) {
+                    return Err(format!(
+                        "\"{name}\" may be formed by token-pasting (##) in {}, which cannot be renamed",
+                        path.display()
+                    ));
+                }
+            }
+            return Ok(Some(PrepareRenameResponse::Range(range)));
+        }
+
+        rename::check(&word)?;
+        match self.function_at(uri, position) {
+            // in configs, the class is under the cursor but the whole name is renamed
+            Some((_, name)) => Ok(Some(PrepareRenameResponse::RangeWithPlaceholder {
+                range,
+                placeholder: name,
+            })),
+            None => Err("Only functions and macros can be renamed".to_string()),
+        }
+    }
+
+    /// Renames a macro defined in the document `uri` or in one of its includes: its definitions and
+    /// its references in the defining file and in all known files including it
+    fn rename_macro(
+        &self,
+        uri: &Url,
+        name: &str,
+        definitions: &[macros::Macro],
+        new_name: &str,
+    ) -> std::result::Result<WorkspaceEdit, String> {
+        macros::validate_name(new_name)?;
+        let document = uri.to_file_path().map_err(|_| "Invalid document path")?;
+        let defining = definitions
+            .iter()
+            .map(|x| x.path.as_deref().map(addon::canonical))
+            .collect::<Vec<_>>();
+
+        let text = |url: &Url| -> Option<String> { self.read(&url.to_file_path().ok()?) };
+
+        let files = self
+            .states
+            .iter()
+            .map(|x| x.key().clone())
+            .chain(self.documents.iter().map(|x| x.key().clone()))
+            .chain(
+                defining
+                    .iter()
+                    .flatten()
+                    .filter_map(|x| Url::from_file_path(x).ok()),
+            )
+            .chain(std::iter::once(uri.clone()))
+            .collect::<std::collections::BTreeSet<_>>();
+
+        let mut operations = vec![];
+        for url in files {
+            let Some(path) = url.to_file_path().ok().filter(|x| !config::is_config(x)) else {
+                continue;
+            };
+            let Some(content) = text(&url) else {
+                continue;
+            };
+            // whether the file defines the macro or includes a file defining it
+            let is_affected = defining.iter().any(|defining| match defining {
+                None => path == document,
+                Some(header) => {
+                    path == *header
+                        || macros::included(&content, &path, &self.addons(&path), &self.scope())
+                            .iter()
+                            .any(|(x, _)| x == header)
+                }
+            });
+            if !is_affected {
+                continue;
+            }
+            let rope = Rope::from_str(&content);
+            if let Some(span) = macros::pasted(&content, name) {
+                let line = offset_to_position(span.0, &rope).unwrap_or_default().line;
+                return Err(format!(
+                    "\"{name}\" may be formed by token-pasting (##) in {}, line {}, which cannot be renamed",
+                    path.display(),
+                    line + 1
+                ));
+            }
+            let edits = macros::references(&content, name)
+                .into_iter()
+                .filter_map(|span| {
+                    let range = span_to_range(span, &rope)?;
+                    Some(OneOf::Left(TextEdit::new(range, new_name.to_string())))
+                })
+                .collect::<Vec<_>>();
+            if !edits.is_empty() {
+                operations.push(DocumentChangeOperation::Edit(TextDocumentEdit {
+                    text_document: OptionalVersionedTextDocumentIdentifier {
+                        uri: url,
+                        version: None,
+                    },
+                    edits,
+                }));
+            }
+        }
+
+        Ok(WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Operations(operations)),
+            ..Default::default()
+        })
+    }
+
+    /// Renames the function `name` declared in the config at `config_path` to `new_name`: its class
+    /// in the config, its script (when derived from the class name) and all references to it in the
+    /// project
+    fn rename_function(
+        &self,
+        config_path: &Path,
+        name: &str,
+        new_name: &str,
+    ) -> std::result::Result<WorkspaceEdit, String> {
+        let new_class = rename::function_class(name, new_name)?;
+
+        let text = |url: &Url| -> Option<Rope> {
+            self.documents.get(url).map(|x| x.clone()).or_else(|| {
+                let content = self.read(&url.to_file_path().ok()?)?;
+                Some(Rope::from_str(&content))
+            })
+        };
+
+        let config_url = Url::from_file_path(config_path).map_err(|_| "Invalid config path")?;
+        let config_rope = text(&config_url).ok_or("Could not read the config")?;
+        let config = config::parse(&config_rope.to_string());
+        let declaration = config::functions::declarations(&config)
+            .into_iter()
+            .find(|x| x.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("The declaration of \"{name}\" was not found in the config"))?;
+
+        let mut edits = HashMap::<Url, Vec<OneOf<TextEdit, AnnotatedTextEdit>>>::new();
+        let range = span_to_range(declaration.class.span, &config_rope).unwrap_or_default();
+        edits
+            .entry(config_url)
+            .or_default()
+            .push(OneOf::Left(TextEdit::new(range, new_class.clone())));
+
+        let files = self
+            .states
+            .iter()
+            .map(|x| x.key().clone())
+            .chain(self.documents.iter().map(|x| x.key().clone()))
+            .filter(|url| {
+                url.to_file_path()
+                    .map(|path| !config::is_config(&path))
+                    .unwrap_or(false)
+            })
+            .collect::<std::collections::BTreeSet<_>>();
+        // occurrences in strings may not refer to the function, and are confirmed by the user in the
+        // preview, grouped by the command they are passed to
+        let strings = self.rename_string_references.load(Ordering::Relaxed);
+        let mut change_annotations = HashMap::new();
+        for url in files {
+            let Some(rope) = text(&url) else {
+                continue;
+            };
+            let Ok(references) = rename::function_references(&rope.to_string(), name) else {
+                continue;
+            };
+            edits
+                .entry(url)
+                .or_default()
+                .extend(references.into_iter().filter_map(|reference| {
+                    let text_edit =
+                        TextEdit::new(span_to_range(reference.span, &rope)?, new_name.to_string());
+                    let command = match reference.kind {
+                        rename::ReferenceKind::Variable => return Some(OneOf::Left(text_edit)),
+                        rename::ReferenceKind::String(_) if !strings => return None,
+                        rename::ReferenceKind::String(command) => command,
+                    };
+                    let annotation_id = command.unwrap_or("string").to_string();
+                    change_annotations
+                        .entry(annotation_id.clone())
+                        .or_insert_with(|| ChangeAnnotation {
+                            label: match command {
+                                Some(command) => format!("Strings passed to {command}"),
+                                None => "Other strings".to_string(),
+                            },
+                            needs_confirmation: Some(true),
+                            description: Some(format!("Occurrences of \"{name}\" in strings")),
+                        });
+                    Some(OneOf::Right(AnnotatedTextEdit {
+                        text_edit,
+                        annotation_id,
+                    }))
+                }));
+        }
+
+        let mut operations = edits
+            .into_iter()
+            .filter(|(_, edits)| !edits.is_empty())
+            .map(|(uri, edits)| {
+                DocumentChangeOperation::Edit(TextDocumentEdit {
+                    text_document: OptionalVersionedTextDocumentIdentifier { uri, version: None },
+                    edits,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // the script is only renamed when its path is derived from the class name
+        let script = self
+            .function_files
+            .get(config_path)
+            .and_then(|x| x.get(UncasedStr::new(name)).cloned());
+        if let (None, Some(script)) = (&declaration.file, script) {
+            let old_file_name = format!("fn_{}.sqf", declaration.class.inner);
+            let is_derived = script
+                .file_name()
+                .map(|x| x.eq_ignore_ascii_case(&old_file_name))
+                .unwrap_or(false);
+            let old_uri = Url::from_file_path(&script);
+            let new_uri = Url::from_file_path(script.with_file_name(format!("fn_{new_class}.sqf")));
+            if let (true, Ok(old_uri), Ok(new_uri)) = (is_derived, old_uri, new_uri) {
+                operations.push(DocumentChangeOperation::Op(ResourceOp::Rename(
+                    RenameFile {
+                        old_uri,
+                        new_uri,
+                        options: None,
+                        annotation_id: None,
+                    },
+                )));
+            }
+        }
+
+        Ok(WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Operations(operations)),
+            change_annotations: (!change_annotations.is_empty()).then_some(change_annotations),
+            ..Default::default()
+        })
+    }
+
+    /// Loads the project for the first time, publishing any diagnostics it can find during the process
+    async fn load_project(&self, uri: &Url) {
+        if self.is_loaded.load(Ordering::Relaxed) {
+            return;
+        };
+        let generation = self.next_generation();
+        let start = Instant::now();
+        self.client
+            .log_message(MessageType::INFO, "loading mission or addon")
+            .await;
+
+        let all_addons = addon::find(uri, &self.scope());
+        for (addon_path, functions) in all_addons.iter() {
+            self.client
+                .log_message(
+                    MessageType::INFO,
+                    format!(
+                        "Found addon at \"{}\" with {} functions.",
+                        addon_path.display(),
+                        functions.len()
+                    ),
+                )
+                .await;
+        }
+        if all_addons.is_empty() {
+            self.client
+                .log_message(MessageType::INFO, "neither mission nor addon found")
+                .await;
+            self.is_loaded.store(true, Ordering::Relaxed);
+            return;
+        }
+
+        let configs = all_addons.iter().map(|x| x.0.clone()).collect::<Vec<_>>();
+        for path in addon::binarized(&configs, &self.scope()) {
+            let Ok(url) = Url::from_file_path(&path) else {
+                continue;
+            };
+            let lint = lint::Lint {
+                code: "binarized-file",
+                span: (0, 0),
+                message: "Binarized file skipped; point the analyzer at the source".to_string(),
+            };
+            let diagnostics = lint_to_diagnostic(lint, &Rope::new()).into_iter().collect();
+            self.publish(url, diagnostics, generation).await;
+        }
+
+        let mut processed = vec![];
+        let mut originals_bytes = 0;
+        for (addon_path, functions) in all_addons.iter() {
+            let addon_paths = self.addons(addon_path);
+            let mission_sqm = self.mission_sqm(addon_path);
+            // functions of addons embedded in a mission are available to the mission's scripts
+            let globals = mission_sqm
+                .iter()
+                .flat_map(|x| x.globals())
+                .chain(
+                    all_addons
+                        .iter()
+                        .filter(|(path, _)| addon::is_embedded(path, addon_path))
+                        .flat_map(|(path, functions)| {
+                            addon::function_globals(path, functions, &addon_paths)
+                        }),
+                )
+                .collect::<Vec<_>>();
+            let function_files = addon::function_files(addon_path, functions);
+            for directory in function_files
+                .values()
+                .filter_map(|x| x.parent())
+                .chain(addon_path.parent())
+            {
+                self.addon_roots
+                    .insert(directory.to_path_buf(), addon_path.clone());
+            }
+            self.function_files
+                .insert(addon_path.clone(), function_files);
+            let (states, originals) = addon::process(
+                addon_path.clone(),
+                addon_paths.clone(),
+                functions,
+                &globals,
+                self.max_file_size(),
+                &self.scope(),
+                &self.ifdef(),
+            );
+
+            // store the state of each of the functions
+            for (path, (function_name, state_semantic)) in states {
+                if let Ok(url) = Url::from_file_path(path) {
+                    self.states.insert(url, (state_semantic, function_name));
+                }
+            }
+
+            originals_bytes += originals.values().map(|x| x.0.len()).sum::<usize>();
+
+            // index the project before linting, since lints depend on the whole project
+            for (path, (content, _)) in originals.iter() {
+                if lint::too_large(content, self.max_file_size()).is_some() {
+                    continue;
+                }
+                if let Ok(url) = Url::from_file_path(path) {
+                    self.index(url, &fsm::sqf(path, content));
+                }
+            }
+            processed.push((addon_path, originals, mission_sqm));
+        }
+
+        let variable_keys = self.variable_key_index();
+        let events = self.event_index();
+        let ifdef = self.ifdef();
+        for (addon_path, originals, mission_sqm) in processed {
+            let naming = self.naming(addon_path);
+            let context = self.lint_context(
+                mission_sqm.as_deref(),
+                &variable_keys,
+                &events,
+                &naming.global_prefixes,
+                &ifdef,
+            );
+            let diagnostics = originals
+                .into_iter()
+                // convert path to url. This is likely never filtered since originals only contain files that we could open
+                .filter_map(|x| Url::from_file_path(x.0).ok().map(|url| (url, x.1)))
+                // filter the current file because it may have not been saved and thus cannot be analyzed
+                .filter(|(url, _)| url != uri)
+                .flat_map(|(url, (content, errors))| {
+                    self.preloaded.insert(url.clone(), content_hash(&content));
+                    let rope = Rope::from_str(&content);
+                    let lints = match lint::too_large(&content, self.max_file_size()) {
+                        Some(lint) => lint_to_diagnostic(lint, &rope)
+                            .map(|x| (url.clone(), x))
+                            .into_iter()
+                            .collect::<Vec<_>>(),
+                        None => {
+                            let path = url.to_file_path().unwrap_or_default();
+                            let text = fsm::sqf(&path, &content);
+                            lint::lint(&text, &context)
+                                .into_iter()
+                                .filter_map(|lint| {
+                                    lint_to_diagnostic(lint, &rope).map(|x| (url.clone(), x))
+                                })
+                                .chain(bracket_diagnostics(&url, &text, &rope, &ifdef))
+                                .collect()
+                        }
+                    };
+                    errors
+                        .into_iter()
+                        .filter_map(|error| {
+                            let origin = error
+                                .1
+                                .origin
+                                .clone()
+                                .and_then(|x| Url::from_file_path(hemtt::map(&x)).ok())
+                                .unwrap_or_else(|| url.clone());
+                            to_diagnostic(error, &rope).map(|x| (origin, x))
+                        })
+                        .chain(lints)
+                        .collect::<Vec<_>>()
+                })
+                // group errors by files.
+                // files may have errors from other files and thus need to be grouped by file
+                .fold(
+                    std::collections::BTreeMap::<_, Vec<_>>::new(),
+                    |mut acc, (a, b)| {
+                        acc.entry(a).or_default().push(b);
+                        acc
+                    },
+                );
+
+            // todo: use futures join to push them concurrently
+            for (url, diagnostics) in diagnostics {
+                self.publish(url, diagnostics, generation).await;
+            }
+        }
+        self.publish_unused_functions(generation).await;
+        *self.last_scan.write().unwrap() = Some((start.elapsed(), originals_bytes));
+        self.is_loaded.store(true, Ordering::Relaxed);
+    }
+
+    /// Reuses the analysis of a document done when the project was loaded, when it is opened for the
+    /// first time since and its content is the one analyzed: its state (and thus its semantic tokens)
+    /// and its diagnostics are kept instead of analyzing it again. Returns whether it was reused.
+    async fn warm_start(&self, params: &TextDocumentItem) -> bool {
+        let uri = clean(params.uri.clone());
+        let Some((_, hash)) = self.preloaded.remove(&uri) else {
+            return false;
+        };
+        if hash != content_hash(&params.text) || !self.states.contains_key(&uri) {
+            return false;
+        }
+        self.versions.insert(uri.clone(), params.version);
+        self.documents
+            .insert(uri.clone(), Rope::from_str(&params.text));
+        // published again with the version of the document
+        let published = self.diagnostics.get(&uri).map(|x| x.clone());
+        if let Some((diagnostics, generation)) = published {
+            self.publish(uri.clone(), diagnostics, generation).await;
+        }
+        if let Ok(path) = uri.to_file_path() {
+            self.send_inactive_regions(&uri, &fsm::sqf(&path, &params.text))
+                .await;
+        }
+        true
+    }
+
+    /// Analyzes a document and publishes its diagnostics. Unless `syntax_checked` (by
+    /// [`Backend::on_syntax_change`]), its syntax errors are first published on their own, before the
+    /// project is loaded and the document analyzed, and then replaced by the full set.
+    async fn on_change(&self, params: TextDocumentItem, syntax_checked: bool) {
+        if !syntax_checked {
+            self.on_syntax_change(params.clone(), false).await;
+        }
+        let uri = clean(params.uri);
+        self.preloaded.remove(&uri);
+        self.versions.insert(uri.clone(), params.version);
+
+        self.load_project(&uri).await;
+        let generation = self.next_generation();
+
+        self.client
+            .log_message(MessageType::INFO, format!("{}", &uri))
+            .await;
+
+        let file_path = uri.to_file_path().expect("utf-8 path");
+        let is_root = file_path.file_name().is_some_and(|x| {
+            x.eq_ignore_ascii_case("config.cpp") || x.eq_ignore_ascii_case("description.ext")
+        });
+        if is_root {
+            // addons or missions may have been added
+            self.addon_roots.clear();
+        }
+        // headers of scripts are analyzed in the context of a script including them
+        let header_context = self.header_context(&file_path);
+        if config::is_config(&file_path) && header_context.is_some() {
+            self.script_headers.insert(uri.clone());
+        } else {
+            self.script_headers.remove(&uri);
+        }
+        if config::is_config(&file_path) && header_context.is_none() {
+            // only the UI event handlers of config documents are analyzed as SQF
+            self.documents
+                .insert(uri.clone(), ropey::Rope::from_str(&params.text));
+            if !self.is_excluded(&file_path) && self.scope().is_allowed(&file_path) {
+                self.analyze_handlers(&uri, &params.text, &file_path, generation)
+                    .await;
+            }
+            return;
+        }
+
+        if self.is_excluded(&file_path) || !self.scope().is_allowed(&file_path) {
+            self.documents
+                .insert(uri.clone(), ropey::Rope::from_str(&params.text));
+            self.publish(uri, vec![], generation).await;
+            return;
+        }
+
+        if let Some(lint) = lint::too_large(&params.text, self.max_file_size()) {
+            let rope = ropey::Rope::from_str(&params.text);
+            let diagnostics = lint_to_diagnostic(lint, &rope).into_iter().collect();
+            self.documents.insert(uri.clone(), rope);
+            self.states.remove(&uri);
+            self.publish(uri, diagnostics, generation).await;
+            return;
+        }
+
+        let cycles = include::cycles(
+            &params.text,
+            &file_path,
+            &self.addons(&file_path),
+            &self.scope(),
+        );
+        if !cycles.is_empty() {
+            // the includes cannot be expanded: keep the previous analysis, but highlight the current text
+            let rope = ropey::Rope::from_str(&params.text);
+            let diagnostics = cycles
+                .into_iter()
+                .filter_map(|lint| lint_to_diagnostic(lint, &rope))
+                .collect();
+            self.documents.insert(uri.clone(), rope);
+            if let Some(mut e) = self.states.get_mut(&uri) {
+                e.value_mut().0.invalidate();
+            }
+            self.publish(uri, diagnostics, generation).await;
+            return;
+        }
+
+        let base_path = self.addon_root(&file_path);
+        let mission_sqm = self.mission_sqm(&base_path);
+
+        let mission = self
+            .states
+            .iter()
+            .filter(|x| x.key() != &uri)
+            .flat_map(|x| x.0.state.globals(x.1.clone()))
+            .chain(mission_sqm.iter().flat_map(|x| x.globals()))
+            .collect();
+
+        self.documents
+            .insert(uri.clone(), ropey::Rope::from_str(&params.text));
+        // the code of FSMs, at the same positions as in the document
+        let text = fsm::sqf(&file_path, &params.text);
+
+        let configuration = sqf::analyzer::Configuration {
+            addons: self.addons(&file_path),
+            file_path: file_path.clone().into(),
+            base_path,
+        };
+
+        let budget = match self.analysis_timeout_ms.load(Ordering::Relaxed) {
+            0 => None,
+            ms => Some(Duration::from_millis(ms)),
+        };
+        let mut truncated = false;
+        let analyzed = match &header_context {
+            Some(context) => format!("{context}{text}"),
+            None => text.to_string(),
+        };
+        let analysis_start = Instant::now();
+        let outcome = compute_within(analyzed, configuration, mission, self.ifdef(), budget).await;
+        self.record_analysis(analysis_start);
+        let (mut state, mut errors) = match outcome {
+            Outcome::Complete(result) => match *result {
+                Ok((state, errors)) => (Some(state), errors),
+                Err(e) => (None, vec![e]),
+            },
+            Outcome::Truncated(errors) => {
+                truncated = true;
+                (None, errors)
+            }
+        };
+        if let Some(context) = &header_context {
+            analyze::shift(state.as_mut(), &mut errors, context.chars().count());
+        }
+
+        let functions = self.function_index();
+        let calls = self.calls.get(&uri).map(|x| x.clone());
+        let addon_usages = self.addon_usages.get(&uri).map(|x| x.clone());
+        self.index(uri.clone(), &text);
+        // the configs are linted against the functions and the addons used over the project
+        if self.function_index() != functions
+            || self.calls.get(&uri).map(|x| x.clone()) != calls
+            || self.addon_usages.get(&uri).map(|x| x.clone()) != addon_usages
+        {
+            self.publish_unused_functions(generation).await;
+        }
+        let variable_keys = self.variable_key_index();
+        let events = self.event_index();
+        let naming = self.naming(&file_path);
+        let ifdef = self.ifdef();
+        let defined_macros =
+            macros::defined(&text, &file_path, &self.addons(&file_path), &self.scope())
+                .into_iter()
+                .map(|x| x.name)
+                .chain(ifdef.defines.iter().cloned())
+                .collect();
+        let functions = self.functions_of(&file_path);
+        let context = lint::Context {
+            macros: Some(&defined_macros),
+            functions: &functions,
+            types: state.as_ref().map(|x| &x.types),
+            ..self.lint_context(
+                mission_sqm.as_deref(),
+                &variable_keys,
+                &events,
+                &naming.global_prefixes,
+                &ifdef,
+            )
+        };
+        let truncation = truncated.then(|| lint::Lint {
+            code: "analysis-truncated",
+            span: (0, 0),
+            message: format!(
+                "The analysis of this file exceeded {} ms and was truncated; only its syntax errors and the lints of the server are reported",
+                budget.unwrap_or_default().as_millis()
+            ),
+        });
+        let lints = lint::lint(&text, &context)
+            .into_iter()
+            .chain(truncation)
+            .filter_map(|lint| {
+                let rope = self.documents.get(&uri)?;
+                lint_to_diagnostic(lint, &rope).map(|x| (uri.clone(), x))
+            })
+            .chain(self.redefinition_diagnostics(&uri, &text, &file_path))
+            .chain(
+                self.unused_macros(&text, &file_path)
+                    .into_iter()
+                    .filter_map(|lint| {
+                        let rope = self.documents.get(&uri)?;
+                        lint_to_diagnostic(lint, &rope).map(|x| (uri.clone(), x))
+                    }),
+            )
+            .chain(
+                self.documents
+                    .get(&uri)
+                    .map(|rope| bracket_diagnostics(&uri, &text, &rope, &self.ifdef()))
+                    .unwrap_or_default(),
+            )
+            .collect::<Vec<_>>();
+
+        let diagnostics = errors
+            .into_iter()
+            .filter_map(|error| {
+                let origin = error
+                    .1
+                    .origin
+                    .clone()
+                    .and_then(|x| Url::from_file_path(hemtt::map(&x)).ok())
+                    .unwrap_or_else(|| uri.clone());
+                let rope = self.documents.get(&origin)?;
+                to_diagnostic(error, &rope).map(|x| (origin, x))
+            })
+            .chain(lints)
+            .fold(
+                std::collections::BTreeMap::<_, Vec<_>>::new(),
+                |mut acc, (a, b)| {
+                    acc.entry(a).or_default().push(b);
+                    acc
+                },
+            );
+
+        if self.is_outdated(&uri, params.version) {
+            // a newer version of the document is being analyzed
+            return;
+        }
+
+        if diagnostics.is_empty() {
+            self.publish(uri.clone(), vec![], generation).await;
+        }
+        for (url, diagnostics) in diagnostics {
+            self.publish(url, diagnostics, generation).await;
+        }
+        self.send_inactive_regions(&uri, &text).await;
+
+        let Some(state) = state else {
+            // keep the previous analysis, but highlight the current text
+            match self.states.get_mut(&uri) {
+                Some(mut e) => e.value_mut().0.invalidate(),
+                None => {
+                    self.states.insert(uri.clone(), (Analysis::default(), None));
+                }
+            }
+            return;
+        };
+
+        if let Some(mut e) = self.states.get_mut(&uri) {
+            self.client
+                .log_message(MessageType::INFO, format!("{:?}", &state.origins))
+                .await;
+            e.value_mut().0 = Analysis::new(state);
+        } else {
+            self.states.insert(uri, (Analysis::new(state), None));
+        };
+    }
+
+    /// Sends the regions of a document that are not compiled, for the client to dim them
+    async fn send_inactive_regions(&self, uri: &Url, text: &str) {
+        if !self.inactive_regions.load(Ordering::Relaxed) {
+            return;
+        }
+        let Some(rope) = self.documents.get(uri).map(|x| x.clone()) else {
+            return;
+        };
+        let regions = analyze::preprocess(text, |ast| ifdef::evaluate(ast, &self.ifdef()).1)
+            .map(|(regions, _)| regions)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|span| span_to_range(span, &rope))
+            .collect();
+        self.client
+            .send_notification::<InactiveRegions>(InactiveRegionsParams {
+                uri: uri.clone(),
+                regions,
+            })
+            .await;
+    }
+
+    /// Checks the syntax of a document, without analyzing it.
+    /// Unless `always_publish`, the diagnostics are only published when there are syntax errors, so
+    /// that those of the last analysis remain until the next one.
+    async fn on_syntax_change(&self, params: TextDocumentItem, always_publish: bool) {
+        let uri = clean(params.uri);
+        self.versions.insert(uri.clone(), params.version);
+        let generation = self.next_generation();
+        let rope = ropey::Rope::from_str(&params.text);
+        self.documents.insert(uri.clone(), rope.clone());
+        // the semantic tokens are of the previous text
+        if let Some(mut e) = self.states.get_mut(&uri) {
+            e.value_mut().0.invalidate();
+        }
+
+        let file_path = uri.to_file_path().expect("utf-8 path");
+        if config::is_config(&file_path)
+            || self.is_excluded(&file_path)
+            || !self.scope().is_allowed(&file_path)
+            || lint::too_large(&params.text, self.max_file_size()).is_some()
+        {
+            return;
+        }
+
+        let text = fsm::sqf(&file_path, &params.text);
+        let configuration = sqf::analyzer::Configuration {
+            addons: self.addons(&file_path),
+            file_path: file_path.clone().into(),
+            ..Default::default()
+        };
+        let diagnostics = analyze::check_syntax(&text, &configuration, &self.ifdef())
+            .into_iter()
+            .filter(|x| x.1.origin.is_none())
+            .filter_map(|error| to_diagnostic(error, &rope))
+            .chain(
+                bracket_diagnostics(&uri, &text, &rope, &self.ifdef())
+                    .into_iter()
+                    .map(|(_, x)| x),
+            )
+            .collect::<Vec<_>>();
+        if (always_publish || !diagnostics.is_empty()) && !self.is_outdated(&uri, params.version) {
+            self.publish(uri, diagnostics, generation).await;
+        }
+    }
+
+    /// Updates the functions declared in a (saved) config, retracting the state and diagnostics of
+    /// the scripts that are no longer functions
+    async fn sync_functions(&self, uri: &Url, config_path: PathBuf) {
+        let generation = self.next_generation();
+        let Some(functions) = addon::functions(&config_path, &self.scope()) else {
+            return;
+        };
+        let new = addon::function_files(&config_path, &functions);
+        let Some(old) = self.function_files.insert(config_path.clone(), new.clone()) else {
+            // not part of the loaded project
+            return;
+        };
+
+        for (name, path) in old {
+            let Ok(url) = Url::from_file_path(&path) else {
+                continue;
+            };
+            // the function may have been renamed while keeping its script
+            if let Some((new_name, _)) = new.iter().find(|(_, x)| **x == path) {
+                if let Some(mut state) = self.states.get_mut(&url) {
+                    state.1 = Some(new_name.clone());
+                }
+                continue;
+            }
+            self.client
+                .log_message(
+                    MessageType::INFO,
+                    format!("function \"{}\" was removed", name),
+                )
+                .await;
+            if self.documents.contains_key(&url) {
+                // open documents are still analyzed, just no longer as a function
+                if let Some(mut state) = self.states.get_mut(&url) {
+                    state.1 = None;
+                }
+                continue;
+            }
+            self.states.remove(&url);
+            self.variable_keys.remove(&url);
+            self.events.remove(&url);
+            self.calls.remove(&url);
+            self.addon_usages.remove(&url);
+            self.publish(url, vec![], generation).await;
+        }
+
+        // errors of the declarations themselves
+        let Some(rope) = self.documents.get(uri).map(|x| x.clone()) else {
+            return;
+        };
+        let diagnostics = addon::declaration_errors(&config_path, &functions)
+            .into_iter()
+            .filter_map(|error| to_diagnostic((Stage::Analyzer, error), &rope))
+            .collect();
+        self.publish(uri.clone(), diagnostics, generation).await;
+    }
+
+    /// Returns the tree of the files included by a file, as a JSON object
+    fn include_tree(&self, uri: Url) -> Option<Value> {
+        let path = uri.to_file_path().ok()?;
+        let text = self.read(&path)?;
+        let includes = include::tree(&text, &path, &self.addons(&path), &self.scope());
+        Some(serde_json::json!({
+            "path": path,
+            "includes": includes,
+        }))
+    }
+
+    /// The directives preceding the header at `path` in the first analyzed script including it, or
+    /// no context (`""`) for `.inc` and `.h` headers included by no script. `None` when the file is
+    /// not a header of scripts.
+    fn header_context(&self, path: &Path) -> Option<String> {
+        if !header::is_header(path) {
+            return None;
+        }
+        let mut scripts = self
+            .states
+            .iter()
+            .filter_map(|x| x.key().to_file_path().ok())
+            .filter(|x| x != path && !config::is_config(x))
+            .collect::<Vec<_>>();
+        scripts.sort();
+        let context = scripts.iter().find_map(|script| {
+            let text = self.read(script)?;
+            header::context(&text, script, path, &self.addons(script), &self.scope())
+        });
+        context.or_else(|| (!config::is_config(path)).then(String::new))
+    }
+
+    /// The edits of the configs declaring the functions whose scripts are moved from `old` to `new`
+    /// (a script or a directory of scripts): their `file` attribute is rewritten, or added when the
+    /// path was derived from the class and category
+    fn moved_script_edits(&self, old: &Path, new: &Path) -> Vec<(Url, TextEdit)> {
+        let mut edits = vec![];
+        for files in self.function_files.iter() {
+            let config_path = files.key();
+            let moved = files
+                .value()
+                .iter()
+                .filter_map(|(name, script)| {
+                    let relative = script.strip_prefix(old).ok()?;
+                    Some((name.clone(), script.clone(), new.join(relative)))
+                })
+                .collect::<Vec<_>>();
+            if moved.is_empty() {
+                continue;
+            }
+            let (Some(text), Ok(url)) = (self.read(config_path), Url::from_file_path(config_path))
+            else {
+                continue;
+            };
+            let rope = Rope::from_str(&text);
+            let declarations = config::functions::declarations(&config::parse(&text));
+            for (name, script, new_script) in moved {
+                let Some(declaration) = declarations
+                    .iter()
+                    .find(|x| UncasedStr::new(&x.name) == name.as_ref())
+                else {
+                    continue;
+                };
+                let declared = declaration.script();
+                let Some(path) = rename::moved_script(&declared.inner, &script, &new_script) else {
+                    continue;
+                };
+                let edit = match &declaration.file {
+                    Some(file) => {
+                        let Some(range) = span_to_range(file.span, &rope) else {
+                            continue;
+                        };
+                        // files given by macros are left as is
+                        if rope.get_char(file.span.0) != Some('"') {
+                            continue;
+                        }
+                        TextEdit::new(range, format!("\"{path}\""))
+                    }
+                    None => {
+                        let class = rope
+                            .slice(declaration.span.0..declaration.span.1)
+                            .to_string();
+                        let Some(range) = span_to_range(declaration.span, &rope) else {
+                            continue;
+                        };
+                        let attribute = format!("file = \"{path}\";");
+                        let new_text = match class.find('{') {
+                            Some(brace) => {
+                                format!("{} {attribute}{}", &class[..=brace], &class[brace + 1..])
+                            }
+                            None => format!(
+                                "{} {{ {attribute} }};",
+                                class.trim_end().trim_end_matches(';')
+                            ),
+                        };
+                        TextEdit::new(range, new_text)
+                    }
+                };
+                edits.push((url.clone(), edit));
+            }
+        }
+        edits
+    }
+
+    /// The config of the addon or mission of the file at `path`, empty if none, cached by directory
+    fn addon_root(&self, path: &Path) -> PathBuf {
+        let directory = path.parent().unwrap_or(path);
+        self.statistics
+            .addon_lookups
+            .fetch_add(1, Ordering::Relaxed);
+        if let Some(config) = self.addon_roots.get(directory) {
+            self.statistics
+                .addon_cache_hits
+                .fetch_add(1, Ordering::Relaxed);
+            return config.clone();
+        }
+        let config = addon::identify(path.to_path_buf(), &self.scope())
+            .map(|x| x.0)
+            .unwrap_or_default();
+        self.addon_roots
+            .insert(directory.to_path_buf(), config.clone());
+        config
+    }
+
+    /// The content of the file at `path`: the open document, or the file on disk unless it is
+    /// outside of the workspace folders in restricted mode. All reads of files go through it.
+    fn read(&self, path: &Path) -> Option<String> {
+        Url::from_file_path(path)
+            .ok()
+            .and_then(|url| self.documents.get(&url).map(|x| x.to_string()))
+            .or_else(|| {
+                self.scope()
+                    .is_allowed(path)
+                    .then(|| std::fs::read_to_string(path).ok())
+                    .flatten()
+            })
+    }
+
+    /// The lints of the dependencies of the addon of the config at `url`: the cycles of calls with
+    /// the other addons of the project it does not require, and the addons its scripts use (by
+    /// include, `EFUNC`-like macros or calls) without requiring them, or the other way around
+    fn dependency_lints(&self, url: &Url, graph: &call_graph::Graph) -> Vec<lint::Lint> {
+        use lint::dependencies::{self, Usage};
+
+        let Ok(path) = url.to_file_path() else {
+            return vec![];
+        };
+        let configs = self
+            .function_files
+            .iter()
+            .map(|x| {
+                let functions = x.value().keys().map(|x| x.to_string()).collect::<Vec<_>>();
+                (x.key().clone(), functions)
+            })
+            .collect::<Vec<_>>();
+        let Some(index) = configs.iter().position(|(x, _)| *x == path) else {
+            return vec![];
+        };
+        let patches = |config: &Path| {
+            config::patches::patches(&config::parse(&self.read(config).unwrap_or_default()))
+        };
+        let components = configs
+            .into_iter()
+            .map(|(config, functions)| dependencies::Component {
+                patches: patches(&config),
+                functions,
+            })
+            .collect::<Vec<_>>();
+        let component = &components[index];
+        let names = |patches: &[config::patches::Patch]| {
+            patches
+                .iter()
+                .map(|x| x.name.inner.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let Some(directory) = path.parent() else {
+            return vec![];
+        };
+        // the other addons used by the scripts of the addon, as indexed
+        let scripts = self
+            .addon_usages
+            .iter()
+            .filter(|x| {
+                x.key()
+                    .to_file_path()
+                    .is_ok_and(|x| x.starts_with(directory))
+            })
+            .flat_map(|x| x.value().clone())
+            .collect::<Vec<_>>();
+        let mut usages = scripts
+            .into_iter()
+            .map(|(other, reason)| Usage {
+                patches: names(&patches(&other)),
+                reason,
+            })
+            .collect::<Vec<_>>();
+        let own = |name: &str| {
+            component
+                .functions
+                .iter()
+                .any(|x| x.eq_ignore_ascii_case(name))
+        };
+        for edge in graph.edges.iter().filter(|x| own(&x.from)) {
+            let other = components
+                .iter()
+                .find(|x| x.functions.iter().any(|f| f.eq_ignore_ascii_case(&edge.to)));
+            if let Some(other) = other.filter(|x| *x != component) {
+                usages.push(Usage {
+                    patches: names(&other.patches),
+                    reason: format!("{} calls {}", edge.from, edge.to),
+                });
+            }
+        }
+
+        let project = components
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != index)
+            .flat_map(|(_, x)| names(&x.patches))
+            .collect::<Vec<_>>();
+        dependencies::cycles(graph, &components, index)
+            .into_iter()
+            .chain(dependencies::requirements(component, &usages, &project))
+            .collect()
+    }
+
+    /// The graph of the calls between the functions declared in the `CfgFunctions` of the project,
+    /// from the calls of each script as indexed when it was analyzed
+    fn call_graph(&self) -> call_graph::Graph {
+        let functions = self
+            .function_files
+            .iter()
+            .flat_map(|x| {
+                x.value()
+                    .iter()
+                    .map(|(name, path)| (name.to_string(), path.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        call_graph::Graph::from_calls(functions.into_iter().map(|(name, path)| {
+            let calls = Url::from_file_path(&path)
+                .ok()
+                .and_then(|url| self.calls.get(&url).map(|x| x.clone()))
+                .unwrap_or_default();
+            (name, calls)
+        }))
+    }
+
+    /// [`Backend::call_graph`] from the current content of the scripts rather than the index, for
+    /// its export (`sqf-analyzer.callGraph`)
+    fn read_call_graph(&self) -> call_graph::Graph {
+        let functions = self
+            .function_files
+            .iter()
+            .flat_map(|x| {
+                x.value()
+                    .iter()
+                    .map(|(name, path)| (name.to_string(), path.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let texts = functions
+            .into_iter()
+            .map(|(name, path)| {
+                let text = self.read(&path).unwrap_or_default();
+                (name, text)
+            })
+            .collect::<Vec<_>>();
+        let asts = texts
+            .iter()
+            .map(|(name, text)| (name, sqf::preprocessor::parse(text).unwrap_or_default()))
+            .collect::<Vec<_>>();
+        call_graph::Graph::new(
+            asts.iter()
+                .map(|(name, ast)| (name.to_string(), tokens::terms(ast))),
+        )
+    }
+
+    /// Writes into `folder` the Markdown documentation of the functions of the project, or of the
+    /// mission or addon of `uri`: a page per function and an index. Returns the paths written.
+    fn docgen(&self, folder: &Path, uri: Option<Url>) -> std::io::Result<Vec<String>> {
+        let root = uri
+            .and_then(|x| x.to_file_path().ok())
+            .map(|x| self.addon_root(&x));
+        let mut functions = self
+            .function_files
+            .iter()
+            .filter(|x| root.as_ref().is_none_or(|root| x.key() == root))
+            .flat_map(|x| x.value().clone())
+            .collect::<Vec<_>>();
+        functions.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let outputs = self
+            .states
+            .iter()
+            .flat_map(|x| x.0.state.globals(x.1.clone()))
+            .map(|(name, (_, output))| (name, output))
+            .collect::<HashMap<_, _>>();
+        let graph = self.call_graph();
+
+        std::fs::create_dir_all(folder)?;
+        let mut written = vec![];
+        let mut names = vec![];
+        for (name, script) in functions {
+            let text = self.read(&script).unwrap_or_default();
+            let ast = sqf::preprocessor::parse(&text).unwrap_or_default();
+            let terms = tokens::terms(&ast);
+            let signature = hover::signature(outputs.get(&name).unwrap_or(&None));
+            let callers = graph
+                .edges
+                .iter()
+                .filter(|x| x.to.eq_ignore_ascii_case(name.as_str()))
+                .map(|x| x.from.clone())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect::<Vec<_>>();
+            let page = docgen::page(
+                name.as_str(),
+                &docgen::header(&text),
+                signature.as_deref(),
+                &docgen::parameters(&text, &terms),
+                &callers,
+            );
+            let path = folder.join(format!("{name}.md"));
+            std::fs::write(&path, page)?;
+            written.push(path.display().to_string());
+            names.push(name.to_string());
+        }
+        let path = folder.join("index.md");
+        std::fs::write(&path, docgen::index(&names))?;
+        written.push(path.display().to_string());
+        Ok(written)
+    }
+
+    /// The data flow of the local variable at `position`: the ranges of its declaration, writes and
+    /// reads
+    fn data_flow(&self, uri: &Url, position: Position) -> Option<Value> {
+        let rope = self.documents.get(uri)?;
+        let offset = position_to_offset(position, &rope)?;
+        let text = rope.to_string();
+        let ast = sqf::preprocessor::parse(&text).ok()?;
+        let state = self.states.get(uri)?;
+        let flow = data_flow::data_flow(&state.0.state, &tokens::terms(&ast), offset)?;
+        let ranges = |spans: &[(usize, usize)]| {
+            spans
+                .iter()
+                .filter_map(|x| span_to_range(*x, &rope))
+                .collect::<Vec<_>>()
+        };
+        Some(serde_json::json!({
+            "name": flow.name,
+            "declaration": span_to_range(flow.declaration, &rope),
+            "writes": ranges(&flow.writes),
+            "reads": ranges(&flow.reads),
+        }))
+    }
+
+    /// Highlights the declaration and writes (as writes) and the reads of the variable at `position`
+    fn document_highlights(&self, uri: &Url, position: Position) -> Option<Vec<DocumentHighlight>> {
+        let rope = self.documents.get(uri)?;
+        let offset = position_to_offset(position, &rope)?;
+        let text = rope.to_string();
+        let ast = sqf::preprocessor::parse(&text).ok()?;
+        let state = self.states.get(uri)?;
+        let flow = data_flow::data_flow(&state.0.state, &tokens::terms(&ast), offset)?;
+        let highlight = |span, kind| {
+            Some(DocumentHighlight {
+                range: span_to_range(span, &rope)?,
+                kind: Some(kind),
+            })
+        };
+        let writes = std::iter::once(flow.declaration)
+            .chain(flow.writes)
+            .filter_map(|x| highlight(x, DocumentHighlightKind::WRITE));
+        let reads = flow
+            .reads
+            .into_iter()
+            .filter_map(|x| highlight(x, DocumentHighlightKind::READ));
+        Some(writes.chain(reads).collect())
+    }
+
+    /// Returns the health of the server as a JSON object, to be attached to performance reports
+    fn server_status(&self) -> Value {
+        let documents_bytes = self
+            .documents
+            .iter()
+            .map(|x| x.value().len_bytes())
+            .sum::<usize>();
+        let states_bytes = self
+            .states
+            .iter()
+            .map(|x| x.value().0.estimated_size())
+            .sum::<usize>();
+        let addons = self
+            .function_files
+            .iter()
+            .map(|x| x.key().display().to_string())
+            .collect::<Vec<_>>();
+        let last_scan = *self.last_scan.read().unwrap();
+
+        serde_json::json!({
+            "uptime_secs": self.started.elapsed().as_secs(),
+            "is_loaded": self.is_loaded.load(Ordering::Relaxed),
+            "indexed_files": self.states.len(),
+            "open_documents": self.documents.len(),
+            "memory_estimate_bytes": {
+                "states": states_bytes,
+                "documents": documents_bytes,
+                "originals": last_scan.map(|x| x.1),
+            },
+            "addons": addons,
+            "last_scan_ms": last_scan.map(|x| x.0.as_millis() as u64),
+        })
+    }
+
+    /// The type inferred for the smallest expression at a position, as
+    /// `{"range": Range, "type": String}`, or `null` when unknown
+    async fn type_at(&self, params: TextDocumentPositionParams) -> Result<Option<Value>> {
+        let uri = clean(params.text_document.uri);
+        let Some(rope) = self.documents.get(&uri).map(|x| x.clone()) else {
+            return Ok(None);
+        };
+        let Some(state) = self.states.get(&uri) else {
+            return Ok(None);
+        };
+        let type_at = position_to_offset(params.position, &rope)
+            .and_then(|offset| definition::type_at(&state.0.state, offset));
+        let Some((span, type_)) = type_at else {
+            return Ok(None);
+        };
+        Ok(Some(serde_json::json!({
+            "range": span_to_range(span, &rope),
+            "type": format!("{type_:?}"),
+        })))
+    }
+
+    /// The global variables assigned by the analyzed scripts and the functions declared by the
+    /// configs, of the mission or addon of a document or of the whole project, as a JSON array of
+    /// `{"name": String, "kind": "variable" | "function", "location": Location | null,
+    /// "type": String | null}`
+    async fn globals(&self, params: GlobalsParams) -> Result<Value> {
+        let root = params
+            .uri
+            .and_then(|x| clean(x).to_file_path().ok())
+            .map(|x| self.addon_root(&x));
+        let is_in_root = |path: &Path| {
+            root.as_ref()
+                .is_none_or(|root| self.addon_root(path) == *root)
+        };
+
+        let mut outputs = HashMap::new();
+        let mut items = vec![];
+        let mut states = self
+            .states
+            .iter()
+            .map(|x| (x.key().clone(), x.0.state.globals(x.1.clone())))
+            .collect::<Vec<_>>();
+        states.sort_by(|a, b| a.0.cmp(&b.0));
+        for (url, globals) in states {
+            if !url.to_file_path().is_ok_and(|x| is_in_root(&x)) {
+                continue;
+            }
+            for (name, (origin, output)) in globals {
+                let location = self.location(Some(&origin.0), &url, origin.1.unwrap_or_default());
+                items.push(serde_json::json!({
+                    "name": name.to_string(),
+                    "kind": "variable",
+                    "location": location,
+                    "type": hover::signature(&output),
+                }));
+                outputs.insert(name, output);
+            }
+        }
+
+        let mut functions = self
+            .function_files
+            .iter()
+            .filter(|x| root.as_ref().is_none_or(|root| x.key() == root))
+            .flat_map(|x| x.value().clone())
+            .collect::<Vec<_>>();
+        functions.sort_by(|a, b| a.0.cmp(&b.0));
+        for (name, script) in functions {
+            let location = Url::from_file_path(hemtt::map(&script))
+                .ok()
+                .map(|url| Location::new(url, Range::default()));
+            let output = outputs.get(&name).cloned().flatten();
+            items.push(serde_json::json!({
+                "name": name.to_string(),
+                "kind": "function",
+                "location": location,
+                "type": hover::signature(&output).unwrap_or_else(|| "Code".to_string()),
+            }));
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn record_analysis(&self, start: Instant) {
+        self.statistics.analyses.fetch_add(1, Ordering::Relaxed);
+        self.statistics
+            .analyses_us
+            .fetch_add(start.elapsed().as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// The counters of the work of the server since it started, for the client to display locally
+    /// (nothing is sent elsewhere), e.g. to tune the debounce
+    async fn statistics(&self) -> Result<Value> {
+        let analyses = self.statistics.analyses.load(Ordering::Relaxed);
+        let analyses_us = self.statistics.analyses_us.load(Ordering::Relaxed);
+        let (functions_hits, functions_misses) = addon::functions_cache_statistics();
+        Ok(serde_json::json!({
+            "uptime_secs": self.started.elapsed().as_secs(),
+            "analyses": analyses,
+            "average_analysis_ms": (analyses > 0).then(|| analyses_us as f64 / analyses as f64 / 1000.0),
+            "cache": {
+                "addons": {
+                    "lookups": self.statistics.addon_lookups.load(Ordering::Relaxed),
+                    "hits": self.statistics.addon_cache_hits.load(Ordering::Relaxed),
+                },
+                "functions": {
+                    "lookups": functions_hits + functions_misses,
+                    "hits": functions_hits,
+                },
+            },
+            "indexed_files": self.states.len(),
+            "open_documents": self.documents.len(),
+            "last_scan_ms": self.last_scan.read().unwrap().map(|x| x.0.as_millis() as u64),
+        }))
+    }
+
+    /// A Markdown report of the diagnostics of the project, as published, grouped by rule
+    fn problems_by_rule(&self) -> String {
+        let folders = self.workspace_folders.read().unwrap().clone();
+        let all = self
+            .diagnostics
+            .iter()
+            .map(|x| (x.key().clone(), x.value().0.clone()))
+            .collect::<Vec<_>>();
+        let problems = all
+            .into_iter()
+            .flat_map(|(url, diagnostics)| {
+                let file = match url.to_file_path() {
+                    Ok(path) => folders
+                        .iter()
+                        .find_map(|x| path.strip_prefix(x).ok())
+                        .unwrap_or(&path)
+                        .display()
+                        .to_string(),
+                    Err(_) => url.to_string(),
+                };
+                self.configure(&url, diagnostics)
+                    .into_iter()
+                    .map(move |diagnostic| {
+                        let code = match diagnostic.code {
+                            Some(NumberOrString::String(code)) => Some(code),
+                            _ => None,
+                        };
+                        (file.clone(), code)
+                    })
+            })
+            .collect::<Vec<_>>();
+        rules::report(&problems, 5)
+    }
+
+    /// Returns all comments with a marker (e.g. `TODO`) of all analyzed files as a JSON array of
+    /// `{"location": Location, "marker": String, "text": String}`
+    fn todos(&self) -> Value {
+        let markers = self.todo_markers.read().unwrap().clone();
+
+        let mut urls = self
+            .states
+            .iter()
+            .map(|x| x.key().clone())
+            .collect::<Vec<_>>();
+        urls.sort();
+
+        let items = urls
+            .into_iter()
+            .filter_map(|url| {
+                let content = self.read(&url.to_file_path().ok()?)?;
+                let todos = todo::collect(&content, &markers).ok()?;
+                let rope = Rope::from_str(&content);
+                Some(
+                    todos
+                        .into_iter()
+                        .filter_map(|todo| {
+                            let range = span_to_range(todo.span, &rope)?;
+                            Some(serde_json::json!({
+                                "location": Location::new(url.clone(), range),
+                                "marker": todo.marker,
+                                "text": todo.text,
+                            }))
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .flatten()
+            .collect();
+        Value::Array(items)
+    }
+
+    /// Updates the project-wide indexes (variable keys, events) with the content of a file
+    fn index(&self, url: Url, content: &str) {
+        let Ok(ast) = sqf::preprocessor::parse(content) else {
+            return;
+        };
+        let terms = tokens::terms(&ast);
+        self.variable_keys
+            .insert(url.clone(), variable_keys::usages(&terms));
+        self.events.insert(url.clone(), events::usages(&terms));
+        self.calls.insert(url.clone(), call_graph::calls(&terms));
+        if let Ok(path) = url.to_file_path() {
+            self.addon_usages
+                .insert(url.clone(), self.addon_usages_of(&path, content, &ast));
+        }
+        self.functions.insert(url, functions::usages(&terms));
+    }
+
+    /// The configs of the other addons the script at `path`, of content `text` and AST `ast`, uses
+    /// by include or `EFUNC`-like macros, and how
+    fn addon_usages_of(
+        &self,
+        path: &Path,
+        text: &str,
+        ast: &VecDeque<Ast>,
+    ) -> Vec<(PathBuf, String)> {
+        let config = self.addon_root(path);
+        if config.as_os_str().is_empty() || config::is_config(path) {
+            return vec![];
+        }
+        let addons = self.addons(path);
+        let scope = self.scope();
+        let includes = include::includes(ast).into_iter().filter_map(|included| {
+            let other = include::resolve(&included.inner, path, &addons, &scope)
+                .map(|x| self.addon_root(&x))
+                .filter(|x| *x != config && !x.as_os_str().is_empty())?;
+            Some((other, format!("`#include \"{}\"`", included.inner)))
+        });
+        let macros = lint::dependencies::macro_components(text)
+            .into_iter()
+            .filter_map(|name| {
+                let directory = config.parent()?;
+                let other = directory.with_file_name(&name).join("config.cpp");
+                (other != config && other.is_file())
+                    .then(|| (other, format!("`EFUNC({name}, ...)`")))
+            });
+        includes.chain(macros).collect()
+    }
+
+    fn lint_context<'a>(
+        &self,
+        mission: Option<&'a MissionSqm>,
+        variable_keys: &'a variable_keys::Index,
+        events: &'a events::Index,
+        global_prefixes: &'a [String],
+        ifdef: &'a ifdef::Settings,
+    ) -> lint::Context<'a> {
+        lint::Context {
+            mission,
+            variable_keys: Some(variable_keys),
+            // lints disabled by the settings are filtered when published
+            variable_keys_mode: variable_keys::Mode::Both,
+            events: Some(events),
+            global_prefixes,
+            macros: None,
+            functions: &[],
+            require_switch_default: self.switch_require_default.load(Ordering::Relaxed),
+            types: None,
+            ifdef,
+        }
+    }
+
+    /// The functions whose script is the file at `path`
+    fn functions_of(&self, path: &Path) -> Vec<String> {
+        let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.function_files
+            .iter()
+            .flat_map(|x| {
+                x.value()
+                    .iter()
+                    .filter(|(_, file)| ***file == *path)
+                    .map(|(name, _)| name.to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// The code style of the project of the file at `path`
+    fn style(&self, path: &Path) -> project::Style {
+        let projects = self.projects.read().unwrap();
+        project::find(&projects, path)
+            .map(|x| x.style.clone())
+            .unwrap_or_default()
+    }
+
+    /// The naming conventions of the project of the file at `path`
+    fn naming(&self, path: &Path) -> project::Naming {
+        let projects = self.projects.read().unwrap();
+        project::find(&projects, path)
+            .map(|x| x.naming.clone())
+            .unwrap_or_default()
+    }
+
+    /// The size, in bytes, above which files are not analyzed
+    fn max_file_size(&self) -> Option<usize> {
+        match self.max_file_size_kb.load(Ordering::Relaxed) {
+            0 => None,
+            kb => Some(kb as usize * 1024),
+        }
+    }
+
+    fn is_excluded(&self, path: &Path) -> bool {
+        let projects = self.projects.read().unwrap();
+        project::find(&projects, path)
+            .map(|x| x.is_excluded(path))
+            .unwrap_or(false)
+    }
+
+    /// Loads the `.sqf-analyzer.toml` of each workspace folder
+    async fn load_projects(&self) {
+        let folders = self.workspace_folders.read().unwrap().clone();
+        let mut projects = vec![];
+        for folder in folders {
+            match Project::load(&folder) {
+                Some(Ok(project)) => {
+                    self.client
+                        .log_message(
+                            MessageType::INFO,
+                            format!(
+                                "Loaded \"{}\" (Arma {})",
+                                folder.join(project::FILE_NAME).display(),
+                                project.arma_version.as_deref().unwrap_or("unspecified")
+                            ),
+                        )
+                        .await;
+                    projects.push(project)
+                }
+                Some(Err(error)) => {
+                    self.client
+                        .show_message(
+                            MessageType::ERROR,
+                            format!(
+                                "Invalid \"{}\": {error}",
+                                folder.join(project::FILE_NAME).display()
+                            ),
+                        )
+                        .await
+                }
+                None => {}
+            }
+        }
+        *self.projects.write().unwrap() = projects;
+        self.load_baselines().await;
+    }
+
+    /// Loads the baselines of the workspace folders
+    async fn load_baselines(&self) {
+        let folders = self.workspace_folders.read().unwrap().clone();
+        let mut baselines = vec![];
+        for folder in folders {
+            match suppress::Baseline::load(&folder) {
+                Some(Ok(baseline)) => baselines.push((folder, baseline)),
+                Some(Err(error)) => {
+                    self.client
+                        .show_message(
+                            MessageType::ERROR,
+                            format!(
+                                "Invalid \"{}\": {error}",
+                                folder.join(suppress::BASELINE_FILE_NAME).display()
+                            ),
+                        )
+                        .await
+                }
+                None => {}
+            }
+        }
+        *self.baselines.write().unwrap() = baselines;
+    }
+
+    /// The generation of a new analysis. Diagnostics of a file are only published if they are from
+    /// a generation at least as recent as the one of its last published diagnostics.
+    fn next_generation(&self) -> u64 {
+        self.generation.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Moves the state of the file `old` to `new` after it was renamed, unless `new` already has
+    /// it (e.g. it was opened under its new name first), and clears the diagnostics of `old`.
+    /// Its diagnostics are republished under `new`, since its content is unchanged.
+    async fn move_file(&self, old: &Url, new: &Url) {
+        self.cancel_debounce(old);
+        move_entry(&self.states, old, new);
+        move_entry(&self.documents, old, new);
+        move_entry(&self.versions, old, new);
+        move_entry(&self.preloaded, old, new);
+        move_entry(&self.variable_keys, old, new);
+        move_entry(&self.events, old, new);
+        move_entry(&self.functions, old, new);
+        move_entry(&self.calls, old, new);
+        move_entry(&self.addon_usages, old, new);
+        move_entry(&self.handler_diagnostics, old, new);
+        move_entry(&self.suppressions, old, new);
+        move_entry(&self.diagnostics, old, new);
+        move_entry(&self.generations, old, new);
+        if self.script_headers.remove(old).is_some() {
+            self.script_headers.insert(new.clone());
+        }
+        self.client
+            .publish_diagnostics(old.clone(), vec![], None)
+            .await;
+        let published = self.diagnostics.get(new).map(|x| x.clone());
+        if let Some((diagnostics, generation)) = published {
+            self.publish(new.clone(), diagnostics, generation).await;
+        }
+    }
+
+    /// Aborts the analysis of the document `uri` waiting for the typing to pause, if any
+    fn cancel_debounce(&self, uri: &Url) {
+        if let Some((_, wait)) = self.debounces.remove(uri) {
+            wait.abort();
+        }
+    }
+
+    /// Whether a newer version than `version` of the document was received
+    fn is_outdated(&self, uri: &Url, version: i32) -> bool {
+        self.versions
+            .get(uri)
+            .map(|x| *x > version)
+            .unwrap_or(false)
+    }
+
+    /// Publishes the diagnostics of a file computed by the analysis `generation`, applying the
+    /// settings to them. Diagnostics of a stale generation are dropped.
+    /// The diagnostics are kept so that they can be republished when the settings change.
+    async fn publish(&self, url: Url, diagnostics: Vec<Diagnostic>, generation: u64) {
+        {
+            let mut latest = self.generations.entry(url.clone()).or_default();
+            if *latest > generation {
+                return;
+            }
+            *latest = generation;
+        }
+        let configured = self.configure(&url, diagnostics.clone());
+        let configured = match self.max_diagnostics.load(Ordering::Relaxed) {
+            0 => configured,
+            max => truncate(configured, max as usize),
+        };
+        self.diagnostics
+            .insert(url.clone(), (diagnostics, generation));
+        // the version of the file the diagnostics refer to, if it is open
+        let version = self.versions.get(&url).map(|x| *x);
+        self.client
+            .publish_diagnostics(url, configured, version)
+            .await
+    }
+
+    /// Republishes all known diagnostics with the current settings
+    async fn republish(&self) {
+        let all = self
+            .diagnostics
+            .iter()
+            .map(|x| (x.key().clone(), x.value().clone()))
+            .collect::<Vec<_>>();
+        for (url, (diagnostics, generation)) in all {
+            self.publish(url, diagnostics, generation).await;
+        }
+    }
+
+    /// Analyzes the project and the open documents again, e.g. after the addon prefixes changed
+    async fn reanalyze(&self) {
+        if !self.is_loaded.swap(false, Ordering::Relaxed) {
+            // not loaded yet, nothing to analyze again
+            return;
+        }
+        self.client
+            .log_message(
+                MessageType::INFO,
+                "settings changed, analyzing the project again",
+            )
+            .await;
+        let documents = self
+            .documents
+            .iter()
+            .map(|x| (x.key().clone(), x.value().to_string()))
+            .collect::<Vec<_>>();
+        for (uri, text) in documents {
+            let version = self.versions.get(&uri).map(|x| *x).unwrap_or_default();
+            // the syntax does not depend on the settings
+            self.on_change(TextDocumentItem { uri, text, version }, true)
+                .await;
+        }
+    }
+
+    /// Whether diagnostics of the rule `code` are enabled by the settings that toggle rules
+    fn is_enabled(&self, code: &str) -> bool {
+        let mode = *self.unmatched_variable_keys.read().unwrap();
+        match code {
+            "undefined-variable" => self.undefined_variables_are_error.load(Ordering::Relaxed),
+            "private-assigned-to-mission" => self
+                .private_variables_in_mission_are_error
+                .load(Ordering::Relaxed),
+            "unused-variable" => self.error_on_unused.load(Ordering::Relaxed),
+            "variable-key-never-set" => {
+                matches!(mode, variable_keys::Mode::Read | variable_keys::Mode::Both)
+            }
+            "variable-key-never-read" => {
+                matches!(
+                    mode,
+                    variable_keys::Mode::Written | variable_keys::Mode::Both
+                )
+            }
+            "event-never-raised" | "event-never-handled" => {
+                self.unmatched_events.load(Ordering::Relaxed)
+            }
+            // opt-in: enabled by setting its level
+            "this-select" | "unused-required-addon" | "unused-macro" => false,
+            _ => true,
+        }
+    }
+
+    /// Whether the level of the rule `code` is set for the file at `path`, in the editor settings or
+    /// in its project
+    fn has_level(&self, path: &Path, code: &str) -> bool {
+        let projects = self.projects.read().unwrap();
+        self.command_rule_levels.read().unwrap().contains_key(code)
+            || self.rule_levels.read().unwrap().contains_key(code)
+            || project::find(&projects, path).is_some_and(|x| x.rules.contains_key(code))
+    }
+
+    /// The lints of the macros defined in `text`, the content of the file at `path`, that no file of
+    /// its addon uses, nor the files they include. Only computed when the rule `unused-macro`, opt-in,
+    /// is enabled.
+    fn unused_macros(&self, text: &str, path: &Path) -> Vec<lint::Lint> {
+        if !self.has_level(path, "unused-macro") {
+            return vec![];
+        }
+        let config = self.addon_root(path);
+        let Some(directory) = config.parent().filter(|_| !config.as_os_str().is_empty()) else {
+            return vec![];
+        };
+        let addons = self.addons(path);
+        let scope = self.scope();
+        let mut files = std::collections::BTreeMap::new();
+        let known = self
+            .states
+            .iter()
+            .map(|x| x.key().clone())
+            .chain(self.documents.iter().map(|x| x.key().clone()))
+            .filter_map(|url| url.to_file_path().ok())
+            .chain(["config.cpp", "description.ext"].map(|x| directory.join(x)))
+            .filter(|x| x.starts_with(directory) && x != path)
+            .collect::<Vec<_>>();
+        for file in known {
+            let Some(content) = self.read(&file) else {
+                continue;
+            };
+            files.extend(macros::included(&content, &file, &addons, &scope));
+            files.insert(file, content);
+        }
+        files.extend(macros::included(text, path, &addons, &scope));
+        files.remove(path);
+        macros::unused(text, &files.into_iter().collect::<Vec<_>>())
+    }
+
+    /// Applies the settings to the diagnostics of a file: the rules they enable, and the level of each
+    /// rule, set by `sqf-analyzer.setRule`, in the editor settings or else in the project (which also
+    /// enables the rule). Drops the diagnostics suppressed by a comment of the file or by the
+    /// baseline of its workspace folder.
+    fn configure(&self, url: &Url, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        let Ok(path) = url.to_file_path() else {
+            return diagnostics;
+        };
+        let projects = self.projects.read().unwrap();
+        let project = project::find(&projects, &path);
+        if project.map(|x| x.is_excluded(&path)).unwrap_or(false) {
+            return vec![];
+        }
+        let levels = self.rule_levels.read().unwrap();
+        let command_levels = self.command_rule_levels.read().unwrap();
+        let warnings_as_errors = self.warnings_as_errors.read().unwrap();
+        let overrides = self.overrides.read().unwrap();
+        let relative = {
+            let folders = self.workspace_folders.read().unwrap();
+            folders
+                .iter()
+                .find_map(|x| path.strip_prefix(x).ok())
+                .unwrap_or(&path)
+                .to_path_buf()
+        };
+        let comments = self.suppressions(url, &path);
+        let baselines = self.baselines.read().unwrap();
+        let baseline = self.workspace_relative(&path).and_then(|(folder, file)| {
+            let baseline = baselines.iter().find(|x| x.0 == folder)?;
+            Some((&baseline.1, file))
+        });
+        diagnostics
+            .into_iter()
+            .filter_map(|mut diagnostic| {
+                let code = match &diagnostic.code {
+                    Some(NumberOrString::String(code)) => Some(code.clone()),
+                    _ => None,
+                };
+                if let Some(code) = &code {
+                    if comments.is_suppressed(code, diagnostic.range.start.line as usize) {
+                        return None;
+                    }
+                    let is_accepted = baseline.as_ref().is_some_and(|(baseline, file)| {
+                        baseline.contains(&suppress::Entry {
+                            file: file.clone(),
+                            rule: code.clone(),
+                            message: diagnostic.message.clone(),
+                        })
+                    });
+                    if is_accepted {
+                        return None;
+                    }
+                    let level = command_levels
+                        .get(code)
+                        .or_else(|| levels.get(code))
+                        .or_else(|| project.and_then(|x| x.rules.get(code)));
+                    match level {
+                        Some(level) => diagnostic.severity = Some(level.severity()?),
+                        None if !self.is_enabled(code) => return None,
+                        None => {}
+                    }
+                }
+                diagnostic.severity = diagnostic
+                    .severity
+                    .map(|x| warnings_as_errors.apply(code.as_deref(), x));
+                if let Some(severity) = diagnostic.severity {
+                    diagnostic.severity = Some(overrides.apply(&relative, severity)?);
+                }
+                Some(diagnostic)
+            })
+            .collect()
+    }
+
+    /// The suppression comments of the file at `url`, parsed once per version of open documents
+    /// (whose text may be newer than the file on disk)
+    fn suppressions(&self, url: &Url, path: &Path) -> suppress::Comments {
+        let parse = |text: String| {
+            if text.contains(suppress::FILE) {
+                suppress::Comments::parse(&text)
+            } else {
+                Default::default()
+            }
+        };
+        let Some(version) = self.versions.get(url).map(|x| *x) else {
+            return self.read(path).map(parse).unwrap_or_default();
+        };
+        if let Some(cached) = self.suppressions.get(url).filter(|x| x.0 == version) {
+            return cached.1.clone();
+        }
+        let Some(text) = self.documents.get(url).map(|x| x.to_string()) else {
+            return Default::default();
+        };
+        let comments = parse(text);
+        self.suppressions
+            .insert(url.clone(), (version, comments.clone()));
+        comments
+    }
+
+    /// Returns the names of the functions referred to over the project
+    fn function_index(&self) -> functions::Index {
+        let mut index = functions::Index::default();
+        for usages in self.functions.iter() {
+            functions::extend(&mut index, usages.value().iter());
+        }
+        index
+    }
+
+    /// Publishes the functions of each config of the project that are never referred to
+    async fn publish_unused_functions(&self, generation: u64) {
+        let index = self.function_index();
+        let graph = self.call_graph();
+        let configs = self
+            .function_files
+            .iter()
+            .map(|x| x.key().clone())
+            .collect::<Vec<_>>();
+        for path in configs {
+            if let Ok(url) = Url::from_file_path(&path) {
+                self.publish_config(url, &index, &graph, generation).await;
+            }
+        }
+    }
+
+    /// Publishes the diagnostics of a config: its unused functions, the attributes of its functions and
+    /// the errors of its UI event handlers
+    async fn publish_config(
+        &self,
+        url: Url,
+        index: &functions::Index,
+        graph: &call_graph::Graph,
+        generation: u64,
+    ) {
+        let Some(rope) = self.documents.get(&url).map(|x| x.clone()).or_else(|| {
+            let content = self.read(&url.to_file_path().ok()?)?;
+            Some(Rope::from_str(&content))
+        }) else {
+            return;
+        };
+        let config = config::parse(&rope.to_string());
+        let declarations = config::functions::declarations(&config);
+        let diagnostics = functions::unused(&declarations, index)
+            .into_iter()
+            .chain(config::functions::lint(&config))
+            .chain(self.dependency_lints(&url, graph))
+            .filter_map(|lint| lint_to_diagnostic(lint, &rope))
+            .chain(
+                self.handler_diagnostics
+                    .get(&url)
+                    .map(|x| x.clone())
+                    .unwrap_or_default(),
+            )
+            .collect();
+        self.publish(url, diagnostics, generation).await;
+    }
+
+    /// Analyzes the UI event handlers of a config document (e.g. `onLoad = "..."`), within its
+    /// mission or addon
+    async fn analyze_handlers(&self, uri: &Url, text: &str, file_path: &Path, generation: u64) {
+        let code = dialog::code(text);
+        if code.trim().is_empty() {
+            self.handler_diagnostics.remove(uri);
+            self.states.remove(uri);
+        } else {
+            let base_path = self.addon_root(file_path);
+            let mission = self
+                .states
+                .iter()
+                .filter(|x| x.key() != uri)
+                .flat_map(|x| x.0.state.globals(x.1.clone()))
+                .chain(
+                    self.mission_sqm(&base_path)
+                        .iter()
+                        .flat_map(|x| x.globals()),
+                )
+                .collect();
+            let configuration = sqf::analyzer::Configuration {
+                addons: self.addons(file_path),
+                file_path: file_path.to_path_buf().into(),
+                base_path,
+            };
+            let budget = match self.analysis_timeout_ms.load(Ordering::Relaxed) {
+                0 => None,
+                ms => Some(Duration::from_millis(ms)),
+            };
+            let analysis_start = Instant::now();
+            let outcome = compute_within(code, configuration, mission, self.ifdef(), budget).await;
+            self.record_analysis(analysis_start);
+            let (state, errors) = match outcome {
+                Outcome::Complete(result) => match *result {
+                    Ok((state, errors)) => (Some(state), errors),
+                    Err(e) => (None, vec![e]),
+                },
+                Outcome::Truncated(errors) => (None, errors),
+            };
+            let Some(rope) = self.documents.get(uri).map(|x| x.clone()) else {
+                return;
+            };
+            let diagnostics = errors
+                .into_iter()
+                // errors within includes are not positioned in the config
+                .filter(|x| x.1.origin.is_none())
+                .filter_map(|error| to_diagnostic(error, &rope))
+                .collect();
+            self.handler_diagnostics.insert(uri.clone(), diagnostics);
+            match state {
+                Some(state) => {
+                    self.states
+                        .insert(uri.clone(), (Analysis::new(state), None));
+                }
+                None => {
+                    if let Some(mut e) = self.states.get_mut(uri) {
+                        e.value_mut().0.invalidate();
+                    }
+                }
+            }
+        }
+        self.publish_config(
+            uri.clone(),
+            &self.function_index(),
+            &self.call_graph(),
+            generation,
+        )
+        .await;
+    }
+
+    /// The references to the function at `position` over the project, including the ones in strings
+    fn function_references(
+        &self,
+        uri: &Url,
+        position: Position,
+        include_declaration: bool,
+    ) -> Option<Vec<Location>> {
+        let (config_path, name) = self.function_at(uri, position)?;
+        let text = |url: &Url| -> Option<Rope> {
+            self.documents.get(url).map(|x| x.clone()).or_else(|| {
+                let content = self.read(&url.to_file_path().ok()?)?;
+                Some(Rope::from_str(&content))
+            })
+        };
+
+        let mut locations = vec![];
+        if include_declaration {
+            let config_url = Url::from_file_path(&config_path).ok()?;
+            let rope = text(&config_url)?;
+            let declaration = config::functions::declarations(&config::parse(&rope.to_string()))
+                .into_iter()
+                .find(|x| x.name.eq_ignore_ascii_case(&name));
+            if let Some(range) = declaration.and_then(|x| span_to_range(x.class.span, &rope)) {
+                locations.push(Location::new(config_url, range));
+            }
+        }
+
+        let usages = self
+            .functions
+            .iter()
+            .filter_map(|x| {
+                let spans = x
+                    .value()
+                    .iter()
+                    .filter(|usage| usage.inner.eq_ignore_ascii_case(&name))
+                    .map(|usage| usage.span)
+                    .collect::<Vec<_>>();
+                (!spans.is_empty()).then(|| (x.key().clone(), spans))
+            })
+            .collect::<Vec<_>>();
+        for (url, spans) in usages {
+            let Some(rope) = text(&url) else {
+                continue;
+            };
+            locations.extend(
+                spans.into_iter().filter_map(|span| {
+                    Some(Location::new(url.clone(), span_to_range(span, &rope)?))
+                }),
+            );
+        }
+        Some(locations)
+    }
+
+    /// Returns the number of raises and handlers of each event over the project
+    fn event_index(&self) -> events::Index {
+        let mut index = events::Index::default();
+        for usages in self.events.iter() {
+            events::extend(&mut index, usages.value().iter());
+        }
+        index
+    }
+
+    /// Completion of the arguments of `remoteExec`: the functions of the project and the commands
+    /// whitelisted in `CfgRemoteExec`, then the targets and the JIP parameter
+    fn remote_exec_completion(&self, uri: &Url, position: Position) -> Option<CompletionResponse> {
+        let before = {
+            let rope = self.documents.get(uri)?;
+            let line = rope.get_line(position.line as usize)?;
+            let cursor =
+                position_to_offset(position, &rope)? - rope.line_to_char(position.line as usize);
+            line.get_slice(..cursor)?.to_string()
+        };
+        let snippets = match remote_exec::argument_at(&before)? {
+            remote_exec::Argument::Function => return Some(self.remote_exec_functions()),
+            remote_exec::Argument::Targets => remote_exec::TARGETS,
+            remote_exec::Argument::Jip => remote_exec::JIP,
+        };
+        let items = snippets
+            .iter()
+            .enumerate()
+            .map(|(i, (label, snippet, documentation))| CompletionItem {
+                label: label.to_string(),
+                kind: Some(CompletionItemKind::SNIPPET),
+                documentation: Some(Documentation::String(documentation.to_string())),
+                insert_text: Some(snippet.to_string()),
+                insert_text_format: Some(InsertTextFormat::SNIPPET),
+                // in the documented order
+                sort_text: Some(format!("{i:02}")),
+                ..Default::default()
+            })
+            .collect();
+        Some(CompletionResponse::Array(items))
+    }
+
+    /// Completion of the attributes of a function class of `CfgFunctions` and of their values
+    fn function_attribute_completion(
+        &self,
+        uri: &Url,
+        position: Position,
+    ) -> Option<CompletionResponse> {
+        if !config::is_config(&uri.to_file_path().ok()?) {
+            return None;
+        }
+        let (before, offset, text) = {
+            let rope = self.documents.get(uri)?;
+            let line = rope.get_line(position.line as usize)?;
+            let offset = position_to_offset(position, &rope)?;
+            let cursor = offset - rope.line_to_char(position.line as usize);
+            (
+                line.get_slice(..cursor)?.to_string(),
+                offset,
+                rope.to_string(),
+            )
+        };
+        let config = config::parse(&text);
+        config::functions::declarations(&config)
+            .into_iter()
+            .find(|x| x.span.0 < offset && offset < x.span.1)?;
+
+        // the value of an attribute, e.g. `ext = "`
+        if let Some((name, value)) = before.split_once('=') {
+            let (_, allowed, _) = config::functions::attribute(name.trim())?;
+            let value = value.trim_start();
+            let items = allowed
+                .iter()
+                .map(|x| CompletionItem {
+                    label: x.to_string(),
+                    kind: Some(CompletionItemKind::VALUE),
+                    // the quote already typed is not repeated
+                    insert_text: Some(
+                        if value.starts_with('"') {
+                            x.trim_start_matches('"')
+                        } else {
+                            x
+                        }
+                        .to_string(),
+                    ),
+                    ..Default::default()
+                })
+                .collect();
+            return Some(CompletionResponse::Array(items));
+        }
+
+        // the name of an attribute
+        if !before
+            .trim()
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_')
+        {
+            return None;
+        }
+        let items = config::functions::ATTRIBUTES
+            .iter()
+            .map(|(name, _, documentation)| CompletionItem {
+                label: name.to_string(),
+                kind: Some(CompletionItemKind::PROPERTY),
+                documentation: Some(Documentation::MarkupContent(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: documentation.to_string(),
+                })),
+                ..Default::default()
+            })
+            .collect();
+        Some(CompletionResponse::Array(items))
+    }
+
+    /// The values denoting the expected types of a parameter, e.g. `objNull`, within the array of
+    /// types of a `params` entry: `params [["_unit", objNull, [`
+    fn params_type_completion(&self, uri: &Url, position: Position) -> Option<CompletionResponse> {
+        let (text, offset) = {
+            let rope = self.documents.get(uri)?;
+            (rope.to_string(), position_to_offset(position, &rope)?)
+        };
+        let ast = sqf::preprocessor::parse(&text).ok()?;
+        let terms = tokens::terms(&ast);
+        let before = terms.iter().take_while(|x| x.span.1 <= offset).count();
+        if !params::is_in_types(&terms[..before]) {
+            return None;
+        }
+        let items = params::TYPE_VALUES
+            .iter()
+            .map(|(value, type_)| CompletionItem {
+                label: value.to_string(),
+                kind: Some(CompletionItemKind::VALUE),
+                detail: Some(format!("accepts {type_} arguments")),
+                ..Default::default()
+            })
+            .collect();
+        Some(CompletionResponse::Array(items))
+    }
+
+    /// The functions of the project and the commands whitelisted in the `CfgRemoteExec` of its configs
+    fn remote_exec_functions(&self) -> CompletionResponse {
+        let configs = self
+            .function_files
+            .iter()
+            .map(|x| {
+                (
+                    x.key().clone(),
+                    x.value().keys().cloned().collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut seen = std::collections::HashSet::new();
+        let mut items = vec![];
+        for (path, functions) in configs {
+            for function in functions {
+                if seen.insert(function.to_string().to_lowercase()) {
+                    items.push(CompletionItem {
+                        label: function.to_string(),
+                        kind: Some(CompletionItemKind::FUNCTION),
+                        detail: Some("function".to_string()),
+                        ..Default::default()
+                    });
+                }
+            }
+            let text = self.read(&path).unwrap_or_default();
+            for command in remote_exec::whitelisted_commands(&config::parse(&text)) {
+                if seen.insert(command.to_lowercase()) {
+                    items.push(CompletionItem {
+                        label: command,
+                        kind: Some(CompletionItemKind::KEYWORD),
+                        detail: Some("command (CfgRemoteExec)".to_string()),
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+        CompletionResponse::Array(items)
+    }
+
+    /// Completion of the events seen over the project when the cursor is inside the name of an event
+    fn event_completion(&self, uri: &Url, position: Position) -> Option<CompletionResponse> {
+        let (before, after) = {
+            let rope = self.documents.get(uri)?;
+            let line = rope.get_line(position.line as usize)?;
+            let cursor =
+                position_to_offset(position, &rope)? - rope.line_to_char(position.line as usize);
+            (
+                line.get_slice(..cursor)?.to_string(),
+                line.get_slice(cursor..)?.to_string(),
+            )
+        };
+        let kind = events::event_kind_at(&before, &after)?;
+
+        let mut seen = std::collections::HashSet::new();
+        let mut items = vec![];
+        for usages in self.events.iter() {
+            for usage in usages.value() {
+                if usage.kind == kind && seen.insert(usage.name.to_lowercase()) {
+                    items.push(CompletionItem {
+                        label: usage.name.clone(),
+                        kind: Some(CompletionItemKind::EVENT),
+                        ..Default::default()
+                    })
+                }
+            }
+        }
+        Some(CompletionResponse::Array(items))
+    }
+
+    /// Completion of the macros defined in the file, its includes and the settings, within `#if`,
+    /// `#ifdef` and `#ifndef`
+    fn macro_completion(&self, uri: &Url, position: Position) -> Option<CompletionResponse> {
+        let (line, text) = {
+            let rope = self.documents.get(uri)?;
+            let line = rope.get_line(position.line as usize)?;
+            let cursor =
+                position_to_offset(position, &rope)? - rope.line_to_char(position.line as usize);
+            (line.get_slice(..cursor)?.to_string(), rope.to_string())
+        };
+        let prefix = macros::condition_prefix(&line)?;
+
+        let path = uri.to_file_path().ok()?;
+        let defined = macros::defined(&text, &path, &self.addons(&path), &self.scope());
+        let mut seen = std::collections::HashSet::new();
+        let items = self
+            .ifdef()
+            .defines
+            .into_iter()
+            .map(|name| (name, Some("defined in the settings".to_string())))
+            .chain(defined.into_iter().map(|x| {
+                let detail = x
+                    .path
+                    .and_then(|x| x.file_name().map(|x| x.to_string_lossy().into_owned()));
+                (x.name, detail)
+            }))
+            .filter(|(name, _)| name.starts_with(prefix) && seen.insert(name.clone()))
+            .map(|(label, detail)| CompletionItem {
+                label,
+                kind: Some(CompletionItemKind::CONSTANT),
+                detail,
+                ..Default::default()
+            })
+            .collect();
+        Some(CompletionResponse::Array(items))
+    }
+
+    /// Completion of the keys seen over the project when the cursor is inside the key argument of
+    /// `setVariable`/`getVariable`
+    fn variable_key_completion(&self, uri: &Url, position: Position) -> Option<CompletionResponse> {
+        let line = {
+            let rope = self.documents.get(uri)?;
+            let line = rope.get_line(position.line as usize)?;
+            let cursor =
+                position_to_offset(position, &rope)? - rope.line_to_char(position.line as usize);
+            line.get_slice(..cursor)?.to_string()
+        };
+        let namespace = variable_keys::key_namespace_at(&line)?;
+
+        let mut seen = std::collections::HashSet::new();
+        let mut items = vec![];
+        for usages in self.variable_keys.iter() {
+            for usage in usages.value() {
+                if usage.namespace == namespace && seen.insert(usage.key.to_lowercase()) {
+                    items.push(CompletionItem {
+                        label: usage.key.clone(),
+                        kind: Some(CompletionItemKind::VALUE),
+                        detail: Some(format!("key of {namespace}")),
+                        ..Default::default()
+                    })
+                }
+            }
+        }
+        Some(CompletionResponse::Array(items))
+    }
+
+    /// Returns the number of reads and writes of each key over the project
+    fn variable_key_index(&self) -> variable_keys::Index {
+        let mut index = variable_keys::Index::default();
+        for usages in self.variable_keys.iter() {
+            variable_keys::extend(&mut index, usages.value().iter());
+        }
+        index
+    }
+
+    /// Returns the (cached) `mission.sqm` of the mission whose `description.ext` is `config_path`
+    fn mission_sqm(&self, config_path: &Path) -> Option<Arc<MissionSqm>> {
+        self.missions
+            .entry(config_path.to_path_buf())
+            .or_insert_with(|| mission::load(config_path).map(Arc::new))
+            .clone()
+    }
+
+    /// The functions declared in the configs of the project whose name contains `query`
+    fn workspace_symbols(&self, query: &str) -> Vec<SymbolInformation> {
+        let query = query.to_lowercase();
+        let configs = self
+            .function_files
+            .iter()
+            .map(|x| x.key().clone())
+            .collect::<Vec<_>>();
+        configs
+            .into_iter()
+            .filter_map(|config_path| {
+                let url = Url::from_file_path(&config_path).ok()?;
+                let rope = self
+                    .documents
+                    .get(&url)
+                    .map(|x| x.clone())
+                    .or_else(|| Some(Rope::from_str(&self.read(&config_path)?)))?;
+                Some((url, rope))
+            })
+            .flat_map(|(url, rope)| {
+                config::functions::declarations(&config::parse(&rope.to_string()))
+                    .into_iter()
+                    .filter(|x| x.name.to_lowercase().contains(&query))
+                    .filter_map(|declaration| {
+                        #[allow(deprecated)]
+                        Some(SymbolInformation {
+                            name: declaration.name,
+                            kind: SymbolKind::FUNCTION,
+                            tags: None,
+                            deprecated: None,
+                            location: Location::new(
+                                url.clone(),
+                                span_to_range(declaration.class.span, &rope)?,
+                            ),
+                            container_name: Some(format!(
+                                "{} >> {}",
+                                declaration.tag, declaration.category.inner
+                            )),
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
 
-            // todo: use futures join to push them concurrently
-            for (url, diagnostics) in diagnostics {
-                self.client
-                    .publish_diagnostics(url, diagnostics, Some(version))
-                    .await;
-            }
+    fn document_symbols(&self, uri: Url) -> Option<DocumentSymbolResponse> {
+        if !config::is_config(&uri.to_file_path().ok()?) {
+            return None;
         }
-        self.is_loaded.store(true, Ordering::Relaxed);
+        let rope = self.documents.get(&uri)?;
+        let config = config::parse(&rope.to_string());
+
+        let symbols = config::symbols::symbols(&config)
+            .into_iter()
+            .filter_map(|symbol| to_document_symbol(symbol, &rope))
+            .collect();
+        Some(DocumentSymbolResponse::Nested(symbols))
     }
 
-    async fn on_change(&self, params: TextDocumentItem) {
-        let uri = clean(params.uri);
+    fn hover(&self, uri: Url, position: Position) -> Option<Hover> {
+        if config::is_config(&uri.to_file_path().ok()?) && !self.script_headers.contains(&uri) {
+            return self.config_hover(&uri, position);
+        }
 
-        self.load_project(&uri, params.version).await;
+        // the document is parsed once for the hovers of its terms
+        let rope = self.documents.get(&uri).map(|x| x.clone())?;
+        let text = rope.to_string();
+        if let Ok(ast) = sqf::preprocessor::parse(&text) {
+            let terms = tokens::terms(&ast);
+            let document = (&rope, terms.as_slice());
+            if let Some(hover) = self.params_hover(document, position) {
+                return Some(hover);
+            }
+            if let Some(hover) = self.enum_hover(document, position) {
+                return Some(hover);
+            }
+        }
 
-        self.client
-            .log_message(MessageType::INFO, format!("{}", &uri))
-            .await;
+        if let Some(hover) = self.builtin_hover(&uri, position) {
+            return Some(hover);
+        }
 
-        let mission = self
-            .states
-            .iter()
-            .filter(|x| x.key() != &uri)
-            .flat_map(|x| x.0 .0.globals(x.1.clone()))
-            .collect();
+        if let Some((_, definitions)) = self.macro_at(&uri, position) {
+            let mut value = definitions
+                .iter()
+                .map(|x| x.markdown())
+                .collect::<Vec<_>>()
+                .join("\n\n---\n\n");
+            if let Some((invocation, expanded)) = self.macro_expansion(&uri, position) {
+                value.push_str(&format!(
+                    "\n\n---\n\n`{invocation}` expands to\n```sqf\n{expanded}\n```"
+                ));
+            }
+            return Some(Hover {
+                contents: HoverContents::Markup(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value,
+                }),
+                range: None,
+            });
+        }
 
-        self.documents
-            .insert(uri.clone(), ropey::Rope::from_str(&params.text));
+        let analysis = &self.states.get(&uri)?.0;
 
-        let file_path = uri.to_file_path().expect("utf-8 path");
-        let base_path = addon::identify(file_path.clone()).unwrap_or_default().0;
+        let offset = position_to_offset(position, &rope)?;
 
-        let configuration = sqf::analyzer::Configuration {
-            file_path: file_path.into(),
-            base_path,
-            addons: self.addon_paths.read().unwrap().clone(),
+        if let Some(explanation) = hover::hover(&analysis.state, offset) {
+            let translation = word_at(offset, &rope)
+                .and_then(|(start, end)| rope.get_slice(start..end))
+                .and_then(|name| {
+                    locale::explanation(&self.explanations.read().unwrap(), &name.to_string())
+                });
+            return Some(Hover {
+                contents: HoverContents::Markup(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: translation.unwrap_or_else(|| explanation.to_string()),
+                }),
+                range: None,
+            });
+        }
+
+        // otherwise, the variable or command under the cursor
+        let (start, end) = word_at(offset, &rope)?;
+        let symbol = analysis.symbol(
+            &rope.get_slice(start..end)?.to_string(),
+            &self.explanations.read().unwrap(),
+        )?;
+        let value = match &symbol.documentation {
+            Some(Documentation::MarkupContent(content)) => content.value.clone(),
+            Some(Documentation::String(value)) => value.clone(),
+            None => format!("`{}`: `{}`", symbol.label, symbol.detail.as_deref()?),
         };
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: Some(Range::new(
+                offset_to_position(start, &rope)?,
+                offset_to_position(end, &rope)?,
+            )),
+        })
+    }
 
-        let (state_semantic, errors) = match compute(&params.text, configuration, mission) {
-            Ok((state, semantic, completion, errors)) => {
-                (Some((state, semantic, completion)), errors)
-            }
-            Err(e) => (None, vec![e]),
+    /// The diagnostics of the macros redefined in a document, related to the definitions they replace
+    fn redefinition_diagnostics(
+        &self,
+        uri: &Url,
+        text: &str,
+        path: &Path,
+    ) -> Vec<(Url, Diagnostic)> {
+        let Some(rope) = self.documents.get(uri).map(|x| x.clone()) else {
+            return vec![];
         };
+        macros::redefinitions(text, path, &self.addons(path), &self.scope())
+            .into_iter()
+            .filter_map(|(new, previous)| {
+                let mut diagnostic = lint_to_diagnostic(macros::redefinition_lint(&new), &rope)?;
+                let previous_path = previous.path.as_deref().map(Path::to_path_buf);
+                diagnostic.related_information = self
+                    .location(previous_path.as_deref(), uri, previous.span)
+                    .map(|location| {
+                        vec![DiagnosticRelatedInformation {
+                            location,
+                            message: "previous definition".to_string(),
+                        }]
+                    });
+                Some((uri.clone(), diagnostic))
+            })
+            .collect()
+    }
 
-        let error_on_undefined = self.undefined_variables_are_error.load(Ordering::Relaxed);
-        let error_on_unused = self.error_on_unused.load(Ordering::Relaxed);
-        let private_variables_in_mission_are_error = self
-            .private_variables_in_mission_are_error
-            .load(Ordering::Relaxed);
+    /// The path of the file at `path` as seen by the game
+    fn game_path(&self, path: &Path) -> String {
+        let folders = self.workspace_folders.read().unwrap().clone();
+        builtins::game_path(path, &self.addons(path), &folders)
+    }
 
-        let diagnostics = errors
+    /// Hover of an entry of `params`, explaining its parts
+    fn params_hover(
+        &self,
+        (rope, terms): (&Rope, &[Spanned<&str>]),
+        position: Position,
+    ) -> Option<Hover> {
+        let offset = position_to_offset(position, rope)?;
+        let entry = params::declarations(terms)
             .into_iter()
-            .filter(|error| {
-                error_on_undefined || (!matches!(error.type_, ErrorType::UndefinedVariable(_)))
-            })
-            .filter(|error| {
-                private_variables_in_mission_are_error
-                    || (error.type_ != ErrorType::PrivateAssignedToMission)
+            .flat_map(|x| x.entries)
+            .find(|x| {
+                terms[x.terms.start].span.0 <= offset && offset < terms[x.terms.end - 1].span.1
+            })?;
+        let source = |range: &std::ops::Range<usize>| {
+            let span = (terms[range.start].span.0, terms[range.end - 1].span.1);
+            rope.get_slice(span.0..span.1).map(|x| x.to_string())
+        };
+        let types = entry.types.as_ref().map(|types| {
+            tokens::elements(terms, types.start)
+                .iter()
+                .filter_map(source)
+                .collect::<Vec<_>>()
+        });
+        let value = hover::parameter(
+            &entry,
+            entry.default.as_ref().and_then(source),
+            types,
+            entry.counts.as_ref().and_then(source),
+        );
+        let span = (
+            terms[entry.terms.start].span.0,
+            terms[entry.terms.end - 1].span.1,
+        );
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: span_to_range(span, rope),
+        })
+    }
+
+    /// Hover of a number given to a command whose numeric argument is enumeration-like, explaining
+    /// its meaning
+    fn enum_hover(
+        &self,
+        (rope, terms): (&Rope, &[Spanned<&str>]),
+        position: Position,
+    ) -> Option<Hover> {
+        let offset = position_to_offset(position, rope)?;
+        let (span, value) = enums::hover(terms, offset)?;
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: span_to_range(span, rope),
+        })
+    }
+
+    /// The description of the built-in macro under the cursor and its value at this usage
+    fn builtin_hover(&self, uri: &Url, position: Position) -> Option<Hover> {
+        let rope = self.documents.get(uri)?;
+        let offset = position_to_offset(position, &rope)?;
+        let (start, end) = word_at(offset, &rope)?;
+        let name = rope.get_slice(start..end)?.to_string();
+        let description = builtins::get(&name)?;
+        let line = rope.try_char_to_line(start).ok()?;
+        let path = uri.to_file_path().ok()?;
+        let mut value = format!("```sqf\n{name}\n```\n{description}");
+        if let Some(evaluated) = builtins::value(&name, &self.game_path(&path), line) {
+            value.push_str(&format!("\n\nValue here: `{evaluated}`"));
+        }
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: Some(Range::new(
+                offset_to_position(start, &rope)?,
+                offset_to_position(end, &rope)?,
+            )),
+        })
+    }
+
+    /// Sends a request to the game through the debug bridge, returning its answer
+    async fn game_request(&self, request: debug::Request) -> Result<Option<Value>> {
+        let address = self.debug_bridge.read().unwrap().clone();
+        if address.is_empty() {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(
+                "The debug bridge is disabled; set `sqf-analyzer.server.debug_bridge` to the address of the game",
+            ));
+        }
+        let result = tokio::task::spawn_blocking(move || debug::request(&address, &request))
+            .await
+            .map_err(|_| tower_lsp::jsonrpc::Error::internal_error())?;
+        result
+            .map(|x| Some(Value::String(x)))
+            .map_err(|message| tower_lsp::jsonrpc::Error {
+                code: tower_lsp::jsonrpc::ErrorCode::InternalError,
+                message: message.into(),
+                data: None,
             })
-            .filter(|error| error_on_unused || (error.type_ != ErrorType::UnusedVariable))
-            .filter_map(|error| {
-                let origin = error
-                    .origin
-                    .clone()
-                    .and_then(|x| Url::from_file_path(x).ok())
-                    .unwrap_or_else(|| uri.clone());
-                let rope = self.documents.get(&origin)?;
-                to_diagnostic(error, &rope).map(|x| (origin, x))
+    }
+
+    /// The edits applied before saving a document: the formatting of configs, with the indentation
+    /// the document already uses, and the casing of the commands of scripts
+    fn save_edits(&self, uri: &Url) -> Option<Vec<TextEdit>> {
+        let path = uri.to_file_path().ok()?;
+        if config::is_config(&path) && !self.script_headers.contains(uri) {
+            let text = self.documents.get(uri)?.to_string();
+            let indentation = text
+                .lines()
+                .map(|line| &line[..line.len() - line.trim_start().len()])
+                .find(|x| !x.is_empty())
+                .unwrap_or("    ");
+            let options = FormattingOptions {
+                tab_size: indentation.len() as u32,
+                insert_spaces: !indentation.starts_with('\t'),
+                ..Default::default()
+            };
+            return self.format_config(uri, &options);
+        }
+        let rope = self.documents.get(uri)?;
+        let text = rope.to_string();
+        let macros = macros::defined(&text, &path, &self.addons(&path), &self.scope())
+            .into_iter()
+            .map(|x| x.name)
+            .chain(self.ifdef().defines)
+            .collect();
+        let edits = casing::fixes(&text, &macros)
+            .into_iter()
+            .filter_map(|(span, name)| {
+                Some(TextEdit::new(span_to_range(span, &rope)?, name.to_string()))
             })
-            .fold(
-                std::collections::BTreeMap::<_, Vec<_>>::new(),
-                |mut acc, (a, b)| {
-                    acc.entry(a).or_default().push(b);
-                    acc
-                },
-            );
+            .collect();
+        Some(edits)
+    }
 
-        if diagnostics.is_empty() {
-            self.client
-                .publish_diagnostics(uri.clone(), vec![], Some(params.version))
-                .await;
+    /// The edit formatting a config document (`config.cpp`, `*.hpp`, `description.ext`), which is
+    /// left as is when it has syntax errors. Generated `mission.sqm` and scripts are not formatted.
+    fn format_config(&self, uri: &Url, options: &FormattingOptions) -> Option<Vec<TextEdit>> {
+        let path = uri.to_file_path().ok()?;
+        let is_sqm = path
+            .extension()
+            .is_some_and(|x| x.eq_ignore_ascii_case("sqm"));
+        if !config::is_config(&path) || is_sqm {
+            return None;
         }
-        for (url, diagnostics) in diagnostics {
-            self.client
-                .publish_diagnostics(url, diagnostics, Some(params.version))
-                .await;
+        let rope = self.documents.get(uri)?;
+        let text = rope.to_string();
+        let indent = if options.insert_spaces {
+            " ".repeat(options.tab_size as usize)
+        } else {
+            "\t".to_string()
+        };
+        let formatted = config::format::format(
+            &text,
+            &config::format::Options {
+                indent,
+                align_assignments: self.config_align_assignments.load(Ordering::Relaxed),
+            },
+        )?;
+        if formatted == text {
+            return Some(vec![]);
         }
+        let end = text_pos::position(rope.len_chars(), &rope)?;
+        Some(vec![TextEdit {
+            range: Range::new(Position::new(0, 0), end),
+            new_text: formatted,
+        }])
+    }
 
-        if let Some(state_semantic) = state_semantic {
-            if let Some(mut e) = self.states.get_mut(&uri) {
-                self.client
-                    .log_message(
-                        MessageType::INFO,
-                        format!("{:?}", &state_semantic.0.origins),
-                    )
-                    .await;
-                e.value_mut().0 = state_semantic;
-            } else {
-                self.states.insert(uri, (state_semantic, None));
-            };
+    /// The variables of each line of `range` up to the line where the execution stopped, to be looked
+    /// up by a debug adapter. They are the usages of variables known by the analysis.
+    fn inline_values(&self, uri: &Url, range: Range, stopped: Range) -> Option<Vec<InlineValue>> {
+        let rope = self.documents.get(uri)?;
+        let state = &self.states.get(uri)?.0.state;
+        let last_line = range.end.line.min(stopped.end.line);
+
+        let mut seen = std::collections::HashSet::new();
+        let mut values = state
+            .origins
+            .keys()
+            .filter_map(|span| {
+                let range = span_to_range(*span, &rope)?;
+                let name = rope.get_slice(span.0..span.1)?.to_string();
+                Some((range, name))
+            })
+            .filter(|(x, _)| range.start.line <= x.start.line && x.start.line <= last_line)
+            .collect::<Vec<_>>();
+        values.sort_by_key(|(range, _)| (range.start.line, range.start.character));
+        Some(
+            values
+                .into_iter()
+                // once per variable and line
+                .filter(|(range, name)| seen.insert((range.start.line, name.to_lowercase())))
+                .map(|(range, name)| {
+                    InlineValue::VariableLookup(InlineValueVariableLookup {
+                        range,
+                        variable_name: Some(name),
+                        case_sensitive_lookup: false,
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    /// The macro under the cursor and its definitions in the compilation of the document
+    fn macro_at(&self, uri: &Url, position: Position) -> Option<(String, Vec<macros::Macro>)> {
+        let (name, text) = {
+            let rope = self.documents.get(uri)?;
+            let offset = position_to_offset(position, &rope)?;
+            let (start, end) = word_at(offset, &rope)?;
+            (rope.get_slice(start..end)?.to_string(), rope.to_string())
+        };
+        if name.is_empty() {
+            return None;
         }
+        let path = uri.to_file_path().ok()?;
+        let definitions = macros::defined(&text, &path, &self.addons(&path), &self.scope())
+            .into_iter()
+            .filter(|x| x.name == name)
+            .collect::<Vec<_>>();
+        (!definitions.is_empty()).then_some((name, definitions))
     }
 
-    fn hover(&self, uri: Url, position: Position) -> Option<Hover> {
-        let rope = self.documents.get(&uri)?;
+    /// The macros defined for the document starting with `prefix`, detailed with their expansion
+    /// (e.g. `ADDON` with `tag_component`)
+    fn defined_macro_items(&self, uri: &Url, prefix: &str) -> Vec<CompletionItem> {
+        if prefix.is_empty() {
+            return vec![];
+        }
+        let Some(text) = self.documents.get(uri).map(|x| x.to_string()) else {
+            return vec![];
+        };
+        let Ok(path) = uri.to_file_path() else {
+            return vec![];
+        };
+        let defined = macros::defined(&text, &path, &self.addons(&path), &self.scope());
+        let mut seen = std::collections::HashSet::new();
+        defined
+            .iter()
+            .filter(|x| x.name.starts_with(prefix) && seen.insert(x.name.clone()))
+            .map(|x| {
+                let detail = match &x.arguments {
+                    Some(arguments) => format!("{}({})", x.name, arguments.join(",")),
+                    None => macros::expand(&x.name, &defined),
+                };
+                CompletionItem {
+                    label: x.name.clone(),
+                    kind: Some(CompletionItemKind::CONSTANT),
+                    detail: Some(detail),
+                    documentation: Some(Documentation::MarkupContent(MarkupContent {
+                        kind: MarkupKind::Markdown,
+                        value: x.markdown(),
+                    })),
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+
+    /// The invocation of the macro at `position` (e.g. `QUOTE(ADDON)`) and its expansion with the
+    /// macros of the document and of the files it includes (e.g. `script_component.hpp`)
+    fn macro_expansion(&self, uri: &Url, position: Position) -> Option<(String, String)> {
+        let (start, text) = {
+            let rope = self.documents.get(uri)?;
+            let offset = position_to_offset(position, &rope)?;
+            (word_at(offset, &rope)?.0, rope.to_string())
+        };
+        let path = uri.to_file_path().ok()?;
+        let defined = macros::defined(&text, &path, &self.addons(&path), &self.scope());
+        let invocation = macros::invocation(&text, start)?;
+        let expanded = macros::expand(&invocation, &defined);
+        (expanded != invocation).then_some((invocation, expanded))
+    }
 
-        let state = &self.states.get(&uri)?.0 .0;
+    /// The references to the macro under the cursor in the document and the files it includes
+    fn macro_references(&self, uri: &Url, position: Position) -> Option<Vec<Location>> {
+        let (name, _) = self.macro_at(uri, position)?;
+        let path = uri.to_file_path().ok()?;
+        let text = self.documents.get(uri)?.to_string();
+        let included = macros::included(&text, &path, &self.addons(&path), &self.scope());
+        let locations = macros::references(&text, &name)
+            .into_iter()
+            .filter_map(|span| self.location(None, uri, span))
+            .chain(included.iter().flat_map(|(path, text)| {
+                macros::references(text, &name)
+                    .into_iter()
+                    .filter_map(|span| self.location(Some(path), uri, span))
+            }))
+            .collect();
+        Some(locations)
+    }
 
-        let offset = position_to_offset(position, &rope)?;
+    /// The location of `span` in the file at `path`, or in the document `uri` when `None`
+    fn location(&self, path: Option<&Path>, uri: &Url, span: (usize, usize)) -> Option<Location> {
+        let url = match path {
+            Some(path) => Url::from_file_path(hemtt::map(path)).ok()?,
+            None => uri.clone(),
+        };
+        let range = match self.documents.get(&url) {
+            Some(rope) => span_to_range(span, &rope)?,
+            None => span_to_range(span, &Rope::from_str(&self.read(path?)?))?,
+        };
+        Some(Location::new(url, range))
+    }
+
+    /// Hover of a function declared in `CfgFunctions`, showing the script it resolves to
+    fn config_hover(&self, uri: &Url, position: Position) -> Option<Hover> {
+        let (declaration, range) = {
+            let rope = self.documents.get(uri)?;
+            let offset = position_to_offset(position, &rope)?;
+            let config = config::parse(&rope.to_string());
+            let declaration = config::functions::declarations(&config)
+                .into_iter()
+                .find(|x| x.span.0 <= offset && offset < x.span.1)?;
+            let range = span_to_range(declaration.class.span, &rope);
+            (declaration, range)
+        };
+
+        let config_path = uri.to_file_path().ok()?;
+        let addons = self.addons(&config_path);
+        let path = addon::function_path(&config_path, &declaration.name, &addons, &self.scope())
+            .and_then(|x| x.ok());
+
+        let output = path
+            .as_ref()
+            .and_then(|path| Url::from_file_path(path).ok())
+            .and_then(|url| {
+                let state = self.states.get(&url)?;
+                state
+                    .0
+                    .state
+                    .globals(state.1.clone())
+                    .into_iter()
+                    .find(|(name, _)| name.to_string().eq_ignore_ascii_case(&declaration.name))
+                    .and_then(|(_, (_, output))| output)
+            });
 
-        hover::hover(state, offset).map(|explanation| Hover {
+        Some(Hover {
             contents: HoverContents::Markup(MarkupContent {
                 kind: MarkupKind::Markdown,
-                value: explanation.to_string(),
+                value: hover::function(&declaration, path.as_deref(), output),
             }),
-            range: None,
+            range,
         })
     }
 
     fn inlay(&self, uri: Url) -> Option<Vec<InlayHint>> {
         let document = self.documents.get(&uri)?;
 
-        let state = &self.states.get(&uri)?.0 .0;
+        let state = &self.states.get(&uri)?.0.state;
 
         let items = state
             .types
@@ -613,20 +4925,71 @@ impl Backend {
             Some(inlay_hint)
         });
 
-        Some(items.chain(params).collect())
+        // the values of `__FILE__` and `__LINE__`, known from the document alone
+        let file = uri
+            .to_file_path()
+            .map(|path| self.game_path(&path))
+            .unwrap_or_default();
+        let builtins = builtins::usages(&document.to_string())
+            .into_iter()
+            .filter_map(|usage| {
+                let line = document.try_char_to_line(usage.span.0).ok()?;
+                let value = builtins::value(usage.inner, &file, line)?;
+                Some(InlayHint {
+                    text_edits: None,
+                    tooltip: None,
+                    kind: None,
+                    padding_left: Some(true),
+                    padding_right: None,
+                    data: None,
+                    position: offset_to_position(usage.span.1, &document)?,
+                    label: InlayHintLabel::String(format!("= {value}")),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // the type of `_x` in the code blocks iterating arrays of a known type
+        let text = document.to_string();
+        let iterations = sqf::preprocessor::parse(&text)
+            .map(|ast| iteration::blocks(&tokens::terms(&ast)))
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(span, type_)| {
+                Some(InlayHint {
+                    text_edits: None,
+                    tooltip: None,
+                    kind: Some(InlayHintKind::TYPE),
+                    padding_left: Some(true),
+                    padding_right: None,
+                    data: None,
+                    position: offset_to_position(span.1, &document)?,
+                    label: InlayHintLabel::String(format!("_x: {type_:?}")),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        Some(
+            items
+                .chain(params)
+                .chain(builtins)
+                .chain(iterations)
+                .collect(),
+        )
     }
 
     fn semantic(&self, uri: Url) -> Option<Vec<SemanticToken>> {
-        let im_complete_tokens = &self.states.get(&uri)?.0 .1;
         let rope = self.documents.get(&uri)?;
+        let state = self.states.get(&uri)?;
+        let text = rope.to_string();
         let mut previous_line = 0;
         let mut previous_start = 0;
-        let semantic_tokens = im_complete_tokens
+        let semantic_tokens = state
+            .0
+            .semantic_tokens(&text)
             .iter()
             .filter_map(|token| {
-                let line = rope.try_byte_to_line(token.start).ok()? as u32;
-                let first = rope.try_line_to_char(line as usize).ok()? as u32;
-                let start = rope.try_byte_to_char(token.start).ok()? as u32 - first;
+                let position = text_pos::position(token.start, &rope)?;
+                let (line, start) = (position.line, position.character);
                 let delta_line = line - previous_line;
                 let delta_start = if delta_line == 0 {
                     start - previous_start
@@ -636,7 +4999,7 @@ impl Backend {
                 let ret = Some(SemanticToken {
                     delta_line,
                     delta_start,
-                    length: token.length as u32,
+                    length: text_pos::utf16_length(token.start, token.length, &rope)? as u32,
                     token_type: token.token_type as u32,
                     token_modifiers_bitset: 0,
                 });
@@ -648,105 +5011,170 @@ impl Backend {
         Some(semantic_tokens)
     }
 
-    fn completion(&self, uri: Url) -> Option<CompletionResponse> {
-        let state = &self.states.get(&uri)?.0 .0;
+    fn completion(&self, uri: Url, position: Position) -> Option<CompletionResponse> {
+        if let Some(keys) = self.variable_key_completion(&uri, position) {
+            return Some(keys);
+        }
+        if let Some(events) = self.event_completion(&uri, position) {
+            return Some(events);
+        }
+        if let Some(arguments) = self.remote_exec_completion(&uri, position) {
+            return Some(arguments);
+        }
+        if let Some(attributes) = self.function_attribute_completion(&uri, position) {
+            return Some(attributes);
+        }
+        if let Some(macros) = self.macro_completion(&uri, position) {
+            return Some(macros);
+        }
+        if let Some(types) = self.params_type_completion(&uri, position) {
+            return Some(types);
+        }
 
-        let vars = state
-            .namespace
-            .stack
-            .iter()
-            .map(|stack| stack.variables.keys())
-            .flatten()
-            .map(|var| CompletionItem {
-                label: var.to_string(),
-                kind: Some(CompletionItemKind::VARIABLE),
-                ..Default::default()
-            })
-            .chain(NULLARY.iter().map(|(var, (type_, detail))| CompletionItem {
-                label: var.to_string(),
-                kind: Some(CompletionItemKind::CONSTANT),
-                detail: Some(detail.to_string()),
-                documentation: Some(Documentation::MarkupContent(MarkupContent {
-                    kind: MarkupKind::Markdown,
-                    value: format!("* `{type_:?}`: {}", detail.to_string()),
-                })),
-                ..Default::default()
-            }))
-            .chain(UNARY.iter().map(|(var, variants)| {
-                CompletionItem {
-                    label: var.to_string(),
-                    kind: Some(CompletionItemKind::FUNCTION),
-                    detail: variants
-                        .iter()
-                        .next()
-                        .and_then(|(_, value)| value.get(0).map(|x| x.1.to_string())),
-                    documentation: Some(Documentation::MarkupContent(MarkupContent {
-                        kind: MarkupKind::Markdown,
-                        value: variants
-                            .iter()
-                            .map(|(type_, value)| {
-                                value.iter().map(move |(t, explanation)| {
-                                    format!("* `{} {:?} -> {:?}`: {}", var, type_, t, explanation)
-                                })
-                            })
-                            .flatten()
-                            .collect::<Vec<String>>()
-                            .join("\n"),
-                    })),
-                    ..Default::default()
-                }
-            }))
-            .chain(BINARY.iter().map(|(var, variants)| {
-                CompletionItem {
-                    label: var.to_string(),
-                    kind: Some(CompletionItemKind::FUNCTION),
-                    detail: variants
-                        .iter()
-                        .next()
-                        .and_then(|(_, value)| value.get(0).map(|x| x.1.to_string())),
-                    documentation: Some(Documentation::MarkupContent(MarkupContent {
-                        kind: MarkupKind::Markdown,
-                        value: variants
-                            .iter()
-                            .map(|(type_, value)| {
-                                value.iter().map(|(t, explanation)| {
-                                    format!(
-                                        "* `{:?} {} {:?} -> {:?}`: {}",
-                                        type_.0, var, type_.1, t, explanation,
-                                    )
-                                })
-                            })
-                            .flatten()
-                            .collect::<Vec<String>>()
-                            .join("\n"),
-                    })),
-                    ..Default::default()
-                }
-            }));
+        let prefix = {
+            let rope = self.documents.get(&uri)?;
+            let offset = position_to_offset(position, &rope)?;
+            let (start, _) = word_at(offset, &rope)?;
+            rope.get_slice(start..offset)?.to_string()
+        };
+        let limit = match self.completion_limit.load(Ordering::Relaxed) {
+            0 => None,
+            limit => Some(limit as usize),
+        };
+        let state = self.states.get(&uri)?;
+        let (items, is_incomplete) = if config::is_config(&uri.to_file_path().ok()?) {
+            // only within the UI event handlers
+            let (offset, text) = {
+                let rope = self.documents.get(&uri)?;
+                (position_to_offset(position, &rope)?, rope.to_string())
+            };
+            dialog::spans(&text)
+                .iter()
+                .find(|(start, end)| *start < offset && offset < *end)?;
+            state
+                .0
+                .ranked_ui_completion(&prefix, limit, &self.explanations.read().unwrap())
+        } else {
+            state
+                .0
+                .ranked_completion(&prefix, limit, &self.explanations.read().unwrap())
+        };
+        let mut items = items;
+        items.extend(self.defined_macro_items(&uri, &prefix));
 
-        Some(CompletionResponse::Array(vars.collect()))
+        Some(CompletionResponse::List(CompletionList {
+            is_incomplete,
+            items,
+        }))
     }
 }
 
-fn to_diagnostic(item: Error, rope: &Rope) -> Option<Diagnostic> {
-    let severity = match item.type_ {
-        ErrorType::PrivateAssignedToMission => DiagnosticSeverity::INFORMATION,
-        ErrorType::UndefinedVariable(_) => DiagnosticSeverity::WARNING,
-        ErrorType::UnusedVariable => DiagnosticSeverity::INFORMATION,
-        _ => DiagnosticSeverity::ERROR,
-    };
-    let (message, span) = (item.type_.to_string(), item.span);
+fn to_document_symbol(symbol: Symbol, rope: &Rope) -> Option<DocumentSymbol> {
+    let children = symbol
+        .children
+        .into_iter()
+        .filter_map(|x| to_document_symbol(x, rope))
+        .collect::<Vec<_>>();
+    #[allow(deprecated)]
+    Some(DocumentSymbol {
+        name: symbol.name,
+        detail: symbol.detail,
+        kind: symbol.kind,
+        tags: None,
+        deprecated: None,
+        range: span_to_range(symbol.span, rope)?,
+        selection_range: span_to_range(symbol.selection_span, rope)?,
+        children: (!children.is_empty()).then_some(children),
+    })
+}
+
+fn to_diagnostic((stage, item): StagedError, rope: &Rope) -> Option<Diagnostic> {
+    let code = rules::code(stage, &item.type_);
+    diagnostic(stage, code, item.span, item.type_.to_string(), rope)
+}
+
+fn lint_to_diagnostic(item: lint::Lint, rope: &Rope) -> Option<Diagnostic> {
+    diagnostic(Stage::Analyzer, item.code, item.span, item.message, rope)
+}
+
+/// The diagnostics of the unbalanced brackets of a script, related to the brackets left open
+fn bracket_diagnostics(
+    url: &Url,
+    text: &str,
+    rope: &Rope,
+    ifdef: &ifdef::Settings,
+) -> Vec<(Url, Diagnostic)> {
+    brackets::unbalanced(text, ifdef)
+        .into_iter()
+        .filter_map(|unbalanced| {
+            let mut diagnostic = lint_to_diagnostic(unbalanced.lint(), rope)?;
+            if let Some(opened) = unbalanced.opened {
+                diagnostic.related_information = Some(vec![DiagnosticRelatedInformation {
+                    location: Location::new(url.clone(), span_to_range(opened.span, rope)?),
+                    message: format!("unclosed `{}` opened here", opened.inner),
+                }]);
+            }
+            Some((url.clone(), diagnostic))
+        })
+        .collect()
+}
+
+fn diagnostic(
+    stage: Stage,
+    code: &str,
+    span: (usize, usize),
+    message: String,
+    rope: &Rope,
+) -> Option<Diagnostic> {
+    let severity = rules::get(code)
+        .map(|rule| rule.severity)
+        .unwrap_or(DiagnosticSeverity::ERROR);
     let start_position = offset_to_position(span.0, rope)?;
     let end_position = offset_to_position(span.1, rope)?;
-    Some(Diagnostic::new(
-        Range::new(start_position, end_position),
-        Some(severity),
+    Some(Diagnostic {
+        code: Some(NumberOrString::String(code.to_string())),
+        code_description: rule_url(code).map(|href| CodeDescription { href }),
+        ..Diagnostic::new(
+            Range::new(start_position, end_position),
+            Some(severity),
+            None,
+            Some(stage.source().into()),
+            message,
+            None,
+            // faded by editors
+            matches!(code, "inactive-region" | "dead-store")
+                .then(|| vec![DiagnosticTag::UNNECESSARY]),
+        )
+    })
+}
+
+/// The `max` most severe of `diagnostics`, followed by an informational diagnostic stating how many
+/// were left out, if any
+fn truncate(mut diagnostics: Vec<Diagnostic>, max: usize) -> Vec<Diagnostic> {
+    if diagnostics.len() <= max {
+        return diagnostics;
+    }
+    let suppressed = diagnostics.len() - max;
+    // stable, keeping the order of the diagnostics of the same severity
+    diagnostics.sort_by_key(|x| x.severity.unwrap_or(DiagnosticSeverity::ERROR));
+    diagnostics.truncate(max);
+    diagnostics.push(Diagnostic::new(
+        Range::default(),
+        Some(DiagnosticSeverity::INFORMATION),
         None,
-        Some("sqf-analyzer".into()),
-        message,
+        Some("sqf-analyzer".to_string()),
+        format!(
+            "{suppressed} more diagnostics are not shown, above the limit of {max} per file (setting `sqf-analyzer.server.max_diagnostics`)"
+        ),
         None,
         None,
-    ))
+    ));
+    diagnostics
+}
+
+/// The url of the explanation of a rule, served by the client via the `sqf-analyzer.explain` command
+fn rule_url(code: &str) -> Option<Url> {
+    Url::parse(&format!("sqf-analyzer:/rules/{code}.md")).ok()
 }
 
 #[tokio::main]
@@ -756,29 +5184,94 @@ async fn main() {
     let stdin = tokio::io::stdin();
     let stdout = tokio::io::stdout();
 
-    let (service, socket) = LspService::build(|client| Backend {
-        client,
-        undefined_variables_are_error: false.into(),
-        private_variables_in_mission_are_error: false.into(),
-        error_on_unused: false.into(),
-        addon_paths: Default::default(),
-        is_loaded: false.into(),
-        states: Default::default(),
-        documents: Default::default(),
+    let (service, socket) = LspService::build(|client| {
+        Handle(Arc::new(Backend {
+            client,
+            undefined_variables_are_error: false.into(),
+            private_variables_in_mission_are_error: false.into(),
+            error_on_unused: false.into(),
+            addon_paths: Default::default(),
+            missions: Default::default(),
+            variable_keys: Default::default(),
+            unmatched_variable_keys: Default::default(),
+            events: Default::default(),
+            functions: Default::default(),
+            calls: Default::default(),
+            addon_usages: Default::default(),
+            handler_diagnostics: Default::default(),
+            unmatched_events: false.into(),
+            restricted: false.into(),
+            scope: Default::default(),
+            ifdef: Default::default(),
+            explanations: Default::default(),
+            inactive_regions: false.into(),
+            analysis_timeout_ms: DEFAULT_ANALYSIS_TIMEOUT_MS.into(),
+            completion_limit: DEFAULT_COMPLETION_LIMIT.into(),
+            max_file_size_kb: DEFAULT_MAX_FILE_SIZE_KB.into(),
+            max_diagnostics: DEFAULT_MAX_DIAGNOSTICS.into(),
+            run_on_save: false.into(),
+            debounce_ms: DEFAULT_DEBOUNCE_MS.into(),
+            debounces: Default::default(),
+            rename_string_references: false.into(),
+            config_align_assignments: false.into(),
+            fix_on_save: false.into(),
+            on_type_close: RwLock::new(vec![on_type::Construct::Endif, on_type::Construct::Class]),
+            switch_require_default: false.into(),
+            debug_bridge: Default::default(),
+            todo_markers: RwLock::new(
+                todo::DEFAULT_MARKERS
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect(),
+            ),
+            is_loaded: false.into(),
+            states: Default::default(),
+            preloaded: Default::default(),
+            script_headers: Default::default(),
+            function_files: Default::default(),
+            addon_roots: Default::default(),
+            workspace_folders: Default::default(),
+            projects: Default::default(),
+            baselines: Default::default(),
+            suppressions: Default::default(),
+            rule_levels: Default::default(),
+            command_rule_levels: Default::default(),
+            warnings_as_errors: Default::default(),
+            overrides: Default::default(),
+            diagnostics: Default::default(),
+            generations: Default::default(),
+            generation: Default::default(),
+            versions: Default::default(),
+            started: Instant::now(),
+            last_scan: Default::default(),
+            statistics: Default::default(),
+            documents: Default::default(),
+        }))
     })
+    .custom_method("sqfAnalyzer/statistics", Handle::statistics)
+    .custom_method("sqfAnalyzer/typeAt", Handle::type_at)
+    .custom_method("sqfAnalyzer/globals", Handle::globals)
     .finish();
 
     Server::new(stdin, stdout, socket).serve(service).await;
 }
 
 fn offset_to_position(offset: usize, rope: &Rope) -> Option<Position> {
-    let line = rope.try_char_to_line(offset).ok()?;
-    let first_char_of_line = rope.try_line_to_char(line).ok()?;
-    let column = offset - first_char_of_line;
-    Some(Position::new(line as u32, column as u32))
+    text_pos::position(offset, rope)
 }
 
 fn position_to_offset(position: Position, rope: &Rope) -> Option<usize> {
-    let char = rope.try_line_to_char(position.line as usize).ok()?;
-    Some(char + position.character as usize)
+    text_pos::offset(position, rope)
+}
+
+/// Returns the (char) range of the word around `offset`, which may be empty
+fn word_at(offset: usize, rope: &Rope) -> Option<(usize, usize)> {
+    let is_word = |c: &&char| c.is_alphanumeric() || **c == '_';
+    let line = rope.try_char_to_line(offset).ok()?;
+    let first = rope.try_line_to_char(line).ok()?;
+    let text = rope.line(line).chars().collect::<Vec<_>>();
+    let column = (offset - first).min(text.len());
+    let start = column - text[..column].iter().rev().take_while(is_word).count();
+    let end = column + text[column..].iter().take_while(is_word).count();
+    Some((first + start, first + end))
 }
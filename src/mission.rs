@@ -0,0 +1,126 @@
+//! Index of the entities (markers, triggers, units, ...) placed in the editor and stored in `mission.sqm`
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use sqf::analyzer::{Origin, Output};
+use sqf::span::Spanned;
+use sqf::types::Type;
+use sqf::UncasedStr;
+
+use crate::config::{self, Class};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityKind {
+    Marker,
+    Trigger,
+    Object,
+    Logic,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entity {
+    pub name: Spanned<String>,
+    pub kind: EntityKind,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MissionSqm {
+    /// the path of `mission.sqm`
+    pub path: PathBuf,
+    pub entities: Vec<Entity>,
+}
+
+impl MissionSqm {
+    /// Returns the entity named `name` (case-insensitive) of a given kind
+    pub fn get(&self, name: &str, kind: EntityKind) -> Option<&Entity> {
+        self.entities
+            .iter()
+            .find(|entity| entity.kind == kind && entity.name.inner.eq_ignore_ascii_case(name))
+    }
+
+    /// The named triggers, units and logics, which are global variables of the mission
+    pub fn globals(
+        &self,
+    ) -> impl Iterator<Item = (Arc<UncasedStr>, (Origin, Option<Output>))> + '_ {
+        let path: Arc<Path> = self.path.clone().into();
+        self.entities
+            .iter()
+            .filter(|entity| entity.kind != EntityKind::Marker)
+            .map(move |entity| {
+                (
+                    Arc::from(UncasedStr::new(&entity.name.inner)),
+                    (
+                        Origin(path.clone(), Some(entity.name.span)),
+                        Some(Output::Type(Type::Object)),
+                    ),
+                )
+            })
+    }
+}
+
+fn name(class: &Class) -> Option<Spanned<String>> {
+    class
+        .property("name")
+        .or_else(|| class.class("Attributes")?.property("name"))
+        .and_then(|property| {
+            let name = property.value.inner.as_str()?;
+            (!name.is_empty()).then(|| Spanned::new(name.to_string(), property.value.span))
+        })
+}
+
+fn entities(class: &Class, container: &mut Vec<Entity>) {
+    for item in class.classes() {
+        let kind = item
+            .property("dataType")
+            .and_then(|x| x.value.inner.as_str());
+        let kind = match kind {
+            Some(kind) if kind.eq_ignore_ascii_case("Marker") => EntityKind::Marker,
+            Some(kind) if kind.eq_ignore_ascii_case("Trigger") => EntityKind::Trigger,
+            Some(kind) if kind.eq_ignore_ascii_case("Object") => EntityKind::Object,
+            Some(kind) if kind.eq_ignore_ascii_case("Logic") => EntityKind::Logic,
+            _ => {
+                // groups and layers contain other entities
+                if let Some(nested) = item.class("Entities") {
+                    entities(nested, container)
+                }
+                continue;
+            }
+        };
+        if let Some(name) = name(item) {
+            container.push(Entity { name, kind })
+        }
+    }
+}
+
+/// Indexes the entities of the (non-binarized) content of a `mission.sqm`
+pub fn index(text: &str, path: PathBuf) -> MissionSqm {
+    let config = config::parse(text);
+    let mut container = vec![];
+    if let Some(root) = config
+        .class("Mission")
+        .and_then(|mission| mission.class("Entities"))
+    {
+        entities(root, &mut container);
+    }
+    MissionSqm {
+        path,
+        entities: container,
+    }
+}
+
+/// Loads the `mission.sqm` next to a mission's `description.ext`, if any
+pub fn load(config_path: &Path) -> Option<MissionSqm> {
+    let is_mission = config_path
+        .file_name()
+        .map(|x| x.eq_ignore_ascii_case("description.ext"))
+        .unwrap_or(false);
+    if !is_mission {
+        return None;
+    }
+    let path = config_path.with_file_name("mission.sqm");
+    if config::is_binarized(&path) {
+        return None;
+    }
+    let content = std::fs::read_to_string(&path).ok()?;
+    Some(index(&content, path))
+}
@@ -0,0 +1,76 @@
+//! The completion of constructs as they are typed: the `#endif` of a condition and the `};` of a
+//! class of a config, inserted when a new line follows their opening and they are not closed
+
+/// A construct that may be closed when a new line follows its opening
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Construct {
+    /// `#if`, `#ifdef` and `#ifndef`, closed by `#endif`
+    Endif,
+    /// `class a {`, closed by `};` in configs
+    Class,
+}
+
+impl Construct {
+    pub fn from_setting(value: &str) -> Option<Self> {
+        match value {
+            "endif" => Some(Construct::Endif),
+            "class" => Some(Construct::Class),
+            _ => None,
+        }
+    }
+}
+
+fn directive(line: &str) -> Option<&str> {
+    let directive = line.trim_start().strip_prefix('#')?.trim_start();
+    directive.split(|c: char| !c.is_alphanumeric()).next()
+}
+
+/// The number of conditions of `text` not closed by an `#endif`
+fn open_conditions(text: &str) -> isize {
+    text.lines()
+        .map(|line| match directive(line) {
+            Some("if" | "ifdef" | "ifndef") => 1,
+            Some("endif") => -1,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// The number of `{` of `text` not closed by a `}`, ignoring strings and comments
+fn open_braces(text: &str) -> isize {
+    let mut count = 0;
+    for line in text.lines() {
+        let mut in_string = false;
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => in_string = !in_string,
+                '/' if !in_string && chars.peek() == Some(&'/') => break,
+                '{' if !in_string => count += 1,
+                '}' if !in_string => count -= 1,
+                _ => {}
+            }
+        }
+    }
+    count
+}
+
+/// The line closing the construct opened by `previous` (the line before the new line) in `text`,
+/// when it is one of `constructs` and is not closed. `is_config` enables classes.
+pub fn closing(
+    text: &str,
+    previous: &str,
+    constructs: &[Construct],
+    is_config: bool,
+) -> Option<String> {
+    let is_condition = matches!(directive(previous), Some("if" | "ifdef" | "ifndef"));
+    if is_condition && constructs.contains(&Construct::Endif) && open_conditions(text) > 0 {
+        return Some("#endif".to_string());
+    }
+    let is_class = previous.trim_end().ends_with('{') && !previous.trim_start().starts_with('#');
+    if is_class && is_config && constructs.contains(&Construct::Class) && open_braces(text) > 0 {
+        let indentation = &previous[..previous.len() - previous.trim_start().len()];
+        return Some(format!("{indentation}}};"));
+    }
+    None
+}
@@ -0,0 +1,361 @@
+//! Parameters declared via `params`
+use std::collections::BTreeSet;
+
+use sqf::analyzer::{BINARY, UNARY};
+use sqf::span::{Span, Spanned};
+use sqf::types::Type;
+use sqf::UncasedStr;
+
+use crate::lint::Lint;
+use crate::private;
+use crate::tokens;
+
+/// Returns the names of the parameters declared with literal strings by `params`, i.e. `"_a"` and
+/// `["_b", default, types]` in `params ["_a", ["_b", default, types]]`, with the span of the string
+pub fn names(terms: &[Spanned<&str>]) -> Vec<Spanned<String>> {
+    let mut names = vec![];
+    for (i, term) in terms.iter().enumerate() {
+        if !term.inner.eq_ignore_ascii_case("params")
+            || terms.get(i + 1).map(|x| x.inner) != Some("[")
+        {
+            continue;
+        }
+        let mut depth = 0usize;
+        for (j, term) in terms.iter().enumerate().skip(i + 1) {
+            match term.inner {
+                "[" => depth += 1,
+                "]" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {
+                    let is_name = depth == 1 || (depth == 2 && terms[j - 1].inner == "[");
+                    if let Some(name) = is_name.then(|| tokens::unquote(term.inner)).flatten() {
+                        names.push(Spanned {
+                            inner: name,
+                            span: term.span,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    names
+}
+
+/// A parameter read by position, i.e. `private _a = _this select 0;` or `_a = _this # 0;`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selection {
+    pub name: String,
+    pub index: usize,
+    /// span of the whole statement, including its `;`
+    pub span: Span,
+}
+
+/// Returns the parameters read by position with `_this select N` by the first statements of a
+/// script, which can be declared with a single `params`
+pub fn selections(terms: &[Spanned<&str>]) -> Vec<Selection> {
+    let mut selections: Vec<Selection> = vec![];
+    let mut i = 0;
+    loop {
+        let start = i;
+        if terms
+            .get(i)
+            .is_some_and(|x| x.inner.eq_ignore_ascii_case("private"))
+        {
+            i += 1;
+        }
+        let statement = terms.get(i..i + 6).and_then(|x| {
+            let is_selection = x[0].inner.starts_with('_')
+                && x[1].inner == "="
+                && x[2].inner.eq_ignore_ascii_case("_this")
+                && (x[3].inner.eq_ignore_ascii_case("select") || x[3].inner == "#")
+                && x[5].inner == ";";
+            let index = x[4].inner.parse::<usize>().ok()?;
+            is_selection.then(|| Selection {
+                name: x[0].inner.to_string(),
+                index,
+                span: (terms[start].span.0, x[5].span.1),
+            })
+        });
+        match statement {
+            Some(selection)
+                if !selections
+                    .iter()
+                    .any(|x| x.index == selection.index || x.name == selection.name) =>
+            {
+                selections.push(selection);
+                i += 6;
+            }
+            _ => break,
+        }
+    }
+    selections
+}
+
+/// The `params` declaring the parameters of `selections`, e.g. `params ["_a", "", "_c"];`, where
+/// `""` skips a parameter
+pub fn declaration(selections: &[Selection]) -> String {
+    let count = selections.iter().map(|x| x.index + 1).max().unwrap_or(0);
+    let names = (0..count)
+        .map(|index| {
+            let name = selections
+                .iter()
+                .find(|x| x.index == index)
+                .map(|x| x.name.as_str())
+                .unwrap_or("");
+            format!("\"{name}\"")
+        })
+        .collect::<Vec<_>>();
+    format!("params [{}];", names.join(", "))
+}
+
+/// Lints the parameters read with `_this select N` at the top of a script when there are several
+pub fn lint(terms: &[Spanned<&str>]) -> Option<Lint> {
+    let selections = selections(terms);
+    (selections.len() > 1).then(|| Lint {
+        code: "this-select",
+        span: (
+            selections[0].span.0,
+            selections[selections.len() - 1].span.1,
+        ),
+        message: format!(
+            "These parameters can be declared with `{}`",
+            declaration(&selections)
+        ),
+    })
+}
+
+/// An entry of a `params` array, `"_a"` or `["_a", default, types, counts]`, with the ranges of the
+/// terms of each of its parts
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    /// the position of the argument in `_this`
+    pub index: usize,
+    pub name: Spanned<String>,
+    pub default: Option<std::ops::Range<usize>>,
+    pub types: Option<std::ops::Range<usize>>,
+    pub counts: Option<std::ops::Range<usize>>,
+    /// the range of all the terms of the entry
+    pub terms: std::ops::Range<usize>,
+}
+
+/// A `params [...]` of a script
+#[derive(Debug, Clone, PartialEq)]
+pub struct Params {
+    /// index of `params`
+    pub keyword: usize,
+    /// index of the `[` and `]` of the array
+    pub array: (usize, usize),
+    pub entries: Vec<Entry>,
+}
+
+/// Returns the `params [...]` of the terms, with their entries declared with literal names
+pub fn declarations(terms: &[Spanned<&str>]) -> Vec<Params> {
+    terms
+        .iter()
+        .enumerate()
+        .filter(|(i, term)| {
+            term.inner.eq_ignore_ascii_case("params")
+                && terms.get(i + 1).map(|x| x.inner) == Some("[")
+        })
+        .filter_map(|(keyword, _)| {
+            let elements = tokens::elements(terms, keyword + 1);
+            let end = elements.last().map_or(keyword + 2, |x| x.end);
+            terms.get(end).filter(|x| x.inner == "]")?;
+            let entries = elements
+                .into_iter()
+                .enumerate()
+                .filter_map(|(index, element)| {
+                    let first = &terms[element.start];
+                    if let Some(name) = tokens::unquote(first.inner) {
+                        return (element.len() == 1).then(|| Entry {
+                            index,
+                            name: Spanned::new(name, first.span),
+                            default: None,
+                            types: None,
+                            counts: None,
+                            terms: element,
+                        });
+                    }
+                    let parts = tokens::elements(terms, element.start);
+                    let name = terms[parts.first()?.start];
+                    let name = Spanned::new(tokens::unquote(name.inner)?, name.span);
+                    Some(Entry {
+                        index,
+                        name,
+                        default: parts.get(1).cloned(),
+                        types: parts.get(2).cloned(),
+                        counts: parts.get(3).cloned(),
+                        terms: element,
+                    })
+                })
+                .collect();
+            Some(Params {
+                keyword,
+                array: (keyword + 1, end),
+                entries,
+            })
+        })
+        .collect()
+}
+
+/// The values whose type `params` accepts when in the array of expected types, e.g. `[objNull]`,
+/// with the name of the type
+pub const TYPE_VALUES: &[(&str, &str)] = &[
+    ("objNull", "object"),
+    ("grpNull", "group"),
+    ("\"\"", "string"),
+    ("0", "number"),
+    ("true", "boolean"),
+    ("[]", "array"),
+    ("{}", "code"),
+    ("sideUnknown", "side"),
+    ("configNull", "config"),
+    ("controlNull", "control"),
+    ("displayNull", "display"),
+    ("locationNull", "location"),
+    ("scriptNull", "script"),
+    ("taskNull", "task"),
+    ("teamMemberNull", "team member"),
+    ("missionNamespace", "namespace"),
+    ("createHashMap", "hash map"),
+];
+
+/// Whether the position after the terms is in the array of expected types of a `params` entry,
+/// i.e. `params [["_a", objNull, [`, when the terms end at the position
+pub fn is_in_types(terms: &[Spanned<&str>]) -> bool {
+    let Some(keyword) = terms
+        .iter()
+        .rposition(|x| x.inner.eq_ignore_ascii_case("params"))
+    else {
+        return false;
+    };
+    // the index of the element within each open bracket
+    let mut open: Vec<usize> = vec![];
+    for term in &terms[keyword + 1..] {
+        match term.inner {
+            "[" | "(" | "{" => open.push(0),
+            "]" | ")" | "}" => {
+                open.pop();
+                if open.is_empty() {
+                    return false;
+                }
+            }
+            "," => {
+                if let Some(element) = open.last_mut() {
+                    *element += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    // the third element of an entry of the array of `params`
+    open.len() == 3 && open[1] == 2
+}
+
+/// The literal used for a type in `params`, as default value and in the array of expected types
+pub fn type_literal(type_: Type) -> Option<&'static str> {
+    Some(match type_ {
+        Type::Boolean => "false",
+        Type::Number => "0",
+        Type::String => "\"\"",
+        Type::Array => "[]",
+        Type::Code => "{}",
+        Type::Object => "objNull",
+        Type::Group => "grpNull",
+        Type::Side => "sideUnknown",
+        Type::Config => "configNull",
+        _ => return None,
+    })
+}
+
+fn is_boundary(term: Option<&Spanned<&str>>) -> bool {
+    term.map(|x| matches!(x.inner, ";" | "," | "(" | "[" | "{" | "="))
+        .unwrap_or(true)
+}
+
+/// The types accepted for the variable at `i` by the command it is a whole operand of, if any
+fn accepted(terms: &[Spanned<&str>], i: usize) -> Option<BTreeSet<Type>> {
+    let previous = i.checked_sub(1).and_then(|x| terms.get(x));
+    let next = terms.get(i + 1);
+    let next_is_end = next.is_none_or(|x| matches!(x.inner, ";" | "," | ")" | "]" | "}"));
+    let types = if is_boundary(previous) {
+        // left operand
+        let signatures = BINARY.get(UncasedStr::new(next?.inner))?;
+        signatures.keys().map(|x| x.0).collect()
+    } else if next_is_end {
+        let command = previous?;
+        let before = (i - 1).checked_sub(1).and_then(|x| terms.get(x));
+        if is_boundary(before) {
+            let signatures = UNARY.get(UncasedStr::new(command.inner))?;
+            signatures.keys().copied().collect()
+        } else {
+            let signatures = BINARY.get(UncasedStr::new(command.inner))?;
+            signatures.keys().map(|x| x.1).collect()
+        }
+    } else {
+        return None;
+    };
+    Some(types)
+}
+
+/// The type of the parameter `name` inferred from its usage by the terms: the single type accepted
+/// by all the commands it is an operand of
+pub fn inferred_type(terms: &[Spanned<&str>], name: &str) -> Option<Type> {
+    let mut types: Option<BTreeSet<Type>> = None;
+    for (i, term) in terms.iter().enumerate() {
+        if !term.inner.eq_ignore_ascii_case(name) {
+            continue;
+        }
+        // reassigned: any type may be used afterwards
+        if terms.get(i + 1).is_some_and(|x| x.inner == "=") {
+            break;
+        }
+        let Some(accepted) = accepted(terms, i) else {
+            continue;
+        };
+        if accepted.contains(&Type::Anything) {
+            continue;
+        }
+        types = Some(match types {
+            Some(types) => types.intersection(&accepted).copied().collect(),
+            None => accepted,
+        });
+    }
+    let types = types?;
+    (types.len() == 1).then(|| *types.first().unwrap())
+}
+
+/// The edits adding a default value and the expected types to the entries of `params` without
+/// types, whose type is inferred from their usage in the rest of the scope: `"_a"` becomes
+/// `["_a", objNull, [objNull]]`, and `["_a", x]` becomes `["_a", x, [objNull]]`
+pub fn annotations(terms: &[Spanned<&str>], params: &Params) -> Vec<(Span, String)> {
+    let scope = private::scope_at(terms, terms[params.keyword].span.0);
+    let usage = &terms[params.array.1 + 1..scope.end.max(params.array.1 + 1)];
+    params
+        .entries
+        .iter()
+        .filter(|entry| entry.types.is_none())
+        .filter_map(|entry| {
+            let literal = type_literal(inferred_type(usage, &entry.name.inner)?)?;
+            let name = entry.name.span;
+            Some(match &entry.default {
+                Some(default) => {
+                    let end = terms[default.end - 1].span.1;
+                    ((end, end), format!(", [{literal}]"))
+                }
+                None if entry.terms.len() == 1 => (
+                    name,
+                    format!(
+                        "[{}, {literal}, [{literal}]]",
+                        terms[entry.terms.start].inner
+                    ),
+                ),
+                None => ((name.1, name.1), format!(", {literal}, [{literal}]")),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,223 @@
+//! The two styles of declaring private variables, `private _a = 1;` and `private ["_a"]; _a = 1;`,
+//! and the conversion of the declarations of a scope from one to the other
+use serde::Deserialize;
+use sqf::span::{Span, Spanned};
+
+use crate::tokens;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Style {
+    /// `private _a = 1;`
+    Keyword,
+    /// `private ["_a", "_b"];` at the entry of the scope
+    Array,
+}
+
+/// An edit of the text: the span replaced (empty for insertions) and the new text
+pub type Edit = (Span, String);
+
+/// The index of the term closing the bracket at `start`
+fn closing(terms: &[Spanned<&str>], start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, term) in terms.iter().enumerate().skip(start) {
+        match term.inner {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The range of the terms of the innermost code block containing `offset`, excluding its braces, or
+/// all the terms when it is at the top level
+pub fn scope_at(terms: &[Spanned<&str>], offset: usize) -> std::ops::Range<usize> {
+    let mut scope = 0..terms.len();
+    for (i, term) in terms.iter().enumerate() {
+        if term.inner != "{" || term.span.0 > offset {
+            continue;
+        }
+        if let Some(end) = closing(terms, i).filter(|end| offset <= terms[*end].span.0) {
+            scope = i + 1..end;
+        }
+    }
+    scope
+}
+
+/// The indices of the first terms of the statements of a scope
+fn statements(terms: &[Spanned<&str>], scope: std::ops::Range<usize>) -> Vec<usize> {
+    let mut statements = vec![];
+    let mut i = scope.start;
+    let mut is_start = true;
+    while i < scope.end {
+        if is_start && terms[i].inner != ";" {
+            statements.push(i);
+        }
+        is_start = terms[i].inner == ";";
+        i = match terms[i].inner {
+            "(" | "[" | "{" => closing(terms, i).unwrap_or(scope.end) + 1,
+            _ => i + 1,
+        };
+    }
+    statements
+}
+
+/// The end of the statement starting at `start`: the index of its `;` or of its last term
+fn statement_end(terms: &[Spanned<&str>], start: usize, end: usize) -> usize {
+    let mut i = start;
+    while i < end && terms[i].inner != ";" {
+        i = match terms[i].inner {
+            "(" | "[" | "{" => closing(terms, i).unwrap_or(end),
+            _ => i,
+        } + 1;
+    }
+    i.min(end.saturating_sub(1))
+}
+
+/// The names declared by `private ["_a", "_b"];` or `private "_a";` at `start`
+fn array_declaration(terms: &[Spanned<&str>], start: usize, end: usize) -> Option<Vec<String>> {
+    if !terms[start].inner.eq_ignore_ascii_case("private") {
+        return None;
+    }
+    let value = terms.get(start + 1)?;
+    if let Some(name) = tokens::unquote(value.inner) {
+        return Some(vec![name]);
+    }
+    if value.inner != "[" {
+        return None;
+    }
+    let close = closing(terms, start + 1).filter(|x| *x < end)?;
+    terms[start + 2..close]
+        .iter()
+        .filter(|x| x.inner != ",")
+        .map(|x| tokens::unquote(x.inner))
+        .collect()
+}
+
+/// Whether the statement at `start` is `name = ...` (or `private name = ...` when `keyword`)
+fn is_assignment(terms: &[Spanned<&str>], start: usize, keyword: bool) -> Option<usize> {
+    let name = if keyword {
+        terms[start]
+            .inner
+            .eq_ignore_ascii_case("private")
+            .then_some(start + 1)?
+    } else {
+        start
+    };
+    (terms.get(name)?.inner.starts_with('_') && terms.get(name + 1)?.inner == "=").then_some(name)
+}
+
+/// Whether `name` is used by the terms
+fn uses(terms: &[Spanned<&str>], name: &str) -> bool {
+    terms.iter().any(|x| x.inner.eq_ignore_ascii_case(name))
+}
+
+/// The text removing the statement from `start` to `end` (its `;`), or declaring `names` instead
+fn replace_declaration(
+    terms: &[Spanned<&str>],
+    start: usize,
+    end: usize,
+    names: &[String],
+) -> Edit {
+    let span = (terms[start].span.0, terms[end].span.1);
+    if names.is_empty() {
+        (span, String::new())
+    } else {
+        (span, array(names))
+    }
+}
+
+fn array(names: &[String]) -> String {
+    let names = names.iter().map(|x| format!("\"{x}\"")).collect::<Vec<_>>();
+    format!("private [{}];", names.join(", "))
+}
+
+/// Converts the `private [...]` of the scope of the terms `scope` to the keyword on the first
+/// assignment of each variable, when it is a statement of the scope not preceded by any usage.
+/// Returns the edits, empty when there is nothing to convert.
+pub fn to_keyword(terms: &[Spanned<&str>], scope: std::ops::Range<usize>) -> Vec<Edit> {
+    let statements = statements(terms, scope.clone());
+    let mut edits = vec![];
+    let mut converted = false;
+    for (n, start) in statements.iter().enumerate() {
+        let Some(names) = array_declaration(terms, *start, scope.end) else {
+            continue;
+        };
+        let end = statement_end(terms, *start, scope.end);
+        let mut kept = vec![];
+        for name in names {
+            let assignment = statements[n + 1..].iter().find_map(|x| {
+                let name_index = is_assignment(terms, *x, false)?;
+                terms[name_index]
+                    .inner
+                    .eq_ignore_ascii_case(&name)
+                    .then_some(name_index)
+            });
+            match assignment {
+                Some(i) if !uses(&terms[end + 1..i], &name) => {
+                    let at = terms[i].span.0;
+                    edits.push(((at, at), "private ".to_string()));
+                    converted = true;
+                }
+                _ => kept.push(name),
+            }
+        }
+        edits.push(replace_declaration(terms, *start, end, &kept));
+    }
+    if !converted {
+        return vec![];
+    }
+    edits
+}
+
+/// Converts the `private _a = ...` and the `private [...]` of a scope to a single `private [...]`
+/// at its entry. The variables used before their declaration, e.g. `private _a = _a + 1`, are left
+/// as is. Returns the edits removing the previous declarations and the one declaring all the
+/// variables at the entry (an insertion before the first statement, or the replacement of the
+/// `private [...]` already there), or `None` when there is nothing to convert.
+pub fn to_array(
+    terms: &[Spanned<&str>],
+    scope: std::ops::Range<usize>,
+) -> Option<(Vec<Edit>, Edit)> {
+    let statements = statements(terms, scope.clone());
+    let first = *statements.first()?;
+    let mut names: Vec<String> = vec![];
+    let mut edits = vec![];
+    let mut entry = None;
+    for start in &statements {
+        let end = statement_end(terms, *start, scope.end);
+        if let Some(declared) = array_declaration(terms, *start, scope.end) {
+            if *start == first {
+                entry = Some((terms[*start].span.0, terms[end].span.1));
+            } else {
+                edits.push(replace_declaration(terms, *start, end, &[]));
+            }
+            names.extend(declared);
+        } else if let Some(name) = is_assignment(terms, *start, true) {
+            let variable = terms[name].inner;
+            let is_used_before = uses(&terms[scope.start..*start], variable)
+                || uses(&terms[name + 1..end], variable);
+            if !is_used_before {
+                edits.push(((terms[*start].span.0, terms[name].span.0), String::new()));
+                names.push(variable.to_string());
+            }
+        }
+    }
+    if edits.is_empty() {
+        return None;
+    }
+    let mut unique: Vec<String> = vec![];
+    for name in names {
+        if !unique.iter().any(|x| x.eq_ignore_ascii_case(&name)) {
+            unique.push(name);
+        }
+    }
+    let at = terms[first].span.0;
+    Some((edits, (entry.unwrap_or((at, at)), array(&unique))))
+}
@@ -0,0 +1,131 @@
+//! Project configuration, `.sqf-analyzer.toml`, checked in at the root of a workspace folder.
+//!
+//! ```toml
+//! arma_version = "2.14"
+//! exclude = ["include/**", "tools/**"]
+//!
+//! [rules]
+//! undefined-variable = "error"
+//! unused-variable = "off"
+//!
+//! [addons]
+//! '\x\cba\addons' = "include/x/cba/addons"
+//!
+//! [naming]
+//! global_prefixes = ["MY_"]
+//!
+//! [style]
+//! private = "keyword"
+//! ```
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::Deserialize;
+
+use crate::private;
+use crate::rules::Level;
+
+pub const FILE_NAME: &str = ".sqf-analyzer.toml";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Naming {
+    /// prefixes that global variables assigned in the project must start with
+    pub global_prefixes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Style {
+    /// how private variables are declared: `"keyword"` (`private _a = 1;`) or `"array"`
+    /// (`private ["_a"];` at the entry of the scope). Both are offered when unset.
+    pub private: Option<private::Style>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct File {
+    arma_version: Option<String>,
+    exclude: Vec<String>,
+    rules: HashMap<String, String>,
+    addons: HashMap<String, String>,
+    naming: Naming,
+    style: Style,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Project {
+    /// the directory containing the configuration
+    pub root: PathBuf,
+    /// the version of Arma the project targets
+    pub arma_version: Option<String>,
+    /// files (relative to the root) that are not analyzed
+    pub exclude: GlobSet,
+    pub rules: HashMap<String, Level>,
+    /// location of addon prefixes, relative to the root
+    pub addons: HashMap<Arc<str>, PathBuf>,
+    pub naming: Naming,
+    pub style: Style,
+}
+
+impl Project {
+    /// Parses the content of a `.sqf-analyzer.toml` at `root`
+    pub fn parse(content: &str, root: PathBuf) -> Result<Self, String> {
+        let file: File = toml::from_str(content).map_err(|e| e.to_string())?;
+
+        let mut exclude = GlobSetBuilder::new();
+        for pattern in &file.exclude {
+            exclude.add(Glob::new(pattern).map_err(|e| e.to_string())?);
+        }
+        let exclude = exclude.build().map_err(|e| e.to_string())?;
+
+        let rules = file
+            .rules
+            .into_iter()
+            .map(|(code, level)| {
+                Level::from_setting(&level)
+                    .map(|level| (code, level))
+                    .ok_or_else(|| format!("unknown severity \"{level}\""))
+            })
+            .collect::<Result<_, _>>()?;
+
+        let addons = file
+            .addons
+            .into_iter()
+            .map(|(prefix, path)| (prefix.into(), root.join(path)))
+            .collect();
+
+        Ok(Self {
+            root,
+            arma_version: file.arma_version,
+            exclude,
+            rules,
+            addons,
+            naming: file.naming,
+            style: file.style,
+        })
+    }
+
+    /// Loads the configuration at the root of a workspace folder, if any
+    pub fn load(root: &Path) -> Option<Result<Self, String>> {
+        let content = std::fs::read_to_string(root.join(FILE_NAME)).ok()?;
+        Some(Self::parse(&content, root.to_path_buf()))
+    }
+
+    /// Whether the file at `path` is excluded from the analysis
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        path.strip_prefix(&self.root)
+            .map(|x| self.exclude.is_match(x))
+            .unwrap_or(false)
+    }
+}
+
+/// Returns the project the file at `path` belongs to, i.e. the one with the innermost root
+pub fn find<'a>(projects: &'a [Project], path: &Path) -> Option<&'a Project> {
+    projects
+        .iter()
+        .filter(|x| path.starts_with(&x.root))
+        .max_by_key(|x| x.root.components().count())
+}
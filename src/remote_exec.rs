@@ -0,0 +1,122 @@
+//! The arguments of `remoteExec` and `remoteExecCall`: `params remoteExec [functionName, targets, JIP]`
+use crate::config::Config;
+
+/// The argument of `remoteExec` being written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Argument {
+    /// the name of the function or command executed
+    Function,
+    Targets,
+    Jip,
+}
+
+/// The snippets completing the targets: a label, the snippet and its documentation
+pub const TARGETS: &[(&str, &str, &str)] = &[
+    ("0", "0", "Every machine, including the server"),
+    ("2", "2", "The server only"),
+    ("-2", "-2", "Every machine except the server"),
+    (
+        "[0, -2] select isDedicated",
+        "[0, -2] select isDedicated",
+        "Every machine running a player, i.e. except a dedicated server",
+    ),
+    (
+        "clientOwner",
+        "clientOwner",
+        "The machine executing the `remoteExec`",
+    ),
+    (
+        "object",
+        "${1:_unit}",
+        "The machine where the object is local",
+    ),
+    (
+        "side",
+        "${1:west}",
+        "The machines of the players of the side",
+    ),
+    (
+        "array",
+        "[${1:_target}]",
+        "Every machine of the targets of the array",
+    ),
+];
+
+/// The snippets completing the JIP argument: a label, the snippet and its documentation
+pub const JIP: &[(&str, &str, &str)] = &[
+    ("false", "false", "Not executed for players joining later"),
+    (
+        "true",
+        "true",
+        "Also executed for players joining later, with a unique JIP ID",
+    ),
+    (
+        "\"id\"",
+        "\"${1:TAG_jipId}\"",
+        "Also executed for players joining later, replacing the previous call with the same JIP ID",
+    ),
+    (
+        "object",
+        "${1:_vehicle}",
+        "Also executed for players joining later, as long as the object exists",
+    ),
+];
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// If the cursor, at the end of `before` in the same line, is in the arguments of a `remoteExec`,
+/// returns the argument. This is text-based since the document is often incomplete while it is
+/// being typed.
+pub fn argument_at(before: &str) -> Option<Argument> {
+    let lowercase = before.to_ascii_lowercase();
+    let start = lowercase
+        .match_indices("remoteexec")
+        .map(|(index, _)| index)
+        .filter(|&index| {
+            !before[..index]
+                .chars()
+                .next_back()
+                .map(is_word)
+                .unwrap_or(false)
+        })
+        .last()?;
+    let rest = &before[start + "remoteExec".len()..];
+    let rest = rest
+        .get(.."Call".len())
+        .filter(|x| x.eq_ignore_ascii_case("Call"))
+        .map(|_| &rest["Call".len()..])
+        .unwrap_or(rest);
+    let arguments = rest.trim_start().strip_prefix('[')?;
+
+    let mut elements = 0;
+    let mut depth = 0usize;
+    let mut quote = None;
+    for c in arguments.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[' | '{' | '(') => depth += 1,
+            (None, ']' | '}' | ')') => depth = depth.checked_sub(1)?,
+            (None, ',') if depth == 0 => elements += 1,
+            _ => {}
+        }
+    }
+    match (elements, quote) {
+        (0, Some(_)) => Some(Argument::Function),
+        (1, None) => Some(Argument::Targets),
+        (2, None) => Some(Argument::Jip),
+        _ => None,
+    }
+}
+
+/// Returns the commands allowed to be executed remotely by `CfgRemoteExec` of a config
+pub fn whitelisted_commands(config: &Config) -> Vec<String> {
+    config
+        .class("CfgRemoteExec")
+        .and_then(|x| x.class("Commands"))
+        .map(|x| x.classes().map(|x| x.name.inner.clone()).collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,206 @@
+//! Renaming of functions declared in `CfgFunctions`, and of their scripts
+use std::path::Path;
+
+use sqf::analyzer::{BINARY, NULLARY, UNARY};
+use sqf::error::Error;
+use sqf::preprocessor;
+use sqf::span::{Span, Spanned};
+use sqf::UncasedStr;
+
+use crate::{text_pos, tokens};
+
+/// The variables set by the engine, which cannot be renamed
+const MAGIC_VARIABLES: &[&str] = &[
+    "_this",
+    "_x",
+    "_y",
+    "_forEachIndex",
+    "_exception",
+    "_thisScript",
+    "_thisFSM",
+    "_thisEvent",
+    "_thisEventHandler",
+    "_thisArgs",
+    "_thisID",
+    "_thisList",
+    "_thisTrigger",
+    "_time",
+    "_fnc_scriptName",
+    "_fnc_scriptNameParent",
+];
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Returns an error message if `name` is defined by the engine (a command or a magic variable) and
+/// thus cannot be renamed
+pub fn check(name: &str) -> Result<(), String> {
+    let uncased = UncasedStr::new(name);
+    if BINARY.contains_key(uncased) || UNARY.contains_key(uncased) || NULLARY.contains_key(uncased)
+    {
+        Err(format!(
+            "\"{name}\" is a command of the engine, which cannot be renamed"
+        ))
+    } else if MAGIC_VARIABLES.iter().any(|x| x.eq_ignore_ascii_case(name)) {
+        Err(format!(
+            "\"{name}\" is set by the engine, which cannot be renamed"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the new class name of a function renamed from `old` (`{tag}_fnc_{class}`) to `new`,
+/// or an error message if `new` is not a valid name for it.
+/// The tag is defined by the config and thus cannot be changed by a rename.
+pub fn function_class(old: &str, new: &str) -> Result<String, String> {
+    let Some(index) = old.to_lowercase().find("_fnc_") else {
+        return Err(format!("\"{old}\" is not a function"));
+    };
+    let prefix = &old[..index + "_fnc_".len()];
+    let class = new
+        .get(..prefix.len())
+        .filter(|x| x.eq_ignore_ascii_case(prefix))
+        .map(|_| &new[prefix.len()..])
+        .ok_or_else(|| format!("The new name must start with \"{prefix}\""))?;
+    if class.is_empty() || !class.chars().all(is_word) {
+        return Err(format!("\"{class}\" is not a valid function name"));
+    }
+    Ok(class.to_string())
+}
+
+/// Returns the whole-word occurrences of `name` (case-insensitive) in `text`
+fn occurrences(text: &str, name: &str) -> Vec<usize> {
+    let lowercase = text.to_ascii_lowercase();
+    let name = name.to_ascii_lowercase();
+    lowercase
+        .match_indices(&name)
+        .map(|(index, _)| index)
+        .filter(|&index| {
+            let before = text[..index].chars().next_back();
+            let after = text[index + name.len()..].chars().next();
+            !before.map(is_word).unwrap_or(false) && !after.map(is_word).unwrap_or(false)
+        })
+        .collect()
+}
+
+/// The commands whose string argument may refer to a function, e.g. `remoteExec ["TAG_fnc_name"]`
+const STRING_COMMANDS: &[&str] = &[
+    "remoteExec",
+    "remoteExecCall",
+    "compile",
+    "compileFinal",
+    "execVM",
+];
+
+/// Where a function is referred to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceKind {
+    /// a usage as a variable, e.g. `call TAG_fnc_name`
+    Variable,
+    /// within a string, passed to the command if any (e.g. `remoteExec ["TAG_fnc_name"]`)
+    String(Option<&'static str>),
+}
+
+/// A reference to a function in a SQF file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reference {
+    pub span: Span,
+    pub kind: ReferenceKind,
+}
+
+/// The command whose argument is the string `terms[index]`: directly (e.g. `compile "..."`) or as
+/// the first element of an array (e.g. `remoteExec ["...", 0]`)
+fn string_command(terms: &[Spanned<&str>], index: usize) -> Option<&'static str> {
+    let is_command = |i: usize| {
+        STRING_COMMANDS
+            .iter()
+            .find(|x| x.eq_ignore_ascii_case(terms[i].inner))
+            .copied()
+    };
+    match index.checked_sub(1).map(|i| terms[i].inner) {
+        Some("[") => is_command(index.checked_sub(2)?),
+        Some(_) => is_command(index - 1),
+        None => None,
+    }
+}
+
+/// Returns all references to the function `name` in a SQF file: its usages as a variable and its
+/// occurrences within strings (e.g. `remoteExec ["TAG_fnc_name"]` or code in strings)
+pub fn function_references(text: &str, name: &str) -> Result<Vec<Reference>, Error> {
+    let ast = preprocessor::parse(text)?;
+    let terms = tokens::terms(&ast);
+    Ok(terms
+        .iter()
+        .enumerate()
+        .flat_map(|(index, term)| {
+            if tokens::is_string(term.inner) {
+                let kind = ReferenceKind::String(string_command(&terms, index));
+                occurrences(term.inner, name)
+                    .into_iter()
+                    .filter_map(|index| text_pos::byte_to_char(term.inner, index))
+                    .map(|index| {
+                        let start = term.span.0 + index;
+                        Reference {
+                            span: (start, start + name.chars().count()),
+                            kind,
+                        }
+                    })
+                    .collect()
+            } else if term.inner.eq_ignore_ascii_case(name) {
+                vec![Reference {
+                    span: term.span,
+                    kind: ReferenceKind::Variable,
+                }]
+            } else {
+                vec![]
+            }
+        })
+        .collect())
+}
+
+/// The path of a script as declared in a config (e.g. `\x\tag\addons\main\fnc_a.sqf` or
+/// `functions\fn_a.sqf`) resolving to `old`, rewritten for the script moved to `new`: the
+/// components matching the end of `old` are replaced by the path of `new` from the same directory.
+/// Returns `None` when the script is moved outside of that directory.
+pub fn moved_script(declared: &str, old: &Path, new: &Path) -> Option<String> {
+    let declared_parts = declared
+        .split(['\\', '/'])
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>();
+    let old_parts = old
+        .components()
+        .map(|x| x.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>();
+    let common = declared_parts
+        .iter()
+        .rev()
+        .zip(old_parts.iter().rev())
+        .take_while(|(declared, old)| declared.eq_ignore_ascii_case(old))
+        .count();
+    if common == 0 {
+        return None;
+    }
+    let moved = new.strip_prefix(old.ancestors().nth(common)?).ok()?;
+    let separator = if declared.contains('/') && !declared.contains('\\') {
+        "/"
+    } else {
+        "\\"
+    };
+    let parts = declared_parts[..declared_parts.len() - common]
+        .iter()
+        .map(|x| x.to_string())
+        .chain(
+            moved
+                .components()
+                .map(|x| x.as_os_str().to_string_lossy().to_string()),
+        )
+        .collect::<Vec<_>>();
+    let leading = if declared.starts_with(['\\', '/']) {
+        separator
+    } else {
+        ""
+    };
+    Some(format!("{leading}{}", parts.join(separator)))
+}
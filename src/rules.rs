@@ -0,0 +1,1075 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use globset::{Glob, GlobMatcher};
+use sqf::error::ErrorType;
+
+use crate::analyze::Stage;
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+/// A rule of the analyzer. Each published diagnostic has the code of the rule that produced it.
+#[derive(Debug)]
+pub struct Rule {
+    pub code: &'static str,
+    pub title: &'static str,
+    pub severity: DiagnosticSeverity,
+    /// Markdown explanation of the rule, with examples of the problem and the fix
+    pub explanation: &'static str,
+}
+
+pub const RULES: &[Rule] = &[
+    Rule {
+        code: "undefined-variable",
+        title: "Undefined variable",
+        severity: DiagnosticSeverity::WARNING,
+        explanation: r#"A variable is used but it was never assigned in the current scope, the mission or the addon.
+
+This is often a typo or a variable that is only assigned in a different script that the analyzer could not find.
+
+### Problem
+
+```sqf
+private _units = allUnits;
+hint str (count _unit);
+```
+
+### Fix
+
+```sqf
+private _units = allUnits;
+hint str (count _units);
+```
+
+Global variables that are assigned elsewhere (e.g. via `publicVariable` or in the editor) can be ignored by
+disabling `sqf-analyzer.server.variables`."#,
+    },
+    Rule {
+        code: "private-assigned-to-mission",
+        title: "Private variable assigned to mission namespace",
+        severity: DiagnosticSeverity::INFORMATION,
+        explanation: r#"A variable starting with an underscore is assigned without being declared `private`,
+which, outside of a function, assigns it to the mission namespace.
+
+### Problem
+
+```sqf
+_position = getPos player;
+```
+
+### Fix
+
+```sqf
+private _position = getPos player;
+```"#,
+    },
+    Rule {
+        code: "unused-variable",
+        title: "Unused variable",
+        severity: DiagnosticSeverity::INFORMATION,
+        explanation: r#"A variable is assigned but its value is never read.
+
+This is often a leftover of a refactor or a typo in a later usage of the variable.
+
+### Problem
+
+```sqf
+private _unit = _this select 0;
+private _group = group player;
+hint name _unit;
+```
+
+### Fix
+
+```sqf
+private _unit = _this select 0;
+hint name _unit;
+```"#,
+    },
+    Rule {
+        code: "preprocessor-error",
+        title: "Preprocessor error",
+        severity: DiagnosticSeverity::ERROR,
+        explanation: r#"A directive could not be preprocessed, e.g. an `#ifdef` without `#endif` or a malformed `#define`.
+
+The file is not analyzed further.
+
+### Problem
+
+```sqf
+#ifdef DEBUG
+diag_log "debug";
+```
+
+### Fix
+
+```sqf
+#ifdef DEBUG
+diag_log "debug";
+#endif
+```"#,
+    },
+    Rule {
+        code: "syntax-error",
+        title: "Syntax error",
+        severity: DiagnosticSeverity::ERROR,
+        explanation: r#"The code could not be parsed, e.g. because of a missing bracket or semicolon.
+
+### Problem
+
+```sqf
+if (alive player then {
+    hint "alive";
+};
+```
+
+### Fix
+
+```sqf
+if (alive player) then {
+    hint "alive";
+};
+```"#,
+    },
+    Rule {
+        code: "error",
+        title: "Error",
+        severity: DiagnosticSeverity::ERROR,
+        explanation: r#"The code could not be type-checked.
+
+The message of the diagnostic describes the problem. For example, adding a boolean to a number:
+
+### Problem
+
+```sqf
+params [["_a", true, [true]]];
+private _b = _a + 1;
+```
+
+### Fix
+
+```sqf
+params [["_a", 0, [0]]];
+private _b = _a + 1;
+```"#,
+    },
+    Rule {
+        code: "unknown-marker",
+        title: "Unknown marker",
+        severity: DiagnosticSeverity::WARNING,
+        explanation: r#"A marker is referenced by name but no marker with that name is placed in `mission.sqm` nor
+created with `createMarker` in the same file.
+
+This is often a typo in the (string) name of the marker.
+
+### Problem
+
+```sqf
+// mission.sqm contains a marker named "respawn_west"
+player setPos (getMarkerPos "respawn_wes");
+```
+
+### Fix
+
+```sqf
+player setPos (getMarkerPos "respawn_west");
+```"#,
+    },
+    Rule {
+        code: "variable-key-never-set",
+        title: "Variable key never set",
+        severity: DiagnosticSeverity::WARNING,
+        explanation: r#"A key is read with `getVariable` from a namespace but no `setVariable` with that key
+on the same namespace exists in the project.
+
+This is often a typo in the key. Keys set by other addons (e.g. CBA or ACE) are also reported, which is why
+this rule is only enabled via `sqf-analyzer.server.unmatched_variable_keys`.
+
+### Problem
+
+```sqf
+_unit setVariable ["my_isMedic", true];
+if (_unit getVariable ["my_isMedik", false]) then {};
+```
+
+### Fix
+
+```sqf
+_unit setVariable ["my_isMedic", true];
+if (_unit getVariable ["my_isMedic", false]) then {};
+```"#,
+    },
+    Rule {
+        code: "variable-key-never-read",
+        title: "Variable key never read",
+        severity: DiagnosticSeverity::INFORMATION,
+        explanation: r#"A key is set with `setVariable` on a namespace but no `getVariable` with that key
+on the same namespace exists in the project.
+
+This is either dead code or a typo in the key of the corresponding `getVariable`.
+
+### Problem
+
+```sqf
+missionNamespace setVariable ["my_counter", 0];
+```
+
+### Fix
+
+Remove the `setVariable`, or read the key where it is needed:
+
+```sqf
+missionNamespace setVariable ["my_counter", 0];
+hint str (missionNamespace getVariable ["my_counter", 0]);
+```"#,
+    },
+    Rule {
+        code: "event-never-raised",
+        title: "Event never raised",
+        severity: DiagnosticSeverity::WARNING,
+        explanation: r#"A handler is added to an event (via `addPublicVariableEventHandler` or
+`CBA_fnc_addEventHandler`) but the event is never raised in the project.
+
+This is often a typo in the name of the event. Enabled via `sqf-analyzer.server.unmatched_events`.
+
+### Problem
+
+```sqf
+["my_unitKilled", {hint "killed"}] call CBA_fnc_addEventHandler;
+["my_unitkiled", [_unit]] call CBA_fnc_globalEvent;
+```
+
+### Fix
+
+```sqf
+["my_unitKilled", {hint "killed"}] call CBA_fnc_addEventHandler;
+["my_unitKilled", [_unit]] call CBA_fnc_globalEvent;
+```"#,
+    },
+    Rule {
+        code: "event-never-handled",
+        title: "Event never handled",
+        severity: DiagnosticSeverity::INFORMATION,
+        explanation: r#"A CBA event is raised (e.g. via `CBA_fnc_serverEvent`) but no handler is added
+to it in the project.
+
+Either the event is handled by another addon, or the name of the event has a typo.
+Enabled via `sqf-analyzer.server.unmatched_events`.
+
+### Problem
+
+```sqf
+["my_spawn", [_group]] call CBA_fnc_serverEvent;
+```
+
+### Fix
+
+```sqf
+["my_spawn", {params ["_group"]}] call CBA_fnc_addEventHandler;
+["my_spawn", [_group]] call CBA_fnc_serverEvent;
+```"#,
+    },
+    Rule {
+        code: "analysis-truncated",
+        title: "Analysis truncated",
+        severity: DiagnosticSeverity::INFORMATION,
+        explanation: r#"The analysis of the file took longer than `sqf-analyzer.server.analysis_timeout_ms` and was
+abandoned, so that the server keeps responding. Only the lints computed by the server itself are reported,
+and the file keeps the semantic highlighting and completions of its last complete analysis.
+
+This happens with generated files (e.g. exported compositions) or deeply nested macros.
+
+### Fix
+
+Increase `sqf-analyzer.server.analysis_timeout_ms`, or exclude the file from the analysis via `exclude`
+of the project's `.sqf-analyzer.toml`."#,
+    },
+    Rule {
+        code: "file-too-large",
+        title: "File too large",
+        severity: DiagnosticSeverity::INFORMATION,
+        explanation: r#"The file is larger than `sqf-analyzer.server.max_file_size_kb` and is not analyzed, so that
+large generated files (e.g. exported compositions) do not freeze the editor.
+
+### Fix
+
+Increase `sqf-analyzer.server.max_file_size_kb` (`0` disables the limit), or exclude the file from the
+analysis via `exclude` of the project's `.sqf-analyzer.toml` to silence this."#,
+    },
+    Rule {
+        code: "binarized-file",
+        title: "Binarized file",
+        severity: DiagnosticSeverity::INFORMATION,
+        explanation: r#"The file is binarized (e.g. `config.bin` or a binarized `mission.sqm`, as found in packed
+addons and missions) and cannot be analyzed. The functions it declares and the objects it places are
+unknown to the analysis.
+
+### Fix
+
+Point the analyzer at the sources of the addon or mission, or save the mission unbinarized in the editor
+(uncheck "Binarize the Scenario File" in the attributes of the scenario)."#,
+    },
+    Rule {
+        code: "inactive-region",
+        title: "Inactive region",
+        severity: DiagnosticSeverity::HINT,
+        explanation: r#"The branch of an `#ifdef` or `#ifndef` is not compiled with the macros defined in the file and in
+`sqf-analyzer.server.defines`. Editors show it faded.
+
+```sqf
+#ifdef DEBUG_MODE_FULL
+diag_log "not compiled unless DEBUG_MODE_FULL is defined";
+#endif
+```
+
+Conditions on macros that may be defined by an include are not evaluated. Enable
+`sqf-analyzer.server.analyze_inactive_branches` to analyze the inactive branches as well."#,
+    },
+    Rule {
+        code: "undefined-macro",
+        title: "Undefined macro",
+        severity: DiagnosticSeverity::HINT,
+        explanation: r#"A macro is referenced by `#if`, `#ifdef` or `#ifndef` but is never defined, neither in the file,
+nor in the files it includes, nor in `sqf-analyzer.server.defines`.
+
+This is often a typo, or a flag meant to be defined when building (e.g. `DEBUG_MODE_FULL`).
+
+### Problem
+
+```sqf
+#define DEBUG_MODE
+#ifdef DEBUG_MDOE
+diag_log "debug";
+#endif
+```
+
+### Fix
+
+```sqf
+#define DEBUG_MODE
+#ifdef DEBUG_MODE
+diag_log "debug";
+#endif
+```"#,
+    },
+    Rule {
+        code: "macro-redefined",
+        title: "Macro redefined",
+        severity: DiagnosticSeverity::WARNING,
+        explanation: r#"A macro is defined again with a different body (or arguments), in the same file or after being
+defined by an include, without an `#undef` in between. The new definition silently replaces the previous one.
+
+### Problem
+
+```sqf
+#include "script_component.hpp" // defines MAX_UNITS as 12
+#define MAX_UNITS 24
+```
+
+### Fix
+
+```sqf
+#include "script_component.hpp"
+#undef MAX_UNITS
+#define MAX_UNITS 24
+```"#,
+    },
+    Rule {
+        code: "include-cycle",
+        title: "Circular include",
+        severity: DiagnosticSeverity::ERROR,
+        explanation: r#"A file includes itself, directly or through other files, e.g. `a.hpp` includes `b.hpp`, which
+includes `a.hpp`. The preprocessor cannot expand such includes, so the file is not analyzed.
+
+### Problem
+
+```sqf
+// a.hpp
+#include "b.hpp"
+// b.hpp
+#include "a.hpp"
+```
+
+### Fix
+
+Guard the headers so that they are only expanded once, or remove one of the includes:
+
+```sqf
+// b.hpp
+#ifndef B_HPP
+#define B_HPP
+// ...
+#endif
+```"#,
+    },
+    Rule {
+        code: "unused-function",
+        title: "Unused function",
+        severity: DiagnosticSeverity::HINT,
+        explanation: r#"A function declared in `CfgFunctions` is never referred to in the project: neither called,
+nor named in a string (e.g. `remoteExec ["TAG_fnc_name"]`), nor called by the engine at initialization
+(`preInit`, `postInit` or `preStart`).
+
+Functions of addons may be used by other addons or missions, which are not part of the project.
+
+### Problem
+
+```cpp
+class CfgFunctions {
+    class TAG {
+        class common {
+            class leftover {};
+        };
+    };
+};
+```
+
+### Fix
+
+Remove its declaration and its script, or call it where intended."#,
+    },
+    Rule {
+        code: "legacy-sqs",
+        title: "Legacy SQS script",
+        severity: DiagnosticSeverity::INFORMATION,
+        explanation: r#"A script in the legacy SQS syntax is executed with `exec`. SQS scripts are not analyzed,
+run line by line and have been superseded by SQF.
+
+The quick fix creates a SQF skeleton converted from the script (comments, delays, conditions) and
+executes it with `execVM` instead; the statements without equivalent (labels and `goto`) are marked
+`TODO`.
+
+### Problem
+
+```sqf
+[player] exec "scripts\intro.sqs";
+```
+
+### Fix
+
+```sqf
+[player] execVM "scripts\intro.sqf";
+```"#,
+    },
+    Rule {
+        code: "function-attribute",
+        title: "Invalid function attribute",
+        severity: DiagnosticSeverity::WARNING,
+        explanation: r#"A function class of `CfgFunctions` has an attribute the game does not know, or a value the
+attribute does not accept. The game silently ignores both, so the function is e.g. never run at
+`preInit`.
+
+### Problem
+
+```cpp
+class CfgFunctions {
+    class TAG {
+        class Init {
+            class setup { preInit = "true"; extension = ".sqf"; };
+        };
+    };
+};
+```
+
+### Fix
+
+```cpp
+class CfgFunctions {
+    class TAG {
+        class Init {
+            class setup { preInit = 1; ext = ".sqf"; };
+        };
+    };
+};
+```"#,
+    },
+    Rule {
+        code: "unbalanced-bracket",
+        title: "Unbalanced bracket",
+        severity: DiagnosticSeverity::ERROR,
+        explanation: r#"A `{`, `(` or `[` is never closed, or a `}`, `)` or `]` closes nothing. The diagnostic is
+reported where the bracket was expected to be closed, and its related information points at the
+bracket left open. The rest of the file is analyzed as if the offending bracket was not there.
+
+### Problem
+
+```sqf
+if (alive player then {
+    hint "alive";
+};
+```
+
+### Fix
+
+```sqf
+if (alive player) then {
+    hint "alive";
+};
+```"#,
+    },
+    Rule {
+        code: "dead-store",
+        title: "Dead store",
+        severity: DiagnosticSeverity::INFORMATION,
+        explanation: r#"A local variable is assigned a value that is overwritten in the same scope before being
+read, so the first assignment has no effect. This is often a bug from copying and pasting, where the
+second assignment was meant for another variable.
+
+The statements in between must not read the variable at all, even conditionally or through `call`.
+
+### Problem
+
+```sqf
+private _units = allUnits select {side _x == west};
+private _units = allUnits select {side _x == east};
+[_units] call TAG_fnc_attack;
+```
+
+### Fix
+
+```sqf
+private _blufor = allUnits select {side _x == west};
+private _opfor = allUnits select {side _x == east};
+[_blufor, _opfor] call TAG_fnc_attack;
+```"#,
+    },
+    Rule {
+        code: "case-insensitive-comparison",
+        title: "Case-insensitive comparison of a converted string",
+        severity: DiagnosticSeverity::INFORMATION,
+        explanation: r#"A string converted with `toLower` or `toUpper` is compared with `==` or `!=`, which ignore the
+case of strings. Either the conversion is useless, or the comparison was meant to be exact, which
+requires `isEqualTo` or `isNotEqualTo`.
+
+The quick fix replaces the operator by its exact equivalent.
+
+### Problem
+
+```sqf
+if (toUpper _code == "ABC") then { ... };
+```
+
+### Fix
+
+```sqf
+if (toUpper _code isEqualTo "ABC") then { ... };
+```
+
+or, when the case does not matter:
+
+```sqf
+if (_code == "abc") then { ... };
+```"#,
+    },
+    Rule {
+        code: "index-out-of-range",
+        title: "Index out of range",
+        severity: DiagnosticSeverity::WARNING,
+        explanation: r#"A literal index is out of the range of an array whose size is known: a literal array, or a
+local variable assigned a literal array once and only read with `select`, `param`, `#` or `count`
+afterwards. `select` returns `nil` or fails, and `param` always returns its default value. This
+is usually an off-by-one error, since indices start at 0.
+
+### Problem
+
+```sqf
+private _position = [100, 200, 0];
+private _height = _position select 3;
+```
+
+### Fix
+
+```sqf
+private _position = [100, 200, 0];
+private _height = _position select 2;
+```"#,
+    },
+    Rule {
+        code: "type-mismatch",
+        title: "Type mismatch",
+        severity: DiagnosticSeverity::WARNING,
+        explanation: r#"A binary command or operator is applied to operands whose types, inferred by the analysis,
+match none of its signatures, e.g. a string added to a number, or a group passed where an object is
+expected. The command fails or returns `nil` in the game. Only operands of known types are checked.
+
+### Problem
+
+```sqf
+private _count = 3;
+hint ("units: " + _count);
+```
+
+### Fix
+
+```sqf
+private _count = 3;
+hint ("units: " + str _count);
+```"#,
+    },
+    Rule {
+        code: "duplicate-case",
+        title: "Duplicated case",
+        severity: DiagnosticSeverity::WARNING,
+        explanation: r#"A `case` of a switch has the same literal value as a previous one of the same switch. Only
+the first case with a value is ever reached, so the duplicate is dead code, or a value was mistyped.
+
+The quick fix removes the duplicated case.
+
+### Problem
+
+```sqf
+switch (_side) do {
+    case "west": { _color = "blue" };
+    case "west": { _color = "red" };
+};
+```
+
+### Fix
+
+```sqf
+switch (_side) do {
+    case "west": { _color = "blue" };
+    case "east": { _color = "red" };
+};
+```"#,
+    },
+    Rule {
+        code: "missing-switch-default",
+        title: "Switch without default",
+        severity: DiagnosticSeverity::INFORMATION,
+        explanation: r#"A switch has no `default` branch, so values matching no case are silently ignored. This rule is
+only checked when the setting `sqf-analyzer.server.switch_require_default` is enabled.
+
+The quick fix adds an empty `default` branch.
+
+### Problem
+
+```sqf
+switch (_side) do {
+    case "west": { _color = "blue" };
+};
+```
+
+### Fix
+
+```sqf
+switch (_side) do {
+    case "west": { _color = "blue" };
+    default { _color = "white" };
+};
+```"#,
+    },
+    Rule {
+        code: "infinite-loop",
+        title: "Infinite loop",
+        severity: DiagnosticSeverity::WARNING,
+        explanation: r#"A `while {true}` loop has a body that never suspends (`sleep`, `uiSleep`, `waitUntil`) nor
+exits (`breakOut`, `breakTo`, `exitWith`, `throw`). Run unscheduled, e.g. from an event handler or
+`call`ed from one, it freezes the game; scheduled, it consumes the whole time of the scheduler.
+
+### Problem
+
+```sqf
+while {true} do {
+    hintSilent str time;
+};
+```
+
+### Fix
+
+```sqf
+while {true} do {
+    hintSilent str time;
+    sleep 1;
+};
+```"#,
+    },
+    Rule {
+        code: "unconditional-recursion",
+        title: "Unconditional recursion",
+        severity: DiagnosticSeverity::WARNING,
+        explanation: r#"The script of a function calls the function itself, and has no conditional code (`if`,
+`exitWith`, `switch`, a loop, ...) that could stop the recursion. The function never returns and the
+game freezes or fails with a stack overflow.
+
+### Problem
+
+```sqf
+// TAG_fnc_countdown
+params ["_n"];
+hint str _n;
+[_n - 1] call TAG_fnc_countdown;
+```
+
+### Fix
+
+```sqf
+// TAG_fnc_countdown
+params ["_n"];
+if (_n <= 0) exitWith {};
+hint str _n;
+[_n - 1] call TAG_fnc_countdown;
+```"#,
+    },
+    Rule {
+        code: "this-select",
+        title: "Parameters read with _this select",
+        severity: DiagnosticSeverity::INFORMATION,
+        explanation: r#"The first statements of a script read its parameters one by one with `_this select N`.
+A single `params` declares them as private variables at once, and can later validate their types
+and give them default values. This rule is opt-in: set its level to enable it.
+
+The quick fix replaces the statements by the equivalent `params`.
+
+### Problem
+
+```sqf
+private _unit = _this select 0;
+private _target = _this select 1;
+```
+
+### Fix
+
+```sqf
+params ["_unit", "_target"];
+```"#,
+    },
+    Rule {
+        code: "dependency-cycle",
+        title: "Undeclared cycle of calls between addons",
+        severity: DiagnosticSeverity::WARNING,
+        explanation: r#"The functions of this addon call the functions of another addon, which call the
+functions of this addon back, but this addon does not list the other one in its `requiredAddons[]`.
+The order in which the game loads the two addons is then undefined, and the components are hard to
+separate. Move the shared functions to a common addon, or declare the dependency.
+
+### Problem
+
+```cpp
+// addons/a/config.cpp, whose A_fnc_a calls B_fnc_b
+class CfgPatches {
+    class a { requiredAddons[] = {"main"}; };
+};
+// addons/b/config.cpp, whose B_fnc_b calls A_fnc_a
+class CfgPatches {
+    class b { requiredAddons[] = {"main"}; };
+};
+```
+
+### Fix
+
+```cpp
+// addons/common/config.cpp, declaring the functions both call
+class CfgPatches {
+    class common { requiredAddons[] = {"main"}; };
+};
+```"#,
+    },
+    Rule {
+        code: "missing-required-addon",
+        title: "Addon used without being required",
+        severity: DiagnosticSeverity::WARNING,
+        explanation: r#"The scripts of this addon use another addon, by including one of its files,
+referring to one of its components with a macro of CBA (`EFUNC`, `EGVAR`, ...) or calling one of its
+functions, but its `CfgPatches` does not list it in `requiredAddons[]`. The game may then load this
+addon before the one it uses.
+
+### Problem
+
+```cpp
+// addons/main/config.cpp, whose scripts use EFUNC(common,log)
+class CfgPatches {
+    class my_main { requiredAddons[] = {"cba_main"}; };
+};
+```
+
+### Fix
+
+```cpp
+class CfgPatches {
+    class my_main { requiredAddons[] = {"cba_main", "my_common"}; };
+};
+```"#,
+    },
+    Rule {
+        code: "unused-required-addon",
+        title: "Required addon of the project never used",
+        severity: DiagnosticSeverity::INFORMATION,
+        explanation: r#"An addon of the project is listed in `requiredAddons[]`, but no script of this
+addon includes its files, refers to it with a macro of CBA nor calls its functions. The requirement
+may still be needed by the config, e.g. to inherit from its classes, so this rule is opt-in: set its
+level to enable it.
+
+### Problem
+
+```cpp
+class CfgPatches {
+    class my_main { requiredAddons[] = {"cba_main", "my_unused"}; };
+};
+```
+
+### Fix
+
+```cpp
+class CfgPatches {
+    class my_main { requiredAddons[] = {"cba_main"}; };
+};
+```"#,
+    },
+    Rule {
+        code: "unused-macro",
+        title: "Macro never used",
+        severity: DiagnosticSeverity::HINT,
+        explanation: r#"A macro defined with `#define` is never used by the files of its addon nor by the files
+they include: neither in their code, nor in the body of another macro, nor by `#ifdef`. Macros that
+may be formed by token-pasting (`##`) are considered used.
+
+Checking the whole addon on every change is costly, so this rule is opt-in: set its level to enable
+it.
+
+### Problem
+
+```sqf
+#define MAX_UNITS 10
+#define OLD_DELAY 5 // no longer used
+```
+
+### Fix
+
+```sqf
+#define MAX_UNITS 10
+```"#,
+    },
+    Rule {
+        code: "naming-convention",
+        title: "Naming convention",
+        severity: DiagnosticSeverity::INFORMATION,
+        explanation: r#"A global variable is assigned with a name that does not start with any of the prefixes of
+`naming.global_prefixes` of the project's `.sqf-analyzer.toml`.
+
+Prefixing globals avoids collisions with variables of other missions and addons.
+
+### Problem
+
+```sqf
+// .sqf-analyzer.toml: naming.global_prefixes = ["MY_"]
+isReady = true;
+```
+
+### Fix
+
+```sqf
+MY_isReady = true;
+```"#,
+    },
+];
+
+/// The level a rule is configured to, e.g. via the `rules` setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Off,
+    Hint,
+    Information,
+    Warning,
+    Error,
+}
+
+impl Level {
+    pub fn from_setting(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "off" | "none" => Some(Level::Off),
+            "hint" => Some(Level::Hint),
+            "info" | "information" => Some(Level::Information),
+            "warning" | "warn" => Some(Level::Warning),
+            "error" => Some(Level::Error),
+            _ => None,
+        }
+    }
+
+    /// The severity of diagnostics of this level, `None` when they are not published
+    pub fn severity(self) -> Option<DiagnosticSeverity> {
+        match self {
+            Level::Off => None,
+            Level::Hint => Some(DiagnosticSeverity::HINT),
+            Level::Information => Some(DiagnosticSeverity::INFORMATION),
+            Level::Warning => Some(DiagnosticSeverity::WARNING),
+            Level::Error => Some(DiagnosticSeverity::ERROR),
+        }
+    }
+}
+
+/// The warnings reported as errors, e.g. to match the strictness of a CI gate
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum WarningsAsErrors {
+    #[default]
+    None,
+    All,
+    /// the warnings of these rules
+    Codes(HashSet<String>),
+}
+
+impl WarningsAsErrors {
+    /// From the setting, either a boolean or a list of codes of rules
+    pub fn from_setting(value: &serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Bool(true) => WarningsAsErrors::All,
+            serde_json::Value::Array(codes) => WarningsAsErrors::Codes(
+                codes
+                    .iter()
+                    .filter_map(|x| x.as_str().map(|x| x.to_string()))
+                    .collect(),
+            ),
+            _ => WarningsAsErrors::None,
+        }
+    }
+
+    /// The severity of a diagnostic of the rule `code` (`None` when it has no rule) of `severity`
+    pub fn apply(&self, code: Option<&str>, severity: DiagnosticSeverity) -> DiagnosticSeverity {
+        if severity != DiagnosticSeverity::WARNING {
+            return severity;
+        }
+        let is_error = match self {
+            WarningsAsErrors::None => false,
+            WarningsAsErrors::All => true,
+            WarningsAsErrors::Codes(codes) => code.is_some_and(|x| codes.contains(x)),
+        };
+        if is_error {
+            DiagnosticSeverity::ERROR
+        } else {
+            severity
+        }
+    }
+}
+
+/// The highest severity of the diagnostics of the files matching a glob, e.g. to keep the ones of
+/// vendored code visible without drowning the ones of the project
+#[derive(Debug, Clone)]
+pub struct Override {
+    glob: GlobMatcher,
+    level: Level,
+}
+
+/// The overrides of the setting, the last matching a file applying to it
+#[derive(Debug, Clone, Default)]
+pub struct Overrides(Vec<Override>);
+
+impl Overrides {
+    /// From the setting, a list of `{"path": glob, "severity": level}`. Invalid entries are ignored.
+    pub fn from_setting(value: &serde_json::Value) -> Self {
+        let overrides = value
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|x| {
+                let glob = Glob::new(x.get("path")?.as_str()?).ok()?.compile_matcher();
+                let level = Level::from_setting(x.get("severity")?.as_str()?)?;
+                Some(Override { glob, level })
+            })
+            .collect();
+        Self(overrides)
+    }
+
+    /// The level of the override of the file at `path`, relative to its workspace folder, matched by
+    /// the path or one of its directories
+    pub fn level(&self, path: &Path) -> Option<Level> {
+        self.0
+            .iter()
+            .rev()
+            .find(|x| path.ancestors().any(|path| x.glob.is_match(path)))
+            .map(|x| x.level)
+    }
+
+    /// The severity of a diagnostic of `severity` in the file at `path` (see [`Self::level`]),
+    /// `None` when it is not published
+    pub fn apply(&self, path: &Path, severity: DiagnosticSeverity) -> Option<DiagnosticSeverity> {
+        match self.level(path) {
+            Some(level) => Some(severity.max(level.severity()?)),
+            None => Some(severity),
+        }
+    }
+}
+
+/// Returns the code of the rule that produced an error in `stage`
+pub fn code(stage: Stage, type_: &ErrorType) -> &'static str {
+    match (stage, type_) {
+        (Stage::Preprocessor, _) => "preprocessor-error",
+        (Stage::Parser, _) => "syntax-error",
+        (_, ErrorType::UndefinedVariable(_)) => "undefined-variable",
+        (_, ErrorType::PrivateAssignedToMission) => "private-assigned-to-mission",
+        (_, ErrorType::UnusedVariable) => "unused-variable",
+        _ => "error",
+    }
+}
+
+pub fn get(code: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|rule| rule.code == code)
+}
+
+/// A Markdown report of `problems` (the file and rule of each) grouped by rule, the most frequent
+/// first, with the `top` files with the most problems of each rule
+pub fn report(problems: &[(String, Option<String>)], top: usize) -> String {
+    // the number of problems of each file, by rule
+    let mut rules = BTreeMap::<&str, BTreeMap<&str, usize>>::new();
+    for (file, code) in problems {
+        let code = code.as_deref().unwrap_or("error");
+        *rules.entry(code).or_default().entry(file).or_default() += 1;
+    }
+    let mut rules = rules
+        .into_iter()
+        .map(|(code, files)| {
+            let mut files = files.into_iter().collect::<Vec<_>>();
+            files.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+            let count = files.iter().map(|x| x.1).sum::<usize>();
+            (code, count, files)
+        })
+        .collect::<Vec<_>>();
+    rules.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    let title = |code: &str| get(code).map_or(code.to_string(), |x| x.title.to_string());
+
+    let file_count = problems.iter().map(|x| &x.0).collect::<HashSet<_>>().len();
+    let mut lines = vec![
+        "# Problems by rule".to_string(),
+        String::new(),
+        format!(
+            "{} problems of {} rules in {file_count} files.",
+            problems.len(),
+            rules.len()
+        ),
+        String::new(),
+    ];
+    if rules.is_empty() {
+        return lines.join("\n");
+    }
+    lines.push("| Rule | Problems | Files |".to_string());
+    lines.push("|------|----------|-------|".to_string());
+    lines.extend(rules.iter().map(|(code, count, files)| {
+        format!("| {} (`{code}`) | {count} | {} |", title(code), files.len())
+    }));
+    for (code, count, files) in &rules {
+        lines.push(String::new());
+        lines.push(format!("## {} (`{code}`)", title(code)));
+        lines.push(String::new());
+        lines.push(format!("{count} problems in {} files.", files.len()));
+        lines.push(String::new());
+        lines.extend(
+            files
+                .iter()
+                .take(top)
+                .map(|(file, count)| format!("* `{file}`: {count}")),
+        );
+        if files.len() > top {
+            lines.push(format!("* and {} more files", files.len() - top));
+        }
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// Returns the Markdown explanation of a rule
+pub fn explain(code: &str) -> Option<String> {
+    get(code).map(|rule| {
+        format!(
+            "# {} (`{}`)\n\n{}\n",
+            rule.title, rule.code, rule.explanation
+        )
+    })
+}
@@ -1,4 +1,4 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use sqf::{
     analyzer::{MissionNamespace, BINARY, NULLARY, UNARY},
@@ -8,7 +8,9 @@ use sqf::{
 };
 use tower_lsp::lsp_types::SemanticTokenType;
 
-#[derive(Debug)]
+use crate::{params, tokens};
+
+#[derive(Debug, Clone)]
 pub struct SemanticTokenLocation {
     pub start: usize,
     pub length: usize,
@@ -50,12 +52,14 @@ fn to_semantic_tokens<'a>(
     spanned.inner.split("\n").map(move |token| {
         // first record starts at the start of the span. All others start from the beginning, since they represent
         // new lines
+        let length = token.chars().count();
         let r = SemanticTokenLocation {
             start,
-            length: token.len(),
+            // the `\r` of `\r\n` is not highlighted
+            length: token.strip_suffix('\r').unwrap_or(token).chars().count(),
             token_type: MAP[&token_type],
         };
-        start += token.len() + 1; // +1 due to the \n
+        start += length + 1; // +1 due to the \n
         r
     })
 }
@@ -156,7 +160,50 @@ pub fn semantic_tokens(
     for ast in tokens {
         recurse(ast, &mut container, mission);
     }
+
+    let terms = tokens::terms(tokens);
+
+    // code in strings is highlighted as code, when its offsets are those of the file, i.e. without
+    // escaped quotes
+    for string in tokens::code_strings(&terms) {
+        let code = &string.inner[1..string.inner.len() - 1];
+        if code.contains(&string.inner[..1]) {
+            continue;
+        }
+        let Ok(ast) = sqf::preprocessor::parse(code) else {
+            continue;
+        };
+        let (start, end) = string.span;
+        container.retain(|x| x.start < start || x.start >= end);
+        container.extend(semantic_tokens(&ast, mission).into_iter().map(|x| {
+            SemanticTokenLocation {
+                start: x.start + start + 1,
+                ..x
+            }
+        }));
+    }
     container.sort_by_key(|x| x.start);
 
+    // variables introduced by `params`, including their declaration, are highlighted as parameters
+    let declarations = params::names(&terms);
+    let names = declarations
+        .iter()
+        .map(|x| x.inner.to_lowercase())
+        .collect::<HashSet<_>>();
+    let parameters = terms
+        .iter()
+        .filter(|x| !tokens::is_string(x.inner) && names.contains(&x.inner.to_lowercase()))
+        .map(|x| x.span.0)
+        .chain(declarations.iter().map(|x| x.span.0))
+        .collect::<HashSet<_>>();
+    for token in &mut container {
+        if (token.token_type == MAP[&SemanticTokenType::VARIABLE]
+            || token.token_type == MAP[&SemanticTokenType::STRING])
+            && parameters.contains(&token.start)
+        {
+            token.token_type = MAP[&SemanticTokenType::PARAMETER];
+        }
+    }
+
     container
 }
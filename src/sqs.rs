@@ -0,0 +1,100 @@
+//! Legacy SQS scripts, executed with `exec`: their usages and the skeleton of an equivalent SQF script
+use std::path::{Path, PathBuf};
+
+use sqf::span::{Span, Spanned};
+
+use crate::lint::Lint;
+use crate::tokens;
+
+/// A script executed with `[] exec "script.sqs"`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Usage {
+    /// the span of `exec`
+    pub exec: Span,
+    /// the path, unquoted, and the span of the string
+    pub path: Spanned<String>,
+}
+
+/// Returns the SQS scripts executed with `exec` in the terms of a file
+pub fn usages(terms: &[Spanned<&str>]) -> Vec<Usage> {
+    terms
+        .windows(2)
+        .filter(|x| x[0].inner.eq_ignore_ascii_case("exec"))
+        .filter_map(|x| {
+            let path = tokens::unquote(x[1].inner)?;
+            path.to_ascii_lowercase().ends_with(".sqs").then(|| Usage {
+                exec: x[0].span,
+                path: Spanned::new(path, x[1].span),
+            })
+        })
+        .collect()
+}
+
+/// Lints the usages of SQS scripts, which are not analyzed
+pub fn lint(terms: &[Spanned<&str>]) -> Vec<Lint> {
+    usages(terms)
+        .into_iter()
+        .map(|usage| Lint {
+            code: "legacy-sqs",
+            span: usage.path.span,
+            message: format!(
+                "\"{}\" is a legacy SQS script, which is not analyzed; consider converting it to SQF and using execVM",
+                usage.path.inner
+            ),
+        })
+        .collect()
+}
+
+/// Resolves the path of a script executed by the file at `path`: relative to the root of its
+/// mission (the directory of its `mission.sqm` or `description.ext`), or to its directory
+pub fn resolve(script: &str, path: &Path) -> PathBuf {
+    let directory = path.parent().unwrap_or(path);
+    let root = directory
+        .ancestors()
+        .find(|x| x.join("mission.sqm").exists() || x.join("description.ext").exists())
+        .unwrap_or(directory);
+    script
+        .trim_start_matches('\\')
+        .split(['\\', '/'])
+        .fold(root.to_path_buf(), |path, x| path.join(x))
+}
+
+/// Converts a line of SQS to SQF, on a best effort basis
+fn convert_line(line: &str) -> String {
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let line = line.trim();
+    let converted = if line.is_empty() {
+        String::new()
+    } else if let Some(comment) = line.strip_prefix(';') {
+        format!("//{comment}")
+    } else if let Some(label) = line.strip_prefix('#') {
+        format!("// TODO: label \"{label}\": SQF has no goto, use a loop")
+    } else if let Some(delay) = line.strip_prefix('~') {
+        format!("sleep {};", delay.trim())
+    } else if let Some(condition) = line.strip_prefix('@') {
+        format!("waitUntil {{ {} }};", condition.trim())
+    } else if let Some((condition, statement)) =
+        line.strip_prefix('?').and_then(|x| x.split_once(':'))
+    {
+        format!(
+            "if ({}) then {{ {} }};",
+            condition.trim(),
+            convert_line(statement).trim_end_matches(';')
+        )
+    } else if line.eq_ignore_ascii_case("exit") {
+        "// TODO: exit: use exitWith".to_string()
+    } else if line.to_ascii_lowercase().starts_with("goto ") {
+        format!("// TODO: {line}: SQF has no goto, use a loop")
+    } else if line.ends_with(';') {
+        line.to_string()
+    } else {
+        format!("{line};")
+    };
+    format!("{indent}{converted}")
+}
+
+/// The skeleton of a SQF script equivalent to the SQS script `sqs` at `name`, to be completed by hand
+pub fn skeleton(name: &str, sqs: &str) -> String {
+    let body = sqs.lines().map(convert_line).collect::<Vec<_>>().join("\n");
+    format!("// converted from \"{name}\"; review the statements marked TODO\n\n{body}\n")
+}
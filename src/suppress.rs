@@ -0,0 +1,130 @@
+//! The suppression of diagnostics, by comments in a file or by the baseline of a workspace folder.
+//!
+//! ```sqf
+//! // sqf-analyzer-disable unused-variable
+//! // sqf-analyzer-disable-next-line undefined-variable, dead-store
+//! hint str _a;
+//! ```
+//!
+//! The comments list the rules they suppress, all when none is listed. The baseline,
+//! `.sqf-analyzer-baseline.json`, lists the problems accepted in the project (e.g. when adopting the
+//! analyzer on a large codebase) by file, rule and message, so that it survives edits moving them.
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// The comment suppressing rules on the line after it
+pub const NEXT_LINE: &str = "sqf-analyzer-disable-next-line";
+/// The comment suppressing rules in the whole file
+pub const FILE: &str = "sqf-analyzer-disable";
+pub const BASELINE_FILE_NAME: &str = ".sqf-analyzer-baseline.json";
+
+/// The rules suppressed by the comments of a file, all when empty
+#[derive(Debug, Clone, Default)]
+pub struct Comments {
+    file: Option<Vec<String>>,
+    /// by (zero-based) line
+    lines: HashMap<usize, Vec<String>>,
+}
+
+fn codes(rest: &str) -> Vec<String> {
+    rest.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|x| !x.is_empty())
+        .map(|x| x.to_string())
+        .collect()
+}
+
+fn suppresses(codes: &[String], code: &str) -> bool {
+    codes.is_empty() || codes.iter().any(|x| x == code)
+}
+
+impl Comments {
+    /// Parses the suppression comments of `text`, each on its own line
+    pub fn parse(text: &str) -> Self {
+        let mut comments = Self::default();
+        for (line, content) in text.lines().enumerate() {
+            let Some(comment) = content.trim_start().strip_prefix("//") else {
+                continue;
+            };
+            let comment = comment.trim();
+            // `FILE` is a prefix of `NEXT_LINE`
+            if let Some(rest) = comment.strip_prefix(NEXT_LINE) {
+                comments
+                    .lines
+                    .entry(line + 1)
+                    .or_default()
+                    .extend(codes(rest));
+            } else if let Some(rest) = comment.strip_prefix(FILE) {
+                let rest = codes(rest);
+                match &mut comments.file {
+                    Some(codes) if !codes.is_empty() && !rest.is_empty() => codes.extend(rest),
+                    Some(codes) => codes.clear(),
+                    None => comments.file = Some(rest),
+                }
+            }
+        }
+        comments
+    }
+
+    /// Whether the diagnostics of the rule `code` on `line` (zero-based) are suppressed
+    pub fn is_suppressed(&self, code: &str, line: usize) -> bool {
+        self.file.as_ref().is_some_and(|x| suppresses(x, code))
+            || self.lines.get(&line).is_some_and(|x| suppresses(x, code))
+    }
+}
+
+/// The line suppressing `code` on the line after it, indented by `indentation`
+pub fn next_line_comment(indentation: &str, code: &str) -> String {
+    format!("{indentation}// {NEXT_LINE} {code}\n")
+}
+
+/// The line suppressing `code` in the whole file
+pub fn file_comment(code: &str) -> String {
+    format!("// {FILE} {code}\n")
+}
+
+/// A problem accepted by the baseline
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct Entry {
+    /// the path of the file relative to the workspace folder, with `/` separators
+    pub file: String,
+    pub rule: String,
+    pub message: String,
+}
+
+/// The problems accepted by the baseline of a workspace folder
+#[derive(Debug, Clone, Default)]
+pub struct Baseline {
+    entries: BTreeSet<Entry>,
+}
+
+impl Baseline {
+    /// Parses the content of a `.sqf-analyzer-baseline.json`
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let entries = serde_json::from_str::<Vec<Entry>>(content).map_err(|e| e.to_string())?;
+        Ok(Self {
+            entries: entries.into_iter().collect(),
+        })
+    }
+
+    /// Loads the baseline at the root of a workspace folder, if any
+    pub fn load(root: &Path) -> Option<Result<Self, String>> {
+        let content = std::fs::read_to_string(root.join(BASELINE_FILE_NAME)).ok()?;
+        Some(Self::parse(&content))
+    }
+
+    pub fn contains(&self, entry: &Entry) -> bool {
+        self.entries.contains(entry)
+    }
+
+    /// The content of the baseline with `entry` added, sorted
+    pub fn with(&self, entry: Entry) -> String {
+        let mut entries = self.entries.clone();
+        entries.insert(entry);
+        let entries = entries.into_iter().collect::<Vec<_>>();
+        let mut content = serde_json::to_string_pretty(&entries).unwrap_or_default();
+        content.push('\n');
+        content
+    }
+}
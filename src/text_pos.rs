@@ -0,0 +1,60 @@
+//! Conversions between the offsets used by the different layers:
+//! * the spans of `sqf` and the indices of [`Rope`] count chars;
+//! * Rust strings are indexed by bytes;
+//! * LSP positions count UTF-16 code units within a line.
+use ropey::Rope;
+use sqf::span::Span;
+use tower_lsp::lsp_types::{Position, Range};
+
+/// The char offset of the byte offset `byte` of `text`
+pub fn byte_to_char(text: &str, byte: usize) -> Option<usize> {
+    text.get(..byte).map(|x| x.chars().count())
+}
+
+/// The byte offset of the char offset `char` of `text`
+pub fn char_to_byte(text: &str, char: usize) -> Option<usize> {
+    text.char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(text.len()))
+        .nth(char)
+}
+
+/// The number of UTF-16 code units of `text`
+pub fn utf16_len(text: &str) -> usize {
+    text.chars().map(char::len_utf16).sum()
+}
+
+/// The position of the char offset `offset`
+pub fn position(offset: usize, rope: &Rope) -> Option<Position> {
+    let line = rope.try_char_to_line(offset).ok()?;
+    let first = rope.try_line_to_char(line).ok()?;
+    let column = rope.try_char_to_utf16_cu(offset).ok()? - rope.try_char_to_utf16_cu(first).ok()?;
+    Some(Position::new(line as u32, column as u32))
+}
+
+/// The char offset of `position`. Columns past the end of the line are clamped to it, before its
+/// line break (`\n`, `\r\n` or `\r`)
+pub fn offset(position: Position, rope: &Rope) -> Option<usize> {
+    let first = rope.try_line_to_char(position.line as usize).ok()?;
+    let line = rope.get_line(position.line as usize)?;
+    let chars = line.len_chars();
+    let last = |n: usize| chars.checked_sub(n).map(|i| line.char(i));
+    let line_break = match (last(2), last(1)) {
+        (Some('\r'), Some('\n')) => 2,
+        (_, Some('\n' | '\r')) => 1,
+        _ => 0,
+    };
+    let length = line.len_utf16_cu() - line_break;
+    let column = (position.character as usize).min(length);
+    Some(first + line.try_utf16_cu_to_char(column).ok()?)
+}
+
+/// The range of the span `(start, end)` (in chars)
+pub fn range((start, end): Span, rope: &Rope) -> Option<Range> {
+    Some(Range::new(position(start, rope)?, position(end, rope)?))
+}
+
+/// The number of UTF-16 code units of the `length` chars starting at the char offset `start`
+pub fn utf16_length(start: usize, length: usize, rope: &Rope) -> Option<usize> {
+    Some(rope.try_char_to_utf16_cu(start + length).ok()? - rope.try_char_to_utf16_cu(start).ok()?)
+}
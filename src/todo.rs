@@ -0,0 +1,87 @@
+use std::collections::VecDeque;
+
+use sqf::{error::Error, preprocessor::Ast, span::Span};
+
+use crate::text_pos;
+
+pub const DEFAULT_MARKERS: &[&str] = &["TODO", "FIXME", "HACK"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Todo {
+    pub marker: String,
+    pub text: String,
+    /// span of the marker and its text within the file
+    pub span: Span,
+}
+
+fn recurse(ast: &Ast, markers: &[String], container: &mut Vec<Todo>) {
+    match ast {
+        Ast::Ifdef(ifdef) | Ast::Ifndef(ifdef) => {
+            for token in ifdef.then.iter().chain(ifdef.else_.iter()) {
+                recurse(token, markers, container)
+            }
+        }
+        Ast::If(if_) => {
+            for token in if_.then.iter().chain(if_.else_.iter()) {
+                recurse(token, markers, container)
+            }
+        }
+        Ast::Comment(comment) => {
+            let mut start = comment.span.0;
+            for line in comment.inner.split('\n') {
+                if let Some(todo) = find_marker(line, start, markers) {
+                    container.push(todo);
+                }
+                start += line.chars().count() + 1; // +1 due to the \n
+            }
+        }
+        _ => {}
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// finds the first marker in a line of a comment starting at `start`.
+/// Markers are only matched as whole words (e.g. `TODOS` is not a `TODO`)
+fn find_marker(line: &str, start: usize, markers: &[String]) -> Option<Todo> {
+    markers
+        .iter()
+        .filter(|marker| !marker.is_empty())
+        .filter_map(|marker| {
+            line.match_indices(marker.as_str()).find(|(index, _)| {
+                let before = line[..*index].chars().next_back();
+                let after = line[index + marker.len()..].chars().next();
+                !before.map(is_word).unwrap_or(false) && !after.map(is_word).unwrap_or(false)
+            })
+        })
+        .min_by_key(|(index, _)| *index)
+        .map(|(index, marker)| {
+            let text = line[index + marker.len()..]
+                .trim_start_matches(|c: char| c == ':' || c.is_whitespace())
+                .trim_end_matches("*/")
+                .trim()
+                .to_string();
+            let end = line.trim_end_matches(['\r', '\n']).trim_end().len();
+            let end = end.max(index + marker.len());
+            let chars = |byte| text_pos::byte_to_char(line, byte).unwrap_or_default();
+            Todo {
+                marker: marker.to_string(),
+                text,
+                span: (start + chars(index), start + chars(end)),
+            }
+        })
+}
+
+/// Returns all comments containing one of the `markers` (e.g. `TODO`) in `text`.
+/// Comments in both branches of `#ifdef` are considered.
+pub fn collect(text: &str, markers: &[String]) -> Result<Vec<Todo>, Error> {
+    let ast: VecDeque<Ast> = sqf::preprocessor::parse(text)?;
+    let mut container = vec![];
+    for token in &ast {
+        recurse(token, markers, &mut container);
+    }
+    container.sort_by_key(|x| x.span.0);
+    Ok(container)
+}
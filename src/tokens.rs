@@ -0,0 +1,112 @@
+use std::collections::VecDeque;
+
+use sqf::{preprocessor::Ast, span::Spanned};
+
+fn recurse<'a>(ast: &Ast<'a>, container: &mut Vec<Spanned<&'a str>>) {
+    match ast {
+        Ast::Ifdef(ifdef) | Ast::Ifndef(ifdef) => {
+            for token in ifdef.then.iter().chain(ifdef.else_.iter()) {
+                recurse(token, container)
+            }
+        }
+        Ast::If(if_) => {
+            for token in if_.then.iter().chain(if_.else_.iter()) {
+                recurse(token, container)
+            }
+        }
+        Ast::Term(token) => container.push(*token),
+        _ => {}
+    }
+}
+
+/// Returns the terms of a file as written (i.e. without expanding macros), including both branches
+/// of `#ifdef`. Comments and preprocessor directives are excluded.
+pub fn terms<'a>(ast: &VecDeque<Ast<'a>>) -> Vec<Spanned<&'a str>> {
+    let mut container = vec![];
+    for token in ast {
+        recurse(token, &mut container);
+    }
+    container.sort_by_key(|x| x.span.0);
+    container
+}
+
+/// Returns the elements at the top level of the array starting at `terms[start]` (`[`), each as the
+/// range of its terms
+pub fn elements(terms: &[Spanned<&str>], start: usize) -> Vec<std::ops::Range<usize>> {
+    if terms.get(start).map(|x| x.inner) != Some("[") {
+        return vec![];
+    }
+    let mut elements = vec![];
+    let mut element_start = start + 1;
+    let mut depth = 0usize;
+    for (i, term) in terms.iter().enumerate().skip(start) {
+        match term.inner {
+            "[" | "{" | "(" => depth += 1,
+            "]" | "}" | ")" => {
+                depth -= 1;
+                if depth == 0 {
+                    if element_start < i {
+                        elements.push(element_start..i);
+                    }
+                    break;
+                }
+            }
+            "," if depth == 1 => {
+                elements.push(element_start..i);
+                element_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    elements
+}
+
+/// Returns the string literals that contain code: the argument of `compile`, the statements of
+/// `setTriggerStatements` and the script of `addAction`
+pub fn code_strings<'a>(terms: &[Spanned<&'a str>]) -> Vec<Spanned<&'a str>> {
+    let string = |range: std::ops::Range<usize>| {
+        (range.len() == 1 && is_string(terms[range.start].inner)).then_some(terms[range.start])
+    };
+    terms
+        .iter()
+        .enumerate()
+        .flat_map(|(i, term)| {
+            if term.inner.eq_ignore_ascii_case("compile") {
+                string(i + 1..(i + 2).min(terms.len()))
+                    .into_iter()
+                    .collect::<Vec<_>>()
+            } else if term.inner.eq_ignore_ascii_case("setTriggerStatements") {
+                elements(terms, i + 1)
+                    .into_iter()
+                    .filter_map(string)
+                    .collect()
+            } else if term.inner.eq_ignore_ascii_case("addAction") {
+                elements(terms, i + 1)
+                    .into_iter()
+                    .nth(1)
+                    .and_then(string)
+                    .into_iter()
+                    .collect()
+            } else {
+                vec![]
+            }
+        })
+        .collect()
+}
+
+/// whether the token is a string literal (`"a"` or `'a'`)
+pub fn is_string(token: &str) -> bool {
+    let bytes = token.as_bytes();
+    bytes.len() >= 2
+        && (bytes[0] == bytes[bytes.len() - 1])
+        && (bytes[0] == b'"' || bytes[0] == b'\'')
+}
+
+/// Returns the content of a string literal, or `None` if the token is not a string
+pub fn unquote(token: &str) -> Option<String> {
+    if !is_string(token) {
+        return None;
+    }
+    let quote = &token[..1];
+    Some(token[1..token.len() - 1].replace(&format!("{quote}{quote}"), quote))
+}